@@ -21,11 +21,15 @@ import {
     ModifierKey,
     simKeyUp,
     ShortcutManager,
+    WindowCondition,
 } from "@OverfloatAPI";
 import { useEffect } from "react";
 import useStateRef from "react-usestateref";
 import TradeDisplay from "../components/TradeDisplay";
 
+// Condition for the shortcuts to only be triggered while the game has focus.
+const GAME_FOCUSED: WindowCondition = { title: "^Path of Exile$" };
+
 type Trade = {
     theirName: string;
     yourItemQuantity: string;
@@ -99,10 +103,10 @@ const TradeWindow: React.FC = () => {
 
     useEffect(() => {
         // Add shortcuts.
-        ShortcutManager.addShortcut("select_next", "Select next trade", "Selects the trade below the one currently selected.", selectNextTrade);
-        ShortcutManager.addShortcut("select_previous", "Select previous trade", "Selects the trade above the one currently selected.", selectPreviousTrade);
-        ShortcutManager.addShortcut("invite_to_party", "Invite to party", "Invites the player to a party.", inviteToParty);
-        ShortcutManager.addShortcut("initiate_trade", "Initiate trade", "Initiates a trade with the player.", initiateTrade);
+        ShortcutManager.addShortcut("select_next", "Select next trade", "Selects the trade below the one currently selected.", selectNextTrade, undefined, GAME_FOCUSED);
+        ShortcutManager.addShortcut("select_previous", "Select previous trade", "Selects the trade above the one currently selected.", selectPreviousTrade, undefined, GAME_FOCUSED);
+        ShortcutManager.addShortcut("invite_to_party", "Invite to party", "Invites the player to a party.", inviteToParty, undefined, GAME_FOCUSED);
+        ShortcutManager.addShortcut("initiate_trade", "Initiate trade", "Initiates a trade with the player.", initiateTrade, undefined, GAME_FOCUSED);
 
         // Watch the client.txt file for changes if it was set.
         if (clientFilePath == null) return;
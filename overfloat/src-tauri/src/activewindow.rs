@@ -0,0 +1,296 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/activewindow.rs                    *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+// Struct for information about the currently focused window
+#[derive(Clone, Debug, Default)]
+pub struct ActiveWindow {
+    // Window classes, instance and class name of WM_CLASS on Linux, window class on Windows
+    pub wm_class: Vec<String>,
+    pub title: String,
+    // Names of the process owning the window, e.g. executable name with and without extension
+    pub process_name: Vec<String>,
+}
+
+/**
+ * @brief Function to get the name of the file at the end of a path
+ * @param path - path to the file
+ * @return Option<String>
+ */
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn file_name(path: &std::path::Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{file_name, ActiveWindow};
+    use crate::keygrab::register_display;
+    use lazy_static::lazy_static;
+    use std::ffi::{CStr, CString};
+    use std::os::raw::{c_int, c_long, c_uchar, c_ulong};
+    use std::sync::Mutex;
+    use x11::xlib;
+
+    /*
+    * Lazily initialized global connection to the X server used for querying the focused window, stored as an address
+    * It is opened on the first query and kept open, the lock serializes the queries using it
+    */
+    lazy_static! {
+        static ref QUERY_DISPLAY: Mutex<usize> = Mutex::new(0);
+    }
+
+    /**
+     * @brief Function to get the value of a window property
+     * @param display - connection to the X server
+     * @param window - window to get the property of
+     * @param property - name of the property
+     * @return Option<(c_int, Vec<c_ulong>, Vec<u8>)> - format of the property, its items
+     * when the format is 32, and its bytes when the format is 8
+     */
+    unsafe fn get_property(
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        property: &str,
+    ) -> Option<(c_int, Vec<c_ulong>, Vec<u8>)> {
+        let property_name = CString::new(property).ok()?;
+        let atom: xlib::Atom = xlib::XInternAtom(display, property_name.as_ptr(), xlib::False);
+
+        let mut actual_type: xlib::Atom = 0;
+        let mut actual_format: c_int = 0;
+        let mut item_count: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data: *mut c_uchar = std::ptr::null_mut();
+
+        let status = xlib::XGetWindowProperty(
+            display,
+            window,
+            atom,
+            0,
+            1024,
+            xlib::False,
+            xlib::AnyPropertyType as xlib::Atom,
+            &mut actual_type,
+            &mut actual_format,
+            &mut item_count,
+            &mut bytes_after,
+            &mut data,
+        );
+
+        if status != xlib::Success as c_int || data.is_null() {
+            return None;
+        }
+
+        // Items of format 32 are stored as longs, items of format 8 as bytes
+        let result = match actual_format {
+            32 => {
+                let items = std::slice::from_raw_parts(data as *const c_long, item_count as usize);
+                Some((32, items.iter().map(|item| *item as c_ulong).collect(), Vec::new()))
+            }
+            8 => {
+                let bytes = std::slice::from_raw_parts(data, item_count as usize);
+                Some((8, Vec::new(), bytes.to_vec()))
+            }
+            _ => None,
+        };
+
+        xlib::XFree(data as *mut _);
+        result
+    }
+
+    /**
+     * @brief Function to get the names of the process with given PID
+     * @param pid - process ID
+     * @return Vec<String> - executable name and command name of the process
+     */
+    fn process_names(pid: c_ulong) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+
+        if let Some(name) = std::fs::read_link(format!("/proc/{}/exe", pid))
+            .ok()
+            .and_then(|path| file_name(&path))
+        {
+            names.push(name);
+        }
+
+        if let Ok(comm) = std::fs::read_to_string(format!("/proc/{}/comm", pid)) {
+            names.push(comm.trim().to_string());
+        }
+
+        names
+    }
+
+    /**
+     * @brief Function to get information about the currently focused window
+     * @return Option<ActiveWindow> - None if the X server can't be queried or no window is focused
+     */
+    pub fn get_active_window() -> Option<ActiveWindow> {
+        let mut query_display = QUERY_DISPLAY.lock().unwrap();
+
+        unsafe {
+            // Open a connection to the X server on the first query
+            if *query_display == 0 {
+                let display = xlib::XOpenDisplay(std::ptr::null());
+                if display.is_null() {
+                    return None;
+                }
+
+                // The focused window can be destroyed while it is being queried, ignore the errors
+                register_display(display);
+                *query_display = display as usize;
+            }
+
+            query_window(*query_display as *mut xlib::Display)
+        }
+    }
+
+    /**
+     * @brief Function to query the X server for the focused window
+     * @param display - connection to the X server
+     * @return Option<ActiveWindow>
+     */
+    unsafe fn query_window(display: *mut xlib::Display) -> Option<ActiveWindow> {
+        let root: xlib::Window = xlib::XDefaultRootWindow(display);
+
+        // Get the focused window from the window manager
+        let window: xlib::Window = match get_property(display, root, "_NET_ACTIVE_WINDOW") {
+            Some((32, items, _)) => *items.first()?,
+            _ => return None,
+        };
+
+        if window == 0 {
+            return None;
+        }
+
+        let mut active_window: ActiveWindow = ActiveWindow::default();
+
+        // Get the instance and class name of the window
+        let mut class_hint = xlib::XClassHint {
+            res_name: std::ptr::null_mut(),
+            res_class: std::ptr::null_mut(),
+        };
+        if xlib::XGetClassHint(display, window, &mut class_hint) != 0 {
+            for name in [class_hint.res_name, class_hint.res_class] {
+                if !name.is_null() {
+                    active_window
+                        .wm_class
+                        .push(CStr::from_ptr(name).to_string_lossy().into_owned());
+                    xlib::XFree(name as *mut _);
+                }
+            }
+        }
+
+        // Get the title of the window, preferring the UTF-8 title
+        for property in ["_NET_WM_NAME", "WM_NAME"] {
+            if let Some((8, _, bytes)) = get_property(display, window, property) {
+                active_window.title = String::from_utf8_lossy(&bytes).into_owned();
+                break;
+            }
+        }
+
+        // Get the process owning the window
+        if let Some((32, items, _)) = get_property(display, window, "_NET_WM_PID") {
+            if let Some(pid) = items.first() {
+                active_window.process_name = process_names(*pid);
+            }
+        }
+
+        Some(active_window)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{file_name, ActiveWindow};
+    use winapi::um::{handleapi, processthreadsapi, winbase, winnt, winuser};
+
+    /**
+     * @brief Function to get the names of the process with given PID
+     * @param pid - process ID
+     * @return Vec<String> - executable name with and without the extension
+     */
+    fn process_names(pid: u32) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+
+        unsafe {
+            let process =
+                processthreadsapi::OpenProcess(winnt::PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return names;
+            }
+
+            let mut buffer: [u16; 1024] = [0; 1024];
+            let mut size: u32 = buffer.len() as u32;
+            if winbase::QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut size) != 0 {
+                let path = std::path::PathBuf::from(String::from_utf16_lossy(&buffer[..size as usize]));
+                if let Some(name) = file_name(&path) {
+                    names.push(name);
+                }
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().into_owned());
+                }
+            }
+
+            handleapi::CloseHandle(process);
+        }
+
+        names
+    }
+
+    /**
+     * @brief Function to get information about the currently focused window
+     * @return Option<ActiveWindow> - None if no window is focused
+     */
+    pub fn get_active_window() -> Option<ActiveWindow> {
+        unsafe {
+            let window = winuser::GetForegroundWindow();
+            if window.is_null() {
+                return None;
+            }
+
+            let mut active_window: ActiveWindow = ActiveWindow::default();
+            let mut buffer: [u16; 1024] = [0; 1024];
+
+            // Get the class of the window
+            let length = winuser::GetClassNameW(window, buffer.as_mut_ptr(), buffer.len() as i32);
+            if length > 0 {
+                active_window
+                    .wm_class
+                    .push(String::from_utf16_lossy(&buffer[..length as usize]));
+            }
+
+            // Get the title of the window
+            let length = winuser::GetWindowTextW(window, buffer.as_mut_ptr(), buffer.len() as i32);
+            if length > 0 {
+                active_window.title = String::from_utf16_lossy(&buffer[..length as usize]);
+            }
+
+            // Get the process owning the window
+            let mut pid: u32 = 0;
+            winuser::GetWindowThreadProcessId(window, &mut pid);
+            if pid != 0 {
+                active_window.process_name = process_names(pid);
+            }
+
+            Some(active_window)
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+mod platform {
+    use super::ActiveWindow;
+
+    /**
+     * @brief Function to get information about the currently focused window
+     * @return Option<ActiveWindow> - always None, querying is not supported on this platform
+     */
+    pub fn get_active_window() -> Option<ActiveWindow> {
+        None
+    }
+}
+
+pub use platform::get_active_window;
@@ -0,0 +1,350 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/appactions.rs                      *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+use tauri::Manager;
+
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{mpsc, Mutex};
+
+use crate::shortcuts::{set_app_action_shortcuts, validate_keybind};
+
+/*
+* Lazily initialized global list of the key combinations bound to app-level actions
+* This has to be global because it needs to be accessible from tauri commands and the keyboard listener
+*/
+lazy_static! {
+    static ref APP_ACTIONS: Mutex<Vec<AppActionBinding>> = Mutex::new(Vec::new());
+    static ref REOPENED_MODULES: Mutex<Option<Vec<ReopenedModule>>> = Mutex::new(None);
+}
+
+/*
+* Get the global list of app-level action bindings
+*/
+fn get_app_actions() -> &'static Mutex<Vec<AppActionBinding>> {
+    &APP_ACTIONS
+}
+
+/*
+* Get the global list of the modules to reopen after the Overfloat window is reloaded
+*/
+fn get_reopened_modules() -> &'static Mutex<Option<Vec<ReopenedModule>>> {
+    &REOPENED_MODULES
+}
+
+// Key of the app-level action bindings in the config file
+const CONFIG_KEY: &str = "appActions";
+
+// Label of the Overfloat window and prefix of the labels of module windows
+const OVERFLOAT_WINDOW_LABEL: &str = "Overfloat";
+const MODULE_WINDOW_PREFIX: &str = "module/";
+
+// Maximum time in milliseconds to wait for each module window to be destroyed before reloading
+const MODULE_CLOSE_TIMEOUT_MS: u64 = 1000;
+
+// App-level actions handled by the backend without the frontend
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum AppAction {
+    ToggleOverlay,
+    HideModules,
+    ReloadMainWindow,
+    Quit,
+}
+
+// Struct for a key combination bound to an app-level action
+#[derive(Clone, Debug, serde::Deserialize)]
+struct AppActionBinding {
+    keybind: String,
+    #[serde(flatten)]
+    action: AppAction,
+}
+
+// Struct for a window of a module open when the Overfloat window was reloaded, serialized like the windows of profiles
+// Shortcuts and transparency are only known to the frontend, it takes them from the active profile
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReopenedWindow {
+    title: String,
+    is_visible: bool,
+    is_transparent: bool,
+    x: f64,
+    y: f64,
+    height: f64,
+    width: f64,
+    shortcuts: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subwindow_name: Option<String>,
+}
+
+// Struct for a module open when the Overfloat window was reloaded, serialized like the modules of profiles
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReopenedModule {
+    module_name: String,
+    main_window: ReopenedWindow,
+    subwindows: Vec<ReopenedWindow>,
+}
+
+/**
+ * @brief Function to get the labels of all module windows
+ * @param handle - tauri::AppHandle
+ * @return Vec<String>
+ */
+fn module_window_labels(handle: &tauri::AppHandle) -> Vec<String> {
+    handle
+        .windows()
+        .into_keys()
+        .filter(|label| label.starts_with(MODULE_WINDOW_PREFIX))
+        .collect()
+}
+
+/**
+ * @brief Function to serialize a module window to reopen it after the Overfloat window is reloaded
+ * @param window - the module window
+ * @param subwindow_name - name of the subwindow, None for the main window of the module
+ * @return ReopenedWindow
+ * @note The position and size are logical like in profiles, the parameters of subwindows are taken from their URL
+ */
+fn reopened_window(window: &tauri::Window, subwindow_name: Option<String>) -> ReopenedWindow {
+    let scale_factor: f64 = window.scale_factor().unwrap_or(1.0);
+    let position: Option<tauri::LogicalPosition<f64>> =
+        window.outer_position().ok().map(|position| position.to_logical(scale_factor));
+    let size: Option<tauri::LogicalSize<f64>> = window.outer_size().ok().map(|size| size.to_logical(scale_factor));
+
+    let params: Option<HashMap<String, String>> = subwindow_name
+        .as_ref()
+        .map(|_| window.url().query_pairs().into_owned().collect());
+
+    ReopenedWindow {
+        title: window.title().unwrap_or_default(),
+        is_visible: window.is_visible().unwrap_or(true),
+        is_transparent: false,
+        x: position.map(|position| position.x).unwrap_or(0.0),
+        y: position.map(|position| position.y).unwrap_or(0.0),
+        height: size.map(|size| size.height).unwrap_or(300.0),
+        width: size.map(|size| size.width).unwrap_or(500.0),
+        shortcuts: Vec::new(),
+        params: params,
+        subwindow_name: subwindow_name,
+    }
+}
+
+/**
+ * @brief Function to serialize the open modules to reopen them after the Overfloat window is reloaded
+ * @param handle - tauri::AppHandle
+ * @return Vec<ReopenedModule>
+ * @note Main windows are labeled module/<module>, subwindows module/<module>/<subwindow>/<id>
+ */
+fn reopened_modules(handle: &tauri::AppHandle) -> Vec<ReopenedModule> {
+    let mut modules: Vec<ReopenedModule> = Vec::new();
+    let mut subwindows: Vec<(String, ReopenedWindow)> = Vec::new();
+
+    for (label, window) in handle.windows() {
+        let parts: Vec<&str> = match label.strip_prefix(MODULE_WINDOW_PREFIX) {
+            Some(rest) => rest.split('/').collect(),
+            None => continue,
+        };
+
+        match parts.as_slice() {
+            [module_name] => modules.push(ReopenedModule {
+                module_name: module_name.to_string(),
+                main_window: reopened_window(&window, None),
+                subwindows: Vec::new(),
+            }),
+            [module_name, subwindow_name, _] => subwindows.push((
+                module_name.to_string(),
+                reopened_window(&window, Some(subwindow_name.to_string())),
+            )),
+            _ => {}
+        }
+    }
+
+    // Subwindows are reopened by their modules, subwindows of closed modules are dropped
+    for (module_name, subwindow) in subwindows {
+        if let Some(module) = modules.iter_mut().find(|module| module.module_name == module_name) {
+            module.subwindows.push(subwindow);
+        }
+    }
+
+    modules
+}
+
+/**
+ * @brief Function to reload the webview of the Overfloat window natively
+ * @param handle - tauri::AppHandle
+ * @note The webview is reloaded by the platform instead of JavaScript, so a frozen frontend is reloaded as well.
+ * Module windows are closed first, the frontend reopens the modules that were open after the reload
+ */
+fn reload_main_window(handle: &tauri::AppHandle) {
+    *get_reopened_modules().lock().unwrap() = Some(reopened_modules(handle));
+
+    // Close the module windows and wait until they are destroyed, the frontend can't reopen windows with the same labels otherwise
+    let (sender, receiver) = mpsc::channel::<()>();
+    let mut closing: usize = 0;
+    for label in module_window_labels(handle) {
+        if let Some(window) = handle.get_window(&label) {
+            let sender: mpsc::Sender<()> = sender.clone();
+            window.on_window_event(move |event| {
+                if let tauri::WindowEvent::Destroyed = event {
+                    let _ = sender.send(());
+                }
+            });
+
+            if window.close().is_ok() {
+                closing += 1;
+            }
+        }
+    }
+
+    for _ in 0..closing {
+        if receiver
+            .recv_timeout(std::time::Duration::from_millis(MODULE_CLOSE_TIMEOUT_MS))
+            .is_err()
+        {
+            break;
+        }
+    }
+
+    let window: tauri::Window = match handle.get_window(OVERFLOAT_WINDOW_LABEL) {
+        Some(window) => window,
+        None => return,
+    };
+
+    let result = window.with_webview(|webview| {
+        #[cfg(target_os = "linux")]
+        {
+            use webkit2gtk::WebViewExt;
+            webview.inner().reload();
+        }
+
+        #[cfg(windows)]
+        unsafe {
+            if let Ok(core_webview) = webview.controller().CoreWebView2() {
+                let _ = core_webview.Reload();
+            }
+        }
+
+        #[cfg(not(any(target_os = "linux", windows)))]
+        let _ = webview;
+    });
+
+    // Fall back to reloading from JavaScript if the webview can't be accessed
+    if result.is_err() {
+        let _ = window.eval("window.location.reload()");
+    }
+}
+
+// Implementation of AppAction
+impl AppAction {
+    /**
+     * @brief Function to get the name of the action as declared in the config file
+     * @return &str
+     */
+    fn name(&self) -> &str {
+        match self {
+            AppAction::ToggleOverlay => "toggle_overlay",
+            AppAction::HideModules => "hide_modules",
+            AppAction::ReloadMainWindow => "reload_main_window",
+            AppAction::Quit => "quit",
+        }
+    }
+
+    /**
+     * @brief Function to perform the action
+     * @param handle - tauri::AppHandle
+     */
+    fn perform(&self, handle: &tauri::AppHandle) {
+        match self {
+            AppAction::ToggleOverlay => crate::tray_toggle_window(
+                handle.clone(),
+                OVERFLOAT_WINDOW_LABEL,
+                OVERFLOAT_WINDOW_LABEL,
+                OVERFLOAT_WINDOW_LABEL,
+            ),
+            // Module windows are hidden directly, the frontend is notified to update their state
+            AppAction::HideModules => {
+                for label in module_window_labels(handle) {
+                    if let Some(window) = handle.get_window(&label) {
+                        let _ = window.hide();
+                    }
+                }
+                let _ = handle.emit_to(OVERFLOAT_WINDOW_LABEL, "Overfloat://ModulesHidden", ());
+            }
+            AppAction::ReloadMainWindow => reload_main_window(handle),
+            AppAction::Quit => {
+                crate::inputsim::release_all_inputs();
+                handle.exit(0);
+            }
+        }
+    }
+}
+
+/**
+ * @brief Function to set the app-level action bindings from the content of the config file
+ * @param config_json - content of the config file
+ * @note Invalid bindings are skipped, the config file is shared with the frontend
+ * which stores its settings under other keys. The key combinations are registered as shortcuts,
+ * so they are matched, consumed and checked for conflicts like them
+ */
+pub fn load_app_actions(config_json: &str) {
+    let config: serde_json::Value = match serde_json::from_str(config_json) {
+        Ok(config) => config,
+        Err(_) => return,
+    };
+
+    // Parse the bindings one by one so an invalid binding doesn't disable the others
+    let mut bindings: Vec<AppActionBinding> = Vec::new();
+    if let Some(values) = config.get(CONFIG_KEY).and_then(|value| value.as_array()) {
+        for value in values {
+            match serde_json::from_value::<AppActionBinding>(value.clone()) {
+                Ok(binding) => match validate_keybind(&binding.keybind) {
+                    Ok(()) => bindings.push(binding),
+                    Err(message) => println!("Invalid app action {}: {}", value, message),
+                },
+                Err(error) => println!("Invalid app action {}: {}", value, error),
+            }
+        }
+    }
+
+    let shortcuts: Vec<(usize, String, String)> = bindings
+        .iter()
+        .enumerate()
+        .map(|(index, binding)| (index, binding.action.name().to_string(), binding.keybind.clone()))
+        .collect();
+
+    *get_app_actions().lock().unwrap() = bindings;
+    set_app_action_shortcuts(shortcuts);
+}
+
+/**
+ * @brief Function to take the modules to reopen after the Overfloat window was reloaded
+ * @return Option<Vec<ReopenedModule>> - None if the Overfloat window wasn't reloaded by the app-level action
+ * @note Exposes the operation to tauri commands, the modules are only returned once
+ */
+pub fn take_reopened_modules() -> Option<Vec<ReopenedModule>> {
+    get_reopened_modules().lock().unwrap().take()
+}
+
+/**
+ * @brief Function to perform an app-level action whose key combination was pressed
+ * @param handle - tauri::AppHandle
+ * @param index - index of the binding of the action
+ * @note Exposes the operation to the shortcut registry
+ */
+pub fn perform_app_action(handle: &tauri::AppHandle, index: usize) {
+    let action: AppAction = match get_app_actions().lock().unwrap().get(index) {
+        Some(binding) => binding.action.clone(),
+        None => return,
+    };
+
+    // Perform the action on a separate thread, window operations wait for the main thread
+    // which may be waiting for the keyboard listener
+    let handle: tauri::AppHandle = handle.clone();
+    std::thread::spawn(move || action.perform(&handle));
+}
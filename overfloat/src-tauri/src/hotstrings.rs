@@ -0,0 +1,199 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/hotstrings.rs                      *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+use rdev;
+
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+
+use crate::inputsim::{start_expansion, SimulationResult};
+use crate::keybinds::is_modifier_key;
+
+/*
+* Lazily initialized global instance of HotstringEngine holding the configured hotstrings
+* This has to be global because it needs to be accessible from tauri commands and the keyboard listener
+*/
+lazy_static! {
+    static ref HOTSTRING_ENGINE: Mutex<HotstringEngine> = Mutex::new(HotstringEngine::new());
+}
+
+/*
+* Get the global instance of HotstringEngine
+*/
+fn get_hotstring_engine() -> &'static Mutex<HotstringEngine> {
+    &HOTSTRING_ENGINE
+}
+
+// Struct for a hotstring, the abbreviation is replaced by the replacement text when typed
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct Hotstring {
+    abbreviation: String,
+    replacement: String,
+    #[serde(default)]
+    paste: bool,
+}
+
+// Struct for returning results from hotstring operations
+#[derive(Clone, serde::Serialize)]
+pub struct HotstringResult {
+    successful: bool,
+    message: String,
+}
+
+// Struct for the hotstring state
+struct HotstringEngine {
+    hotstrings: Vec<Hotstring>,
+    typed: Vec<char>,
+    max_length: usize,
+}
+
+// Implementation of HotstringEngine
+impl HotstringEngine {
+    fn new() -> HotstringEngine {
+        HotstringEngine {
+            hotstrings: Vec::new(),
+            typed: Vec::new(),
+            max_length: 0,
+        }
+    }
+
+    /**
+     * @brief Function to set the hotstrings
+     * @param hotstrings - hotstrings, empty to disable text expansion
+     * @return Result<(), String> - error message if a hotstring is invalid, the previous hotstrings are kept in that case
+     */
+    fn set_hotstrings(&mut self, hotstrings: Vec<Hotstring>) -> Result<(), String> {
+        for hotstring in &hotstrings {
+            if hotstring.abbreviation.is_empty() {
+                return Err(format!("Empty abbreviation of: {}", hotstring.replacement));
+            }
+            if hotstring.abbreviation.chars().any(|character| character.is_control()) {
+                return Err(format!("Invalid abbreviation: {}", hotstring.abbreviation));
+            }
+        }
+
+        self.max_length = hotstrings
+            .iter()
+            .map(|hotstring| hotstring.abbreviation.chars().count())
+            .max()
+            .unwrap_or(0);
+        self.hotstrings = hotstrings;
+        self.typed.clear();
+
+        Ok(())
+    }
+
+    /**
+     * @brief Function to add a typed character and replace the abbreviation it completes
+     * @param handle - tauri::AppHandle
+     * @param character - the typed character
+     * @note The replacement is queued as an input simulation, so it can be cancelled like other simulations.
+     * The abbreviation is erased by backspaces, the replacement is typed or pasted from the clipboard
+     */
+    fn type_character(&mut self, handle: &tauri::AppHandle, character: char) {
+        self.typed.push(character);
+
+        // Only the end of the typed text can complete an abbreviation
+        if self.typed.len() > self.max_length {
+            let excess: usize = self.typed.len() - self.max_length;
+            self.typed.drain(..excess);
+        }
+
+        // The longest completed abbreviation is the most specific one (e.g. ;ty instead of ty),
+        // the first declared one wins among abbreviations of the same length
+        let typed: String = self.typed.iter().collect();
+        let hotstring: &Hotstring = match self
+            .hotstrings
+            .iter()
+            .rev()
+            .filter(|hotstring| typed.ends_with(&hotstring.abbreviation))
+            .max_by_key(|hotstring| hotstring.abbreviation.chars().count())
+        {
+            Some(hotstring) => hotstring,
+            None => return,
+        };
+
+        let result: SimulationResult = start_expansion(
+            handle.clone(),
+            hotstring.abbreviation.chars().count(),
+            hotstring.replacement.clone(),
+            hotstring.paste,
+        );
+        if let Some(message) = result.error_message() {
+            println!("Couldn't expand {}: {}", hotstring.abbreviation, message);
+        }
+        self.typed.clear();
+    }
+
+    /**
+     * @brief Function to handle an input event
+     * @param handle - tauri::AppHandle
+     * @param event - rdev::Event
+     * @param command_modifier_held - whether Ctrl, Alt or Meta is held, the key is a command instead of text
+     */
+    fn handle_event(&mut self, handle: &tauri::AppHandle, event: &rdev::Event, command_modifier_held: bool) {
+        if self.hotstrings.is_empty() {
+            return;
+        }
+
+        match event.event_type {
+            rdev::EventType::KeyPress(rdev::Key::Backspace) => {
+                self.typed.pop();
+            }
+            // Modifiers change the typed character but don't interrupt the text
+            rdev::EventType::KeyPress(key) if is_modifier_key(key) => {}
+            rdev::EventType::KeyPress(_) => {
+                // Get the typed character, keys that don't type text (e.g. arrows, Enter) interrupt it
+                let character: Option<char> = match &event.name {
+                    Some(name) if !command_modifier_held && name.chars().count() == 1 => {
+                        name.chars().next().filter(|character| !character.is_control())
+                    }
+                    _ => None,
+                };
+
+                match character {
+                    Some(character) => self.type_character(handle, character),
+                    None => self.typed.clear(),
+                }
+            }
+            // Clicking can move the text cursor
+            rdev::EventType::ButtonPress(_) => self.typed.clear(),
+            _ => {}
+        }
+    }
+}
+
+/**
+ * @brief Function to set the hotstrings
+ * @param hotstrings - hotstrings, empty to disable text expansion
+ * @return HotstringResult
+ * @note Exposes the operation to tauri commands
+ */
+pub fn set_hotstrings(hotstrings: Vec<Hotstring>) -> HotstringResult {
+    let mut hotstring_engine = get_hotstring_engine().lock().unwrap();
+    match hotstring_engine.set_hotstrings(hotstrings) {
+        Ok(()) => HotstringResult {
+            successful: true,
+            message: String::new(),
+        },
+        Err(message) => HotstringResult {
+            successful: false,
+            message: message,
+        },
+    }
+}
+
+/**
+ * @brief Function to watch the typed text for abbreviations
+ * @param handle - tauri::AppHandle
+ * @param event - rdev::Event
+ * @param command_modifier_held - whether Ctrl, Alt or Meta is held
+ * @note Exposes the operation to the keyboard listener
+ */
+pub fn handle_event(handle: &tauri::AppHandle, event: &rdev::Event, command_modifier_held: bool) {
+    let mut hotstring_engine = get_hotstring_engine().lock().unwrap();
+    hotstring_engine.handle_event(handle, event, command_modifier_held);
+}
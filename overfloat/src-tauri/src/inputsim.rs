@@ -6,248 +6,1585 @@
 
 use rdev;
 use std::{thread, time};
+use tauri::{ClipboardManager, Manager};
+
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::keybinds::any_key_pressed;
+use crate::keys::{key_to_code, string_to_button, string_to_key};
+#[cfg(any(target_os = "linux", windows))]
+use crate::keystate::query_lock_states;
+use crate::remap::release_remapped_keys;
+use crate::script::{Script, ScriptError};
+
+/*
+* Lazily initialized global instance of SimulationJobs holding the running simulations
+* This has to be global because it needs to be accessible from tauri commands and the simulation threads
+*/
+lazy_static! {
+    static ref SIMULATION_JOBS: Mutex<SimulationJobs> = Mutex::new(SimulationJobs::new());
+}
+
+/*
+* Get the global instance of SimulationJobs
+*/
+fn get_simulation_jobs() -> &'static Mutex<SimulationJobs> {
+    &SIMULATION_JOBS
+}
+
+/*
+* Lazily initialized global list of the simulated events the keyboard listener is yet to observe, with the time they were simulated
+* This has to be global because it needs to be accessible from the simulation threads and the keyboard listener
+*/
+lazy_static! {
+    static ref SIMULATED_EVENTS: Mutex<Vec<(rdev::EventType, time::Instant)>> = Mutex::new(Vec::new());
+}
+
+/*
+* Get the global list of simulated events
+*/
+fn get_simulated_events() -> &'static Mutex<Vec<(rdev::EventType, time::Instant)>> {
+    &SIMULATED_EVENTS
+}
+
+// Signalled when a simulation is queued, the simulation worker waits for it
+static JOB_QUEUED: Condvar = Condvar::new();
+
+// Time in milliseconds after which a simulated event the keyboard listener didn't observe is forgotten
+pub const SIMULATED_EVENT_TIMEOUT_MS: u64 = 1000;
+
+// Label of the window notified about the simulations of hotstring expansions
+const OVERFLOAT_WINDOW_LABEL: &str = "Overfloat";
+
+// Maximum time in milliseconds to wait for the user to release the keys before expanding a hotstring
+const EXPANSION_RELEASE_TIMEOUT_MS: u64 = 2000;
+
+// Time in milliseconds to wait after pasting before the previous clipboard content is restored
+const PASTE_RESTORE_DELAY_MS: u64 = 200;
+
+// Interval in milliseconds between checks for cancellation while waiting
+const CANCEL_CHECK_INTERVAL_MS: u64 = 10;
+
+// Label prefix of module windows, the label of a module window is "module/<module name>[/<subwindow>]"
+const MODULE_WINDOW_PREFIX: &str = "module/";
+
+// Default delay in milliseconds after each simulated event, for the system to process it
+pub const DEFAULT_EVENT_DELAY_MS: u64 = 1;
+
+// Maximum delay in milliseconds of a single delay step or between events
+pub const MAX_DELAY_MS: u64 = 60000;
+
+// Maximum number of synchronizations with the X server when waiting for the notification about a changed keyboard mapping
+#[cfg(target_os = "linux")]
+const MAPPING_NOTIFY_ATTEMPTS: u32 = 10;
+
+// Simulation step, tagged by its type (e.g. {"type": "keyDown", "key": "A"})
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SimulationStep {
+    KeyDown { key: String },
+    KeyUp { key: String },
+    KeyPress { key: String },
+    MouseDown { button: String },
+    MouseUp { button: String },
+    MouseClick { button: String },
+    MouseScroll { delta_x: i64, delta_y: i64 },
+    MouseMove { x: f64, y: f64 },
+    Delay { ms: u64 },
+    TypeText { text: String },
+}
+
+// Action of a validated simulation sequence
+#[derive(Clone, Debug)]
+enum SimulationAction {
+    Event(rdev::EventType),
+    Delay(u64),
+    TypeText(String),
+    Script(Script),
+    // Waits until the user releases all keys, at most the given time in milliseconds
+    WaitForRelease(u64),
+    // Pastes the text through the clipboard, the previous clipboard content is restored afterwards
+    Paste(tauri::AppHandle, String),
+}
+
+// Struct for an error of a single simulation step
+#[derive(Clone, serde::Serialize)]
+pub struct SimulationStepError {
+    step: usize,
+    message: String,
+}
+
+// Struct for returning results from input simulation
+#[derive(Clone, serde::Serialize)]
+pub struct SimulationResult {
+    successful: bool,
+    message: String,
+    errors: Vec<SimulationStepError>,
+    job_id: Option<u64>,
+}
+
+// Implementation of SimulationResult
+impl SimulationResult {
+    /**
+     * @brief Function to create a result of a simulation that wasn't queued
+     * @param message - reason why the simulation wasn't queued
+     * @return SimulationResult
+     */
+    pub fn failed(message: String) -> SimulationResult {
+        SimulationResult {
+            successful: false,
+            message: message,
+            errors: Vec::new(),
+            job_id: None,
+        }
+    }
+
+    /**
+     * @brief Function to get the reason why the simulation wasn't queued
+     * @return Option<&str> - None if the simulation was queued
+     */
+    pub fn error_message(&self) -> Option<&str> {
+        if self.successful {
+            None
+        } else {
+            Some(&self.message)
+        }
+    }
+
+    /**
+     * @brief Function to get the ID of the queued simulation
+     * @return Option<u64> - None if the simulation wasn't queued
+     */
+    pub fn job_id(&self) -> Option<u64> {
+        self.job_id
+    }
+}
+
+// Struct for the payload of the event notifying about the progress of a simulation
+#[derive(Clone, serde::Serialize)]
+struct PayloadSimulationProgress {
+    job_id: u64,
+    completed_steps: usize,
+    total_steps: usize,
+}
+
+// Struct for the payload of the event notifying about the start of a queued simulation
+#[derive(Clone, serde::Serialize)]
+struct PayloadSimulationStarted {
+    job_id: u64,
+}
+
+// Struct for the payload of the event notifying about the end of a simulation
+#[derive(Clone, serde::Serialize)]
+struct PayloadSimulationFinished {
+    job_id: u64,
+    cancelled: bool,
+    error: Option<ScriptError>,
+}
+
+// Struct for the keys and mouse buttons held by a simulation
+struct HeldInputs {
+    keys: HashSet<rdev::Key>,
+    buttons: HashSet<rdev::Button>,
+}
+
+// Struct for a validated sequence of simulation steps
+struct SimulationSequence {
+    steps: Vec<Vec<SimulationAction>>,
+    delay_ms: u64,
+}
+
+// Policy for starting a simulation while another simulation of the same module is queued or running
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SimulationPolicy {
+    Queue,
+    Reject,
+    Replace,
+}
+
+// Struct for a queued or running simulation
+struct SimulationJob {
+    handle: tauri::AppHandle,
+    window_label: String,
+    module: String,
+    priority: i32,
+    cancelled: Arc<AtomicBool>,
+    // Taken by the simulation worker when the simulation starts
+    sequence: Option<SimulationSequence>,
+}
+
+// Struct for the queued and running simulations, they are simulated one at a time
+struct SimulationJobs {
+    jobs: HashMap<u64, SimulationJob>,
+    policies: HashMap<String, SimulationPolicy>,
+    last_job_id: u64,
+    // Keys and buttons held by the simulations of each module, including finished simulations
+    held: HashMap<String, HeldInputs>,
+}
+
+/**
+ * @brief Function to get the module a window belongs to
+ * @param window_label - label of the window
+ * @return String - name of the module, the label itself for windows that don't belong to a module
+ */
+fn window_module(window_label: &str) -> String {
+    match window_label.strip_prefix(MODULE_WINDOW_PREFIX) {
+        Some(rest) => rest.split('/').next().unwrap_or(rest).to_string(),
+        None => window_label.to_string(),
+    }
+}
+
+/**
+ * @brief Function to notify the window that started a simulation about its end
+ * @param handle - tauri::AppHandle
+ * @param window_label - label of the window that started the simulation
+ * @param job_id - ID of the simulation
+ * @param cancelled - whether the simulation was cancelled
+ * @param error - error of the script that stopped the simulation
+ */
+fn emit_finished(handle: &tauri::AppHandle, window_label: &str, job_id: u64, cancelled: bool, error: Option<ScriptError>) {
+    let _ = handle.emit_to(
+        window_label,
+        "Overfloat://SimulationFinished",
+        PayloadSimulationFinished {
+            job_id: job_id,
+            cancelled: cancelled,
+            error: error,
+        },
+    );
+}
+
+/**
+ * @brief Function to convert the name of a mouse button to rdev::Button
+ * @param button - name of the mouse button
+ * @return Result<rdev::Button, String> - error message if the button is unknown
+ */
+fn parse_button(button: &str) -> Result<rdev::Button, String> {
+    string_to_button(button).map_err(|_| format!("Unknown mouse button: {}", button))
+}
+
+/**
+ * @brief Function to convert the name of a key to rdev::Key
+ * @param key - name of the key
+ * @return Result<rdev::Key, String> - error message if the key is unknown
+ */
+fn string_to_simulated_key(key: &str) -> Result<rdev::Key, String> {
+    string_to_key(key).map_err(|_| format!("Unknown key: {}", key))
+}
+
+// Implementation of SimulationStep
+impl SimulationStep {
+    /**
+     * @brief Function to convert the step to the actions it consists of
+     * @return Result<Vec<SimulationAction>, String> - error message if the step is invalid
+     */
+    fn to_actions(&self) -> Result<Vec<SimulationAction>, String> {
+        let events: Vec<rdev::EventType> = match self {
+            SimulationStep::KeyDown { key } => {
+                vec![rdev::EventType::KeyPress(string_to_simulated_key(key)?)]
+            }
+            SimulationStep::KeyUp { key } => {
+                vec![rdev::EventType::KeyRelease(string_to_simulated_key(key)?)]
+            }
+            SimulationStep::KeyPress { key } => {
+                let key: rdev::Key = string_to_simulated_key(key)?;
+                vec![rdev::EventType::KeyPress(key), rdev::EventType::KeyRelease(key)]
+            }
+            SimulationStep::MouseDown { button } => {
+                vec![rdev::EventType::ButtonPress(parse_button(button)?)]
+            }
+            SimulationStep::MouseUp { button } => {
+                vec![rdev::EventType::ButtonRelease(parse_button(button)?)]
+            }
+            SimulationStep::MouseClick { button } => {
+                let button: rdev::Button = parse_button(button)?;
+                vec![rdev::EventType::ButtonPress(button), rdev::EventType::ButtonRelease(button)]
+            }
+            SimulationStep::MouseScroll { delta_x, delta_y } => vec![rdev::EventType::Wheel {
+                delta_x: *delta_x,
+                delta_y: *delta_y,
+            }],
+            SimulationStep::MouseMove { x, y } => {
+                if !x.is_finite() || !y.is_finite() {
+                    return Err(format!("Invalid coordinates: {}, {}", x, y));
+                }
+                vec![rdev::EventType::MouseMove { x: *x, y: *y }]
+            }
+            SimulationStep::Delay { ms } => {
+                if *ms > MAX_DELAY_MS {
+                    return Err(format!("Delay too long: {} ms, maximum is {} ms", ms, MAX_DELAY_MS));
+                }
+                return Ok(vec![SimulationAction::Delay(*ms)]);
+            }
+            SimulationStep::TypeText { text } => {
+                // Control characters other than newlines and tabs can't be typed
+                if let Some(character) = text
+                    .chars()
+                    .find(|character| character.is_control() && *character != '\n' && *character != '\t')
+                {
+                    return Err(format!("Character can't be typed: {:?}", character));
+                }
+                return Ok(vec![SimulationAction::TypeText(text.clone())]);
+            }
+        };
+
+        Ok(events.into_iter().map(SimulationAction::Event).collect())
+    }
+}
+
+/**
+ * @brief Function to validate a sequence of simulation steps and convert it to actions
+ * @param steps - simulation steps in their JSON representation
+ * @return Result<Vec<Vec<SimulationAction>>, Vec<SimulationStepError>> - actions of each step, errors of all invalid steps
+ * @note Steps are parsed one by one, so an invalid step doesn't hide errors of the following steps
+ */
+fn compile_steps(steps: &[serde_json::Value]) -> Result<Vec<Vec<SimulationAction>>, Vec<SimulationStepError>> {
+    let mut actions: Vec<Vec<SimulationAction>> = Vec::new();
+    let mut errors: Vec<SimulationStepError> = Vec::new();
+
+    for (index, value) in steps.iter().enumerate() {
+        let result = serde_json::from_value::<SimulationStep>(value.clone())
+            .map_err(|error| format!("Invalid step: {}", error))
+            .and_then(|step| step.to_actions());
+
+        match result {
+            Ok(step_actions) => actions.push(step_actions),
+            Err(message) => errors.push(SimulationStepError {
+                step: index,
+                message: message,
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(actions)
+    } else {
+        Err(errors)
+    }
+}
+
+/**
+ * @brief Function to validate a single simulation step without simulating it
+ * @param step - the step
+ * @return Result<(), String> - error message if the step is invalid
+ */
+pub fn validate_step(step: &SimulationStep) -> Result<(), String> {
+    step.to_actions().map(|_| ())
+}
+
+/**
+ * @brief Function to validate a sequence of simulation steps without simulating it
+ * @param steps - simulation steps in their JSON representation
+ * @return Result<(), String> - description of the errors of all invalid steps
+ */
+pub fn validate_steps(steps: &[serde_json::Value]) -> Result<(), String> {
+    compile_steps(steps).map(|_| ()).map_err(|errors| {
+        errors
+            .iter()
+            .map(|error| format!("step {}: {}", error.step, error.message))
+            .collect::<Vec<String>>()
+            .join(", ")
+    })
+}
+
+/**
+ * @brief Function to wait unless cancelled
+ * @param ms - time to wait in milliseconds
+ * @param cancelled - cancellation flag, checked periodically
+ * @return bool - false if cancelled
+ */
+fn wait(ms: u64, cancelled: &AtomicBool) -> bool {
+    let end: time::Instant = time::Instant::now() + time::Duration::from_millis(ms);
+    loop {
+        if cancelled.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let now: time::Instant = time::Instant::now();
+        if now >= end {
+            return true;
+        }
+        thread::sleep((end - now).min(time::Duration::from_millis(CANCEL_CHECK_INTERVAL_MS)));
+    }
+}
+
+// Implementation of HeldInputs
+impl HeldInputs {
+    fn new() -> HeldInputs {
+        HeldInputs {
+            keys: HashSet::new(),
+            buttons: HashSet::new(),
+        }
+    }
+
+    /**
+     * @brief Function to update the held keys and buttons by a simulated event
+     * @param event_type - the simulated event
+     */
+    fn record(&mut self, event_type: &rdev::EventType) {
+        match event_type {
+            rdev::EventType::KeyPress(key) => {
+                self.keys.insert(*key);
+            }
+            rdev::EventType::KeyRelease(key) => {
+                self.keys.remove(key);
+            }
+            rdev::EventType::ButtonPress(button) => {
+                self.buttons.insert(*button);
+            }
+            rdev::EventType::ButtonRelease(button) => {
+                self.buttons.remove(button);
+            }
+            _ => {}
+        }
+    }
+
+    /**
+     * @brief Function to release all held keys and buttons
+     */
+    fn release_all(&mut self) {
+        for key in self.keys.drain() {
+            send_event(&rdev::EventType::KeyRelease(key));
+        }
+        for button in self.buttons.drain() {
+            send_event(&rdev::EventType::ButtonRelease(button));
+        }
+    }
+}
+
+// Implementation of SimulationSequence
+impl SimulationSequence {
+    /**
+     * @brief Function to simulate a single step of the sequence
+     * @param index - index of the step
+     * @param module - module that started the simulation, the keys and buttons it holds are updated by the step
+     * @param cancelled - cancellation flag
+     * @return Result<bool, ScriptError> - false if cancelled, error of the script that stopped the step
+     */
+    fn run_step(&self, index: usize, module: &str, cancelled: &AtomicBool) -> Result<bool, ScriptError> {
+        run_actions(&self.steps[index], module, self.delay_ms, cancelled)
+    }
+}
+
+/**
+ * @brief Function to simulate actions
+ * @param actions - the actions
+ * @param module - module that started the simulation, the keys and buttons it holds are updated by the actions
+ * @param delay_ms - delay in milliseconds after each simulated event
+ * @param cancelled - cancellation flag
+ * @return Result<bool, ScriptError> - false if cancelled, error of the script that stopped the actions
+ */
+fn run_actions(actions: &[SimulationAction], module: &str, delay_ms: u64, cancelled: &AtomicBool) -> Result<bool, ScriptError> {
+    for action in actions {
+        if cancelled.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+
+        match action {
+            SimulationAction::Event(event) => {
+                simulate_event(event);
+                get_simulation_jobs().lock().unwrap().record_held(module, event);
+                wait(delay_ms, cancelled);
+            }
+            SimulationAction::Delay(ms) => {
+                wait(*ms, cancelled);
+            }
+            SimulationAction::TypeText(text) => type_text_with_delay(text, delay_ms, cancelled),
+            SimulationAction::Script(script) => {
+                script.run(module, delay_ms, cancelled)?;
+            }
+            SimulationAction::WaitForRelease(timeout_ms) => wait_for_release(*timeout_ms, cancelled),
+            SimulationAction::Paste(handle, text) => paste_text(handle, text, module, delay_ms, cancelled),
+        }
+    }
+
+    Ok(!cancelled.load(Ordering::SeqCst))
+}
+
+/**
+ * @brief Function to wait until the user releases all keys, so held keys (e.g. Shift) don't change the simulated ones
+ * @param timeout_ms - maximum time to wait in milliseconds, keys held longer are ignored
+ * @param cancelled - cancellation flag
+ */
+fn wait_for_release(timeout_ms: u64, cancelled: &AtomicBool) {
+    let end: time::Instant = time::Instant::now() + time::Duration::from_millis(timeout_ms);
+    while any_key_pressed() && time::Instant::now() < end {
+        if !wait(CANCEL_CHECK_INTERVAL_MS, cancelled) {
+            return;
+        }
+    }
+}
+
+/**
+ * @brief Function to paste text through the clipboard
+ * @param handle - tauri::AppHandle
+ * @param text - text to paste
+ * @param module - module that started the simulation, the keys it holds are updated by the paste shortcut
+ * @param delay_ms - delay in milliseconds after each simulated event
+ * @param cancelled - cancellation flag, the clipboard is restored even if cancelled
+ * @note Pasting preserves characters that can't be typed
+ */
+fn paste_text(handle: &tauri::AppHandle, text: &str, module: &str, delay_ms: u64, cancelled: &AtomicBool) {
+    let mut clipboard = handle.clipboard_manager();
+    let previous: Option<String> = clipboard.read_text().ok().flatten();
+
+    if clipboard.write_text(text).is_err() {
+        println!("Failed to write the pasted text to the clipboard");
+        return;
+    }
+
+    let paste: [SimulationAction; 4] = [
+        SimulationAction::Event(rdev::EventType::KeyPress(rdev::Key::ControlLeft)),
+        SimulationAction::Event(rdev::EventType::KeyPress(rdev::Key::KeyV)),
+        SimulationAction::Event(rdev::EventType::KeyRelease(rdev::Key::KeyV)),
+        SimulationAction::Event(rdev::EventType::KeyRelease(rdev::Key::ControlLeft)),
+    ];
+    if let Ok(true) = run_actions(&paste, module, delay_ms, cancelled) {
+        // Wait for the focused application to read the clipboard before restoring it
+        wait(PASTE_RESTORE_DELAY_MS, cancelled);
+    }
+
+    if let Some(previous) = previous {
+        let _ = clipboard.write_text(previous);
+    }
+}
+
+/**
+ * @brief Function to simulate a single step on the current thread
+ * @param step - the step
+ * @param module - module that started the simulation, the keys and buttons it holds are updated by the step
+ * @param delay_ms - delay in milliseconds after each simulated event
+ * @param cancelled - cancellation flag
+ * @return Result<bool, String> - false if cancelled, error message if the step is invalid
+ * @note Exposes the operation to input simulation scripts running on the simulation worker thread
+ */
+pub fn simulate_step(step: &SimulationStep, module: &str, delay_ms: u64, cancelled: &AtomicBool) -> Result<bool, String> {
+    let actions: Vec<SimulationAction> = step.to_actions()?;
+    run_actions(&actions, module, delay_ms, cancelled).map_err(|error| error.to_string())
+}
+
 /**
- * @brief Function to convert string to rdev::Key
- * @param key - string to convert
- * @return rdev::Key
- */
-fn string_to_key(key: &str) -> Result<rdev::Key, &str> {
-    match key {
-        "Alt" => Ok(rdev::Key::Alt),
-        "AltGr" => Ok(rdev::Key::AltGr),
-        "Backspace" => Ok(rdev::Key::Backspace),
-        "CapsLock" => Ok(rdev::Key::CapsLock),
-        "LCtrl" => Ok(rdev::Key::ControlLeft),
-        "RCtrl" => Ok(rdev::Key::ControlRight),
-        "Del" => Ok(rdev::Key::Delete),
-        "DownArrow" => Ok(rdev::Key::DownArrow),
-        "End" => Ok(rdev::Key::End),
-        "Escape" => Ok(rdev::Key::Escape),
-        "F1" => Ok(rdev::Key::F1),
-        "F10" => Ok(rdev::Key::F10),
-        "F11" => Ok(rdev::Key::F11),
-        "F12" => Ok(rdev::Key::F12),
-        "F2" => Ok(rdev::Key::F2),
-        "F3" => Ok(rdev::Key::F3),
-        "F4" => Ok(rdev::Key::F4),
-        "F5" => Ok(rdev::Key::F5),
-        "F6" => Ok(rdev::Key::F6),
-        "F7" => Ok(rdev::Key::F7),
-        "F8" => Ok(rdev::Key::F8),
-        "F9" => Ok(rdev::Key::F9),
-        "Home" => Ok(rdev::Key::Home),
-        "LeftArrow" => Ok(rdev::Key::LeftArrow),
-        "LMeta" => Ok(rdev::Key::MetaLeft),
-        "RMeta" => Ok(rdev::Key::MetaRight),
-        "PgDown" => Ok(rdev::Key::PageDown),
-        "PgUp" => Ok(rdev::Key::PageUp),
-        "Return" => Ok(rdev::Key::Return),
-        "RightArrow" => Ok(rdev::Key::RightArrow),
-        "LShft" => Ok(rdev::Key::ShiftLeft),
-        "RShft" => Ok(rdev::Key::ShiftRight),
-        "Space" => Ok(rdev::Key::Space),
-        "Tab" => Ok(rdev::Key::Tab),
-        "UpArrow" => Ok(rdev::Key::UpArrow),
-        "PrintScreen" => Ok(rdev::Key::PrintScreen),
-        "ScrollLock" => Ok(rdev::Key::ScrollLock),
-        "Pause" => Ok(rdev::Key::Pause),
-        "NumLock" => Ok(rdev::Key::NumLock),
-        "`" => Ok(rdev::Key::BackQuote),
-        "1" => Ok(rdev::Key::Num1),
-        "2" => Ok(rdev::Key::Num2),
-        "3" => Ok(rdev::Key::Num3),
-        "4" => Ok(rdev::Key::Num4),
-        "5" => Ok(rdev::Key::Num5),
-        "6" => Ok(rdev::Key::Num6),
-        "7" => Ok(rdev::Key::Num7),
-        "8" => Ok(rdev::Key::Num8),
-        "9" => Ok(rdev::Key::Num9),
-        "0" => Ok(rdev::Key::Num0),
-        "-" => Ok(rdev::Key::Minus),
-        "=" => Ok(rdev::Key::Equal),
-        "Q" => Ok(rdev::Key::KeyQ),
-        "W" => Ok(rdev::Key::KeyW),
-        "E" => Ok(rdev::Key::KeyE),
-        "R" => Ok(rdev::Key::KeyR),
-        "T" => Ok(rdev::Key::KeyT),
-        "Y" => Ok(rdev::Key::KeyY),
-        "U" => Ok(rdev::Key::KeyU),
-        "I" => Ok(rdev::Key::KeyI),
-        "O" => Ok(rdev::Key::KeyO),
-        "P" => Ok(rdev::Key::KeyP),
-        "(" => Ok(rdev::Key::LeftBracket),
-        ")" => Ok(rdev::Key::RightBracket),
-        "A" => Ok(rdev::Key::KeyA),
-        "S" => Ok(rdev::Key::KeyS),
-        "D" => Ok(rdev::Key::KeyD),
-        "F" => Ok(rdev::Key::KeyF),
-        "G" => Ok(rdev::Key::KeyG),
-        "H" => Ok(rdev::Key::KeyH),
-        "J" => Ok(rdev::Key::KeyJ),
-        "K" => Ok(rdev::Key::KeyK),
-        "L" => Ok(rdev::Key::KeyL),
-        ";" => Ok(rdev::Key::SemiColon),
-        "\"" => Ok(rdev::Key::Quote),
-        "\\" => Ok(rdev::Key::BackSlash),
-        "Z" => Ok(rdev::Key::KeyZ),
-        "X" => Ok(rdev::Key::KeyX),
-        "C" => Ok(rdev::Key::KeyC),
-        "V" => Ok(rdev::Key::KeyV),
-        "B" => Ok(rdev::Key::KeyB),
-        "N" => Ok(rdev::Key::KeyN),
-        "M" => Ok(rdev::Key::KeyM),
-        "," => Ok(rdev::Key::Comma),
-        "." => Ok(rdev::Key::Dot),
-        "/" => Ok(rdev::Key::Slash),
-        "Insert" => Ok(rdev::Key::Insert),
-        "KpReturn" => Ok(rdev::Key::KpReturn),
-        "KpMinus" => Ok(rdev::Key::KpMinus),
-        "KpPlus" => Ok(rdev::Key::KpPlus),
-        "KpMultiply" => Ok(rdev::Key::KpMultiply),
-        "KpDivide" => Ok(rdev::Key::KpDivide),
-        "Kp0" => Ok(rdev::Key::Kp0),
-        "Kp1" => Ok(rdev::Key::Kp1),
-        "Kp2" => Ok(rdev::Key::Kp2),
-        "Kp3" => Ok(rdev::Key::Kp3),
-        "Kp4" => Ok(rdev::Key::Kp4),
-        "Kp5" => Ok(rdev::Key::Kp5),
-        "Kp6" => Ok(rdev::Key::Kp6),
-        "Kp7" => Ok(rdev::Key::Kp7),
-        "Kp8" => Ok(rdev::Key::Kp8),
-        "Kp9" => Ok(rdev::Key::Kp9),
-        "KpDelete" => Ok(rdev::Key::KpDelete),
-        "Function" => Ok(rdev::Key::Function),
-        _ => Err("Unknown Key"),
-    }
-}
-
-// Struct for simulation step
-#[derive(Debug, serde::Deserialize)]
-pub struct SimulationStep {
-    device_type: i64,
-    simulation_type: i64,
-    data_str: String,
-    data_num1: f64,
-    data_num2: f64,
-}
-
-/**
- * @brief Function to simulate inputs
- * @param inputs - vector of SimulationSteps
- */
-pub fn simulate_inputs(inputs: Vec<SimulationStep>) {
-    // Iterate over all steps
-    for step in inputs {
-        match step.device_type {
-            // Keyboard
-            1 => match step.simulation_type {
-                0..=2 => keyboard_key(step.simulation_type, step.data_str),
-                _ => {}
+ * @brief Function to simulate a sequence on the current thread and notify the window that started it
+ * @param handle - tauri::AppHandle
+ * @param job_id - ID of the simulation
+ * @param window_label - label of the window that started the simulation
+ * @param sequence - the validated sequence
+ * @param cancelled - cancellation flag of the simulation
+ * @note Keys and buttons held by the module are released if the simulation is cancelled or stopped by a script error,
+ * otherwise they stay held until the module releases them or is closed
+ */
+fn run_job(
+    handle: tauri::AppHandle,
+    job_id: u64,
+    window_label: String,
+    sequence: SimulationSequence,
+    cancelled: Arc<AtomicBool>,
+) {
+    let _ = handle.emit_to(
+        &window_label,
+        "Overfloat://SimulationStarted",
+        PayloadSimulationStarted { job_id: job_id },
+    );
+
+    let module: String = window_module(&window_label);
+    let total_steps: usize = sequence.steps.len();
+
+    let mut completed: bool = true;
+    let mut error: Option<ScriptError> = None;
+    for index in 0..total_steps {
+        match sequence.run_step(index, &module, &cancelled) {
+            Ok(true) => {}
+            Ok(false) => {
+                completed = false;
+                break;
+            }
+            Err(script_error) => {
+                completed = false;
+                error = Some(script_error);
+                break;
+            }
+        }
+
+        let _ = handle.emit_to(
+            &window_label,
+            "Overfloat://SimulationProgress",
+            PayloadSimulationProgress {
+                job_id: job_id,
+                completed_steps: index + 1,
+                total_steps: total_steps,
             },
-            // Mouse
-            2 => match step.simulation_type {
-                0..=2 => mouse_button(step.simulation_type, step.data_num1 as i64),
-                3 => mouse_scroll(step.data_num1 as i64, step.data_num2 as i64),
-                4 => mouse_move(step.data_num1, step.data_num2),
-                _ => {}
+        );
+    }
+
+    if !completed {
+        release_held(vec![module]);
+    }
+
+    get_simulation_jobs().lock().unwrap().jobs.remove(&job_id);
+    emit_finished(&handle, &window_label, job_id, !completed && error.is_none(), error);
+}
+
+/**
+ * @brief Function run by the simulation worker thread, simulates the queued sequences one at a time
+ * @note Sequences with higher priority are simulated first, sequences with the same priority in the order they were queued
+ */
+fn run_simulation_worker() {
+    loop {
+        let (job_id, handle, window_label, sequence, cancelled) = {
+            let mut simulation_jobs = get_simulation_jobs().lock().unwrap();
+            loop {
+                if let Some(next) = simulation_jobs.take_next() {
+                    break next;
+                }
+                simulation_jobs = JOB_QUEUED.wait(simulation_jobs).unwrap();
+            }
+        };
+
+        run_job(handle, job_id, window_label, sequence, cancelled);
+    }
+}
+
+// Implementation of SimulationJobs
+impl SimulationJobs {
+    fn new() -> SimulationJobs {
+        // Simulate on a dedicated thread, so the sequences of different modules don't interleave
+        thread::spawn(run_simulation_worker);
+
+        SimulationJobs {
+            jobs: HashMap::new(),
+            policies: HashMap::new(),
+            last_job_id: 0,
+            held: HashMap::new(),
+        }
+    }
+
+    /**
+     * @brief Function to update the keys and buttons held by a module by a simulated event
+     * @param module - name of the module
+     * @param event_type - the simulated event
+     */
+    fn record_held(&mut self, module: &str, event_type: &rdev::EventType) {
+        self.held
+            .entry(module.to_string())
+            .or_insert_with(HeldInputs::new)
+            .record(event_type);
+    }
+
+    /**
+     * @brief Function to cancel the queued and running simulations of a module
+     * @param module - name of the module
+     */
+    fn cancel_module(&mut self, module: &str) {
+        let job_ids: Vec<u64> = self
+            .jobs
+            .iter()
+            .filter(|(_, job)| job.module == module)
+            .map(|(job_id, _)| *job_id)
+            .collect();
+        for job_id in job_ids {
+            self.cancel_job(job_id);
+        }
+    }
+
+    /**
+     * @brief Function to take the next queued simulation to run
+     * @return Option<(u64, tauri::AppHandle, String, SimulationSequence, Arc<AtomicBool>)> - ID, handle,
+     * window label, sequence and cancellation flag of the simulation, None if no simulation is queued
+     */
+    fn take_next(
+        &mut self,
+    ) -> Option<(u64, tauri::AppHandle, String, SimulationSequence, Arc<AtomicBool>)> {
+        let job_id: u64 = self
+            .jobs
+            .iter()
+            .filter(|(_, job)| job.sequence.is_some())
+            .max_by_key(|(job_id, job)| (job.priority, std::cmp::Reverse(**job_id)))
+            .map(|(job_id, _)| *job_id)?;
+
+        let job: &mut SimulationJob = self.jobs.get_mut(&job_id)?;
+        let sequence: SimulationSequence = job.sequence.take()?;
+        Some((
+            job_id,
+            job.handle.clone(),
+            job.window_label.clone(),
+            sequence,
+            job.cancelled.clone(),
+        ))
+    }
+
+    /**
+     * @brief Function to queue a simulation
+     * @param handle - tauri::AppHandle
+     * @param window_label - label of the window starting the simulation
+     * @param sequence - the validated sequence
+     * @param priority - priority of the simulation
+     * @return Result<u64, String> - ID of the simulation, error message if it was rejected by the policy of the module
+     */
+    fn enqueue(
+        &mut self,
+        handle: tauri::AppHandle,
+        window_label: String,
+        sequence: SimulationSequence,
+        priority: i32,
+    ) -> Result<u64, String> {
+        let module: String = window_module(&window_label);
+        let module_jobs: Vec<u64> = self
+            .jobs
+            .iter()
+            .filter(|(_, job)| job.module == module)
+            .map(|(job_id, _)| *job_id)
+            .collect();
+
+        match self.policies.get(&module).copied().unwrap_or(SimulationPolicy::Queue) {
+            SimulationPolicy::Queue => {}
+            SimulationPolicy::Reject if !module_jobs.is_empty() => {
+                return Err(format!("A simulation of {} is already queued or running", module));
+            }
+            SimulationPolicy::Reject => {}
+            SimulationPolicy::Replace => {
+                for job_id in module_jobs {
+                    self.cancel_job(job_id);
+                }
+            }
+        }
+
+        self.last_job_id += 1;
+        let job_id: u64 = self.last_job_id;
+        self.jobs.insert(
+            job_id,
+            SimulationJob {
+                handle: handle,
+                window_label: window_label,
+                module: module,
+                priority: priority,
+                cancelled: Arc::new(AtomicBool::new(false)),
+                sequence: Some(sequence),
             },
-            _ => {}
+        );
+        JOB_QUEUED.notify_one();
+
+        Ok(job_id)
+    }
+
+    /**
+     * @brief Function to cancel a simulation, a queued simulation is removed and a running one is stopped
+     * @param job_id - ID of the simulation
+     */
+    fn cancel_job(&mut self, job_id: u64) {
+        let queued: bool = match self.jobs.get(&job_id) {
+            Some(job) => {
+                job.cancelled.store(true, Ordering::SeqCst);
+                job.sequence.is_some()
+            }
+            None => return,
+        };
+
+        // The running simulation notifies about its end itself
+        if queued {
+            if let Some(job) = self.jobs.remove(&job_id) {
+                emit_finished(&job.handle, &job.window_label, job_id, true, None);
+            }
+        }
+    }
+
+    /**
+     * @brief Function to cancel a simulation
+     * @param window_label - label of the window cancelling the simulation
+     * @param job_id - ID of the simulation
+     * @return bool - false if no such simulation was started by the window
+     */
+    fn cancel(&mut self, window_label: &str, job_id: u64) -> bool {
+        match self.jobs.get(&job_id) {
+            Some(job) if job.window_label == window_label => {
+                self.cancel_job(job_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /**
+     * @brief Function to cancel all queued and running simulations
+     * @return usize - number of the cancelled simulations
+     */
+    fn cancel_all(&mut self) -> usize {
+        let job_ids: Vec<u64> = self.jobs.keys().copied().collect();
+        for job_id in &job_ids {
+            self.cancel_job(*job_id);
         }
+        job_ids.len()
+    }
+
+    /**
+     * @brief Function to set the policy of a module for starting a simulation while another one is queued or running
+     * @param module - name of the module
+     * @param policy - the policy
+     */
+    fn set_policy(&mut self, module: String, policy: SimulationPolicy) {
+        self.policies.insert(module, policy);
     }
 }
 
 /**
- * @brief Function to simulate keyboard key
- * @param simulation_type - type of simulation
- * @param key_string - key to simulate
+ * @brief Function to queue simulating inputs
+ * @param handle - tauri::AppHandle
+ * @param window_label - label of the window starting the simulation, it is notified about the progress
+ * @param steps - simulation steps in their JSON representation
+ * @param delay_ms - delay in milliseconds after each simulated event
+ * @param priority - priority of the simulation, queued simulations with higher priority are simulated first
+ * @return SimulationResult - ID of the queued simulation, or the reason why it wasn't queued
+ * @note The whole sequence is validated before simulating, nothing is simulated if any step is invalid.
+ * Exposes the operation to tauri commands
  */
-fn keyboard_key(simulation_type: i64, key_string: String) {
-    // Convert string representation of the key to rdev::Key
-    let key: rdev::Key = match string_to_key(key_string.as_str()) {
-        Ok(key) => key,
-        Err(_) => return,
+pub fn start_simulation(
+    handle: tauri::AppHandle,
+    window_label: String,
+    steps: Vec<serde_json::Value>,
+    delay_ms: u64,
+    priority: i32,
+) -> SimulationResult {
+    if delay_ms > MAX_DELAY_MS {
+        return SimulationResult {
+            successful: false,
+            message: format!("Delay too long: {} ms, maximum is {} ms", delay_ms, MAX_DELAY_MS),
+            errors: Vec::new(),
+            job_id: None,
+        };
+    }
+
+    let sequence: SimulationSequence = match compile_steps(&steps) {
+        Ok(steps) => SimulationSequence {
+            steps: steps,
+            delay_ms: delay_ms,
+        },
+        Err(errors) => {
+            return SimulationResult {
+                successful: false,
+                message: format!("{} invalid simulation step(s)", errors.len()),
+                errors: errors,
+                job_id: None,
+            }
+        }
     };
 
-    match simulation_type {
-        // KeyDown
-        0 => send_event(&rdev::EventType::KeyPress(key)),
-        // KeyUp
-        1 => send_event(&rdev::EventType::KeyRelease(key)),
-        // KeyPress
-        2 => {
-            send_event(&rdev::EventType::KeyPress(key));
-            send_event(&rdev::EventType::KeyRelease(key));
+    let queued = get_simulation_jobs()
+        .lock()
+        .unwrap()
+        .enqueue(handle, window_label, sequence, priority);
+
+    match queued {
+        Ok(job_id) => SimulationResult {
+            successful: true,
+            message: String::new(),
+            errors: Vec::new(),
+            job_id: Some(job_id),
+        },
+        Err(message) => SimulationResult::failed(message),
+    }
+}
+
+/**
+ * @brief Function to queue running a compiled script
+ * @param handle - tauri::AppHandle
+ * @param window_label - label of the window starting the script, it is notified about the progress
+ * @param script - the compiled script
+ * @param delay_ms - delay in milliseconds after each simulated event
+ * @param priority - priority of the simulation, queued simulations with higher priority are simulated first
+ * @return SimulationResult - ID of the queued simulation, or the reason why it wasn't queued
+ * @note The script is queued as a sequence with a single step, so its progress is only reported when it ends
+ */
+pub fn start_script(
+    handle: tauri::AppHandle,
+    window_label: String,
+    script: Script,
+    delay_ms: u64,
+    priority: i32,
+) -> SimulationResult {
+    if delay_ms > MAX_DELAY_MS {
+        return SimulationResult::failed(format!(
+            "Delay too long: {} ms, maximum is {} ms",
+            delay_ms, MAX_DELAY_MS
+        ));
+    }
+
+    let sequence: SimulationSequence = SimulationSequence {
+        steps: vec![vec![SimulationAction::Script(script)]],
+        delay_ms: delay_ms,
+    };
+
+    let queued = get_simulation_jobs()
+        .lock()
+        .unwrap()
+        .enqueue(handle, window_label, sequence, priority);
+
+    match queued {
+        Ok(job_id) => SimulationResult {
+            successful: true,
+            message: String::new(),
+            errors: Vec::new(),
+            job_id: Some(job_id),
+        },
+        Err(message) => SimulationResult::failed(message),
+    }
+}
+
+/**
+ * @brief Function to queue replacing a typed hotstring abbreviation
+ * @param handle - tauri::AppHandle
+ * @param backspaces - number of characters of the abbreviation, they are erased by backspaces
+ * @param replacement - replacement text
+ * @param paste - whether the replacement is pasted through the clipboard instead of typed
+ * @return SimulationResult - ID of the queued simulation, or the reason why it wasn't queued
+ * @note The replacement is simulated after the user releases the keys, so it isn't changed by a held Shift.
+ * The Overfloat window is notified about the progress. Exposes the operation to the hotstring engine
+ */
+pub fn start_expansion(handle: tauri::AppHandle, backspaces: usize, replacement: String, paste: bool) -> SimulationResult {
+    let mut steps: Vec<Vec<SimulationAction>> = vec![vec![SimulationAction::WaitForRelease(EXPANSION_RELEASE_TIMEOUT_MS)]];
+
+    // Erase the abbreviation
+    for _ in 0..backspaces {
+        steps.push(vec![
+            SimulationAction::Event(rdev::EventType::KeyPress(rdev::Key::Backspace)),
+            SimulationAction::Event(rdev::EventType::KeyRelease(rdev::Key::Backspace)),
+        ]);
+    }
+
+    steps.push(vec![if paste {
+        SimulationAction::Paste(handle.clone(), replacement)
+    } else {
+        SimulationAction::TypeText(replacement)
+    }]);
+
+    let sequence: SimulationSequence = SimulationSequence {
+        steps: steps,
+        delay_ms: DEFAULT_EVENT_DELAY_MS,
+    };
+
+    let queued = get_simulation_jobs().lock().unwrap().enqueue(
+        handle,
+        OVERFLOAT_WINDOW_LABEL.to_string(),
+        sequence,
+        0,
+    );
+
+    match queued {
+        Ok(job_id) => SimulationResult {
+            successful: true,
+            message: String::new(),
+            errors: Vec::new(),
+            job_id: Some(job_id),
+        },
+        Err(message) => SimulationResult::failed(message),
+    }
+}
+
+/**
+ * @brief Function to check whether an event observed by the keyboard listener was simulated by an input simulation
+ * @param event_type - type of the observed rdev::Event
+ * @return bool
+ * @note Simulated events are recognized by their identity, each simulated event is recognized once.
+ * Keys are compared by their keycodes, keys simulated by their keycode are observed by their name.
+ * Exposes the operation to the keyboard listener
+ */
+pub fn take_simulated_event(event_type: &rdev::EventType) -> bool {
+    let mut simulated_events = get_simulated_events().lock().unwrap();
+
+    // Forget the events the keyboard listener missed
+    let timeout: time::Duration = time::Duration::from_millis(SIMULATED_EVENT_TIMEOUT_MS);
+    simulated_events.retain(|(_, simulated_at)| simulated_at.elapsed() < timeout);
+
+    match simulated_events
+        .iter()
+        .position(|(simulated, _)| same_event(simulated, event_type))
+    {
+        Some(position) => {
+            simulated_events.remove(position);
+            true
         }
-        _ => return,
+        None => false,
     }
 }
 
 /**
- * @brief Function to send a simulation event to the system
- * @param event_type - type of event
+ * @brief Function to check whether two events are the same, keys are compared by their keycodes
+ * @param a - type of the first rdev::Event
+ * @param b - type of the second rdev::Event
+ * @return bool
+ * @note Exposes the operation to the remapper, which recognizes the events it simulated the same way
  */
-fn send_event(event_type: &rdev::EventType) {
-    // Delay between events
-    let delay = time::Duration::from_millis(1);
+pub fn same_event(a: &rdev::EventType, b: &rdev::EventType) -> bool {
+    let same_key = |a: &rdev::Key, b: &rdev::Key| a == b || (key_to_code(*a).is_some() && key_to_code(*a) == key_to_code(*b));
 
-    // Send event
-    match rdev::simulate(event_type) {
-        Ok(()) => (),
-        Err(_) => {
-            println!("Failed to send event: {:?}", event_type);
+    match (a, b) {
+        (rdev::EventType::KeyPress(a), rdev::EventType::KeyPress(b)) => same_key(a, b),
+        (rdev::EventType::KeyRelease(a), rdev::EventType::KeyRelease(b)) => same_key(a, b),
+        _ => a == b,
+    }
+}
+
+/**
+ * @brief Function to set the policy of the module of a window for starting a simulation while another one is queued or running
+ * @param window_label - label of a window of the module
+ * @param policy - the policy
+ * @note Exposes the operation to tauri commands
+ */
+pub fn set_simulation_policy(window_label: &str, policy: SimulationPolicy) {
+    let mut simulation_jobs = get_simulation_jobs().lock().unwrap();
+    simulation_jobs.set_policy(window_module(window_label), policy);
+}
+
+/**
+ * @brief Function to cancel a queued or running simulation, keys and buttons it holds are released
+ * @param window_label - label of the window cancelling the simulation, only its own simulations can be cancelled
+ * @param job_id - ID of the simulation
+ * @return bool - false if no such simulation was started by the window
+ * @note Exposes the operation to tauri commands
+ */
+pub fn cancel_simulation(window_label: &str, job_id: u64) -> bool {
+    get_simulation_jobs().lock().unwrap().cancel(window_label, job_id)
+}
+
+/**
+ * @brief Function to release the keys and buttons held by the simulations of modules
+ * @param modules - names of the modules
+ * @note The held inputs are released without holding the lock, releasing them takes time
+ */
+fn release_held(modules: Vec<String>) {
+    let held: Vec<HeldInputs> = {
+        let mut simulation_jobs = get_simulation_jobs().lock().unwrap();
+        modules
+            .iter()
+            .filter_map(|module| simulation_jobs.held.remove(module))
+            .collect()
+    };
+
+    for mut inputs in held {
+        inputs.release_all();
+    }
+}
+
+/**
+ * @brief Function to cancel all queued and running simulations
+ * @return (usize, Vec<String>) - number of the cancelled simulations and the modules holding keys or buttons
+ */
+fn cancel_all_jobs() -> (usize, Vec<String>) {
+    let mut simulation_jobs = get_simulation_jobs().lock().unwrap();
+    let modules: Vec<String> = simulation_jobs.held.keys().cloned().collect();
+    (simulation_jobs.cancel_all(), modules)
+}
+
+/**
+ * @brief Function to cancel all queued and running simulations and release all keys and buttons held by simulations
+ * and by the key remapping
+ * @return usize - number of the cancelled simulations
+ * @note Exposes the operation to the keyboard listener as an emergency stop. The simulations are only signalled
+ * to stop, the inputs are released on a separate thread since releasing waits after each event
+ */
+pub fn cancel_all_simulations() -> usize {
+    let (cancelled, modules) = cancel_all_jobs();
+    thread::spawn(move || {
+        release_held(modules);
+        release_remapped_keys();
+    });
+
+    cancelled
+}
+
+/**
+ * @brief Function to stop the simulations of the module of a destroyed window and release the keys and buttons they hold
+ * @param handle - tauri::AppHandle
+ * @param window_label - label of the destroyed window
+ * @note Exposes the operation to the window event handler. The module is considered closed when its main window
+ * is destroyed or when it has no other windows
+ */
+pub fn window_destroyed(handle: &tauri::AppHandle, window_label: &str) {
+    let module: String = window_module(window_label);
+    let main_window: bool = window_label == format!("{}{}", MODULE_WINDOW_PREFIX, module);
+    let other_windows: bool = handle
+        .windows()
+        .into_keys()
+        .any(|label| label != window_label && window_module(&label) == module);
+
+    if !main_window && other_windows {
+        return;
+    }
+
+    get_simulation_jobs().lock().unwrap().cancel_module(&module);
+    release_held(vec![module]);
+}
+
+/**
+ * @brief Function to stop all simulations and release all keys and buttons held by simulations before quitting
+ * @note Exposes the operation to the application exit handlers
+ */
+pub fn release_all_inputs() {
+    let (_, modules) = cancel_all_jobs();
+    release_held(modules);
+}
+
+/**
+ * @brief Function to get the key typing a character on the US keyboard layout
+ * @param character - character to type
+ * @return Option<(rdev::Key, bool)> - key and whether Shift has to be held, None if the character can't be typed
+ */
+fn character_to_key(character: char) -> Option<(rdev::Key, bool)> {
+    // Letters and digits
+    const LETTERS: [rdev::Key; 26] = [
+        rdev::Key::KeyA, rdev::Key::KeyB, rdev::Key::KeyC, rdev::Key::KeyD, rdev::Key::KeyE,
+        rdev::Key::KeyF, rdev::Key::KeyG, rdev::Key::KeyH, rdev::Key::KeyI, rdev::Key::KeyJ,
+        rdev::Key::KeyK, rdev::Key::KeyL, rdev::Key::KeyM, rdev::Key::KeyN, rdev::Key::KeyO,
+        rdev::Key::KeyP, rdev::Key::KeyQ, rdev::Key::KeyR, rdev::Key::KeyS, rdev::Key::KeyT,
+        rdev::Key::KeyU, rdev::Key::KeyV, rdev::Key::KeyW, rdev::Key::KeyX, rdev::Key::KeyY,
+        rdev::Key::KeyZ,
+    ];
+    const DIGITS: [rdev::Key; 10] = [
+        rdev::Key::Num0, rdev::Key::Num1, rdev::Key::Num2, rdev::Key::Num3, rdev::Key::Num4,
+        rdev::Key::Num5, rdev::Key::Num6, rdev::Key::Num7, rdev::Key::Num8, rdev::Key::Num9,
+    ];
+    // Characters typed by the digit keys while Shift is held, in the order of the digits
+    const SHIFTED_DIGITS: &str = ")!@#$%^&*(";
+
+    if character.is_ascii_lowercase() {
+        return Some((LETTERS[(character as u8 - b'a') as usize], false));
+    }
+    if character.is_ascii_uppercase() {
+        return Some((LETTERS[(character as u8 - b'A') as usize], true));
+    }
+    if character.is_ascii_digit() {
+        return Some((DIGITS[(character as u8 - b'0') as usize], false));
+    }
+    if let Some(index) = SHIFTED_DIGITS.find(character) {
+        return Some((DIGITS[index], true));
+    }
+
+    // Punctuation and whitespace
+    match character {
+        ' ' => Some((rdev::Key::Space, false)),
+        '\n' => Some((rdev::Key::Return, false)),
+        '\t' => Some((rdev::Key::Tab, false)),
+        '-' => Some((rdev::Key::Minus, false)),
+        '_' => Some((rdev::Key::Minus, true)),
+        '=' => Some((rdev::Key::Equal, false)),
+        '+' => Some((rdev::Key::Equal, true)),
+        '[' => Some((rdev::Key::LeftBracket, false)),
+        '{' => Some((rdev::Key::LeftBracket, true)),
+        ']' => Some((rdev::Key::RightBracket, false)),
+        '}' => Some((rdev::Key::RightBracket, true)),
+        '\\' => Some((rdev::Key::BackSlash, false)),
+        '|' => Some((rdev::Key::BackSlash, true)),
+        ';' => Some((rdev::Key::SemiColon, false)),
+        ':' => Some((rdev::Key::SemiColon, true)),
+        '\'' => Some((rdev::Key::Quote, false)),
+        '"' => Some((rdev::Key::Quote, true)),
+        ',' => Some((rdev::Key::Comma, false)),
+        '<' => Some((rdev::Key::Comma, true)),
+        '.' => Some((rdev::Key::Dot, false)),
+        '>' => Some((rdev::Key::Dot, true)),
+        '/' => Some((rdev::Key::Slash, false)),
+        '?' => Some((rdev::Key::Slash, true)),
+        '`' => Some((rdev::Key::BackQuote, false)),
+        '~' => Some((rdev::Key::BackQuote, true)),
+        _ => None,
+    }
+}
+
+/**
+ * @brief Function to press a key, optionally while holding Shift
+ * @param key - key to press
+ * @param shift - whether Shift has to be held
+ * @param delay_ms - delay in milliseconds after each event
+ * @param cancelled - cancellation flag, the key is released even if cancelled but the delays are skipped
+ */
+fn type_key(key: rdev::Key, shift: bool, delay_ms: u64, cancelled: &AtomicBool) {
+    let mut events: Vec<rdev::EventType> = vec![rdev::EventType::KeyPress(key), rdev::EventType::KeyRelease(key)];
+    if shift {
+        events.insert(0, rdev::EventType::KeyPress(rdev::Key::ShiftLeft));
+        events.push(rdev::EventType::KeyRelease(rdev::Key::ShiftLeft));
+    }
+
+    for event in &events {
+        simulate_event(event);
+        wait(delay_ms, cancelled);
+    }
+}
+
+/**
+ * @brief Function to type text as keys of the US keyboard layout
+ * @param text - text to type
+ * @param delay_ms - delay in milliseconds after each event
+ * @param cancelled - cancellation flag, checked before each character
+ * @note Characters without a key are skipped, used when the keyboard layout can't be queried
+ */
+#[cfg_attr(windows, allow(dead_code))]
+fn type_text_us_layout(text: &str, delay_ms: u64, cancelled: &AtomicBool) {
+    for character in text.chars() {
+        if cancelled.load(Ordering::SeqCst) {
+            return;
+        }
+
+        match character_to_key(character) {
+            Some((key, shift)) => type_key(key, shift, delay_ms, cancelled),
+            None => println!("Couldn't type character: {:?}", character),
         }
     }
+}
 
-    // Sleep for delay for the system to process the event
-    thread::sleep(delay);
+/**
+ * @brief Function to get the X11 keysym of a character
+ * @param character - character
+ * @return xlib::KeySym
+ * @note Latin-1 characters have legacy keysyms equal to their code points, other characters use Unicode keysyms
+ */
+#[cfg(target_os = "linux")]
+fn character_to_keysym(character: char) -> x11::xlib::KeySym {
+    match character {
+        '\n' => x11::keysym::XK_Return as x11::xlib::KeySym,
+        '\t' => x11::keysym::XK_Tab as x11::xlib::KeySym,
+        ' '..='~' | '\u{a0}'..='\u{ff}' => character as x11::xlib::KeySym,
+        _ => 0x0100_0000 | character as x11::xlib::KeySym,
+    }
+}
+
+/**
+ * @brief Function to check whether the levels of a key are the lowercase and uppercase forms of a letter
+ * @param levels - keysyms of the first two levels of the key
+ * @return bool - whether CapsLock swaps the levels of the key
+ */
+#[cfg(target_os = "linux")]
+unsafe fn x11_is_cased(levels: &[x11::xlib::KeySym]) -> bool {
+    use x11::xlib;
+
+    if levels.len() < 2 {
+        return false;
+    }
+
+    let mut lower: xlib::KeySym = 0;
+    let mut upper: xlib::KeySym = 0;
+    xlib::XConvertCase(levels[0], &mut lower, &mut upper);
+    lower != upper && levels[0] == lower && levels[1] == upper
+}
+
+/**
+ * @brief Function to find the key typing a keysym on the current keyboard layout
+ * @param display - connection to the X server
+ * @param keysym - keysym to type
+ * @param caps_lock - whether CapsLock is on, Shift is inverted for letters then
+ * @return Option<(xlib::KeyCode, bool)> - keycode and whether Shift has to be held,
+ * None if the keysym isn't on the first two levels of any key
+ */
+#[cfg(target_os = "linux")]
+unsafe fn x11_keysym_to_key(
+    display: *mut x11::xlib::Display,
+    keysym: x11::xlib::KeySym,
+    caps_lock: bool,
+) -> Option<(x11::xlib::KeyCode, bool)> {
+    use x11::xlib;
+
+    let keycode: xlib::KeyCode = xlib::XKeysymToKeycode(display, keysym);
+    if keycode == 0 {
+        return None;
+    }
+
+    // The first keysym of a key is typed without modifiers, the second one with Shift
+    let mut keysyms_per_keycode: std::os::raw::c_int = 0;
+    let keysyms = xlib::XGetKeyboardMapping(display, keycode, 1, &mut keysyms_per_keycode);
+    if keysyms.is_null() {
+        return None;
+    }
+    let levels = std::slice::from_raw_parts(keysyms, keysyms_per_keycode.max(0) as usize);
+    let levels: &[xlib::KeySym] = &levels[..levels.len().min(2)];
+    let shift: Option<bool> = levels.iter().position(|level| *level == keysym).map(|level| level == 1);
+    let cased: bool = x11_is_cased(levels);
+    xlib::XFree(keysyms as *mut std::os::raw::c_void);
+
+    shift.map(|shift| (keycode, shift != (caps_lock && cased)))
+}
+
+/**
+ * @brief Function to find a keycode without any keysyms, which can be remapped temporarily
+ * @param display - connection to the X server
+ * @return Option<xlib::KeyCode> - None if all keycodes are mapped
+ */
+#[cfg(target_os = "linux")]
+unsafe fn x11_spare_keycode(display: *mut x11::xlib::Display) -> Option<x11::xlib::KeyCode> {
+    use x11::xlib;
+
+    let mut min_keycode: std::os::raw::c_int = 0;
+    let mut max_keycode: std::os::raw::c_int = 0;
+    xlib::XDisplayKeycodes(display, &mut min_keycode, &mut max_keycode);
+
+    let count: std::os::raw::c_int = max_keycode - min_keycode + 1;
+    let mut keysyms_per_keycode: std::os::raw::c_int = 0;
+    let keysyms = xlib::XGetKeyboardMapping(display, min_keycode as xlib::KeyCode, count, &mut keysyms_per_keycode);
+    if keysyms.is_null() || count <= 0 || keysyms_per_keycode <= 0 {
+        return None;
+    }
+
+    // Search from the highest keycode, the low ones belong to physical keys
+    let per_keycode: usize = keysyms_per_keycode as usize;
+    let mapping = std::slice::from_raw_parts(keysyms, count as usize * per_keycode);
+    let spare: Option<xlib::KeyCode> = (0..count as usize)
+        .rev()
+        .find(|index| {
+            mapping[index * per_keycode..(index + 1) * per_keycode]
+                .iter()
+                .all(|keysym| *keysym == 0)
+        })
+        .map(|index| (min_keycode as usize + index) as xlib::KeyCode);
+    xlib::XFree(keysyms as *mut std::os::raw::c_void);
+
+    spare
+}
+
+/**
+ * @brief Function to change the keysyms of a keycode and wait until the X server notifies about the change
+ * @param display - connection to the X server
+ * @param keycode - the keycode
+ * @param keysyms - keysyms of the first two levels of the keycode
+ * @note Clients receive the notification before the events simulated afterwards, so they translate the simulated
+ * keys with the new mapping without waiting for them
+ */
+#[cfg(target_os = "linux")]
+unsafe fn x11_change_mapping(display: *mut x11::xlib::Display, keycode: x11::xlib::KeyCode, mut keysyms: [x11::xlib::KeySym; 2]) {
+    use x11::xlib;
+
+    // Drop stale notifications, e.g. of a layout switch, so only the notification of this change is waited for
+    let mut event: xlib::XEvent = std::mem::zeroed();
+    while xlib::XCheckTypedEvent(display, xlib::MappingNotify, &mut event) != 0 {}
+
+    xlib::XChangeKeyboardMapping(display, keycode as std::os::raw::c_int, 2, keysyms.as_mut_ptr(), 1);
+
+    // The notification is sent before the reply to the synchronization, so it is queued once the synchronization ends
+    for _ in 0..MAPPING_NOTIFY_ATTEMPTS {
+        xlib::XSync(display, xlib::False);
+        if xlib::XCheckTypedEvent(display, xlib::MappingNotify, &mut event) != 0 {
+            xlib::XRefreshKeyboardMapping(&mut event.mapping);
+            return;
+        }
+    }
+}
+
+/**
+ * @brief Function to type a keysym that isn't on the keyboard layout by temporarily mapping it to a spare keycode
+ * @param display - connection to the X server
+ * @param keycode - spare keycode
+ * @param keysym - keysym to type
+ * @param caps_lock - whether CapsLock is on, Shift is inverted for letters then
+ * @param delay_ms - delay in milliseconds after each event
+ * @param cancelled - cancellation flag
+ */
+#[cfg(target_os = "linux")]
+unsafe fn x11_type_remapped(
+    display: *mut x11::xlib::Display,
+    keycode: x11::xlib::KeyCode,
+    keysym: x11::xlib::KeySym,
+    caps_lock: bool,
+    delay_ms: u64,
+    cancelled: &AtomicBool,
+) {
+    use x11::xlib;
+
+    // Map letters like the keys of the layout, so CapsLock is compensated the same way,
+    // other keysyms are mapped to both levels so they are typed regardless of Shift
+    let mut lower: xlib::KeySym = 0;
+    let mut upper: xlib::KeySym = 0;
+    xlib::XConvertCase(keysym, &mut lower, &mut upper);
+    let (levels, shift) = if lower != upper {
+        ([lower, upper], (keysym == upper) != caps_lock)
+    } else {
+        ([keysym, keysym], false)
+    };
+
+    x11_change_mapping(display, keycode, levels);
+    type_key(rdev::Key::Unknown(keycode as u32), shift, delay_ms, cancelled);
+    x11_change_mapping(display, keycode, [0, 0]);
 }
 
 /**
- * @brief Function to simulate mouse button
- * @param simulation_type - type of simulation
- * @param button - button to simulate
+ * @brief Function to type text
+ * @param text - text to type
+ * @param delay_ms - delay in milliseconds after each event
+ * @param cancelled - cancellation flag, checked before each character
+ * @note Characters are typed by the keys of the current keyboard layout, holding Shift if needed,
+ * or releasing it for letters while CapsLock is on.
+ * Characters that aren't on the layout are typed by temporarily remapping a spare keycode.
+ * Falls back to the US layout if the X server can't be reached
  */
-fn mouse_button(simulation_type: i64, button: i64) {
-    // Convert mouse button code to rdev::Button
-    let mouse_button: rdev::Button = match button {
-        0 => rdev::Button::Left,
-        1 => rdev::Button::Middle,
-        2 => rdev::Button::Right,
-        _ => return,
+#[cfg(target_os = "linux")]
+fn type_text_with_delay(text: &str, delay_ms: u64, cancelled: &AtomicBool) {
+    use crate::keygrab::{register_display, unregister_display};
+    use x11::xlib;
+
+    let caps_lock: bool = query_lock_states().map(|states| states.caps_lock).unwrap_or(false);
+
+    unsafe {
+        let display = xlib::XOpenDisplay(std::ptr::null());
+        if display.is_null() {
+            type_text_us_layout(text, delay_ms, cancelled);
+            return;
+        }
+
+        // Errors of remapping the spare keycode are ignored instead of reaching the error handler of GDK
+        register_display(display);
+
+        let mut spare_keycode: Option<xlib::KeyCode> = None;
+        for character in text.chars() {
+            if cancelled.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let keysym: xlib::KeySym = character_to_keysym(character);
+
+            if let Some((keycode, shift)) = x11_keysym_to_key(display, keysym, caps_lock) {
+                type_key(rdev::Key::Unknown(keycode as u32), shift, delay_ms, cancelled);
+                continue;
+            }
+
+            // Find the spare keycode only when it is needed
+            if spare_keycode.is_none() {
+                spare_keycode = x11_spare_keycode(display);
+            }
+            match spare_keycode {
+                Some(keycode) => x11_type_remapped(display, keycode, keysym, caps_lock, delay_ms, cancelled),
+                None => println!("Couldn't type character: {:?}", character),
+            }
+        }
+
+        // Synchronize before unregistering, so errors of the last requests are still ignored
+        xlib::XSync(display, xlib::False);
+        unregister_display(display);
+        xlib::XCloseDisplay(display);
+    }
+}
+
+/**
+ * @brief Function to send a Unicode character unit directly, without a key
+ * @param unit - UTF-16 code unit
+ * @param key_up - whether to send the release instead of the press
+ */
+#[cfg(windows)]
+fn send_unicode_unit(unit: u16, key_up: bool) {
+    use winapi::um::winuser;
+
+    // Unicode input is observed as the packet virtual key, remember it so it is recognized as simulated
+    let packet_key: rdev::Key = rdev::Key::Unknown(winuser::VK_PACKET as u32);
+    let event_type: rdev::EventType = if key_up {
+        rdev::EventType::KeyRelease(packet_key)
+    } else {
+        rdev::EventType::KeyPress(packet_key)
     };
+    get_simulated_events()
+        .lock()
+        .unwrap()
+        .push((event_type, time::Instant::now()));
+
+    unsafe {
+        let mut input: winuser::INPUT = std::mem::zeroed();
+        input.type_ = winuser::INPUT_KEYBOARD;
+        *input.u.ki_mut() = winuser::KEYBDINPUT {
+            wVk: 0,
+            wScan: unit,
+            dwFlags: winuser::KEYEVENTF_UNICODE | if key_up { winuser::KEYEVENTF_KEYUP } else { 0 },
+            time: 0,
+            dwExtraInfo: 0,
+        };
+        winuser::SendInput(1, &mut input, std::mem::size_of::<winuser::INPUT>() as i32);
+    }
+}
+
+/**
+ * @brief Function to type text
+ * @param text - text to type
+ * @param delay_ms - delay in milliseconds after each event
+ * @param cancelled - cancellation flag, checked before each character
+ * @note Characters are typed by the keys of the current keyboard layout, holding Shift if needed,
+ * or releasing it for letters while CapsLock is on.
+ * Characters that aren't on the layout or need other modifiers are sent as Unicode input
+ */
+#[cfg(windows)]
+fn type_text_with_delay(text: &str, delay_ms: u64, cancelled: &AtomicBool) {
+    use winapi::um::winuser;
+
+    let caps_lock: bool = query_lock_states().map(|states| states.caps_lock).unwrap_or(false);
+
+    for character in text.chars() {
+        if cancelled.load(Ordering::SeqCst) {
+            return;
+        }
+
+        // Newlines are typed as Enter, the layout maps them to Ctrl+Enter
+        if character == '\n' {
+            type_key(rdev::Key::Return, false, delay_ms, cancelled);
+            continue;
+        }
+
+        let mut buffer: [u16; 2] = [0; 2];
+        let units: &[u16] = character.encode_utf16(&mut buffer);
+
+        // Low byte of the result is the virtual key, high byte the required modifiers (1 is Shift)
+        if units.len() == 1 {
+            let scan: i16 = unsafe { winuser::VkKeyScanW(units[0]) };
+            let virtual_key: u32 = (scan & 0xff) as u32;
+            let modifiers: i16 = (scan >> 8) & 0xff;
+            if scan != -1 && modifiers & !1 == 0 {
+                // CapsLock inverts Shift for letters
+                let cased: bool = character.is_lowercase() || character.is_uppercase();
+                let shift: bool = (modifiers & 1 != 0) != (caps_lock && cased);
+                type_key(rdev::Key::Unknown(virtual_key), shift, delay_ms, cancelled);
+                continue;
+            }
+        }
 
-    match simulation_type {
-        // ButtonDown
-        0 => send_event(&rdev::EventType::ButtonPress(mouse_button)),
-        // ButtonUp
-        1 => send_event(&rdev::EventType::ButtonRelease(mouse_button)),
-        // ButtonClick
-        2 => {
-            send_event(&rdev::EventType::ButtonPress(mouse_button));
-            send_event(&rdev::EventType::ButtonRelease(mouse_button));
+        for unit in units.iter() {
+            send_unicode_unit(*unit, false);
+            send_unicode_unit(*unit, true);
+            wait(delay_ms, cancelled);
         }
-        _ => return,
     }
 }
 
 /**
- * @brief Function to simulate mouse scroll
- * @param delta_x - delta x
- * @param delta_y - delta y
- * @return
+ * @brief Function to type text
+ * @param text - text to type
+ * @param delay_ms - delay in milliseconds after each event
+ * @param cancelled - cancellation flag, checked before each character
+ * @note Characters are typed as keys of the US keyboard layout
  */
-fn mouse_scroll(delta_x: i64, delta_y: i64) {
-    send_event(&rdev::EventType::Wheel {
-        delta_x: delta_x,
-        delta_y: delta_y,
-    });
+#[cfg(not(any(target_os = "linux", windows)))]
+fn type_text_with_delay(text: &str, delay_ms: u64, cancelled: &AtomicBool) {
+    type_text_us_layout(text, delay_ms, cancelled);
+}
+
+/**
+ * @brief Function to send a simulation event to the system
+ * @param event_type - type of event
+ */
+fn send_event(event_type: &rdev::EventType) {
+    simulate_event(event_type);
+
+    // Sleep for delay for the system to process the event
+    thread::sleep(time::Duration::from_millis(DEFAULT_EVENT_DELAY_MS));
 }
 
 /**
- * @brief Function to simulate mouse move
- * @param x - x coordinate
- * @param y - y coordinate
+ * @brief Function to send a simulation event to the system without waiting
+ * @param event_type - type of event
  */
-fn mouse_move(x: f64, y: f64) {
-    send_event(&rdev::EventType::MouseMove { x: x, y: y });
+fn simulate_event(event_type: &rdev::EventType) {
+    // Remember the event, so the keyboard listener recognizes it as simulated
+    get_simulated_events()
+        .lock()
+        .unwrap()
+        .push((*event_type, time::Instant::now()));
+
+    match rdev::simulate(event_type) {
+        Ok(()) => (),
+        Err(_) => {
+            println!("Failed to send event: {:?}", event_type);
+        }
+    }
 }
@@ -5,123 +5,34 @@
  ****************************************************************************/
 
 use rdev;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tauri::Manager;
 
-/**
- * @brief Function to convert rdev::Key to string
- * @param key - rdev::Key to convert
- * @return String
- */
-fn key_to_string(key: rdev::Key) -> String {
-    match key {
-        rdev::Key::Alt => String::from("Alt"),
-        rdev::Key::AltGr => String::from("AltGr"),
-        rdev::Key::Backspace => String::from("Backspace"),
-        rdev::Key::CapsLock => String::from("CapsLock"),
-        rdev::Key::ControlLeft => String::from("LCtrl"),
-        rdev::Key::ControlRight => String::from("RCtrl"),
-        rdev::Key::Delete => String::from("Del"),
-        rdev::Key::DownArrow => String::from("DownArrow"),
-        rdev::Key::End => String::from("End"),
-        rdev::Key::Escape => String::from("Escape"),
-        rdev::Key::F1 => String::from("F1"),
-        rdev::Key::F10 => String::from("F10"),
-        rdev::Key::F11 => String::from("F11"),
-        rdev::Key::F12 => String::from("F12"),
-        rdev::Key::F2 => String::from("F2"),
-        rdev::Key::F3 => String::from("F3"),
-        rdev::Key::F4 => String::from("F4"),
-        rdev::Key::F5 => String::from("F5"),
-        rdev::Key::F6 => String::from("F6"),
-        rdev::Key::F7 => String::from("F7"),
-        rdev::Key::F8 => String::from("F8"),
-        rdev::Key::F9 => String::from("F9"),
-        rdev::Key::Home => String::from("Home"),
-        rdev::Key::LeftArrow => String::from("LeftArrow"),
-        rdev::Key::MetaLeft => String::from("LMeta"),
-        rdev::Key::MetaRight => String::from("RMeta"),
-        rdev::Key::PageDown => String::from("PgDown"),
-        rdev::Key::PageUp => String::from("PgUp"),
-        rdev::Key::Return => String::from("Return"),
-        rdev::Key::RightArrow => String::from("RightArrow"),
-        rdev::Key::ShiftLeft => String::from("LShft"),
-        rdev::Key::ShiftRight => String::from("RShft"),
-        rdev::Key::Space => String::from("Space"),
-        rdev::Key::Tab => String::from("Tab"),
-        rdev::Key::UpArrow => String::from("UpArrow"),
-        rdev::Key::PrintScreen => String::from("PrintScreen"),
-        rdev::Key::ScrollLock => String::from("ScrollLock"),
-        rdev::Key::Pause => String::from("Pause"),
-        rdev::Key::NumLock => String::from("NumLock"),
-        rdev::Key::BackQuote => String::from("`"),
-        rdev::Key::Num1 => String::from("1"),
-        rdev::Key::Num2 => String::from("2"),
-        rdev::Key::Num3 => String::from("3"),
-        rdev::Key::Num4 => String::from("4"),
-        rdev::Key::Num5 => String::from("5"),
-        rdev::Key::Num6 => String::from("6"),
-        rdev::Key::Num7 => String::from("7"),
-        rdev::Key::Num8 => String::from("8"),
-        rdev::Key::Num9 => String::from("9"),
-        rdev::Key::Num0 => String::from("0"),
-        rdev::Key::Minus => String::from("-"),
-        rdev::Key::Equal => String::from("="),
-        rdev::Key::KeyQ => String::from("Q"),
-        rdev::Key::KeyW => String::from("W"),
-        rdev::Key::KeyE => String::from("E"),
-        rdev::Key::KeyR => String::from("R"),
-        rdev::Key::KeyT => String::from("T"),
-        rdev::Key::KeyY => String::from("Y"),
-        rdev::Key::KeyU => String::from("U"),
-        rdev::Key::KeyI => String::from("I"),
-        rdev::Key::KeyO => String::from("O"),
-        rdev::Key::KeyP => String::from("P"),
-        rdev::Key::LeftBracket => String::from("("),
-        rdev::Key::RightBracket => String::from(")"),
-        rdev::Key::KeyA => String::from("A"),
-        rdev::Key::KeyS => String::from("S"),
-        rdev::Key::KeyD => String::from("D"),
-        rdev::Key::KeyF => String::from("F"),
-        rdev::Key::KeyG => String::from("G"),
-        rdev::Key::KeyH => String::from("H"),
-        rdev::Key::KeyJ => String::from("J"),
-        rdev::Key::KeyK => String::from("K"),
-        rdev::Key::KeyL => String::from("L"),
-        rdev::Key::SemiColon => String::from(";"),
-        rdev::Key::Quote => String::from("\""),
-        rdev::Key::BackSlash => String::from("\\"),
-        rdev::Key::IntlBackslash => String::from("\\"),
-        rdev::Key::KeyZ => String::from("Z"),
-        rdev::Key::KeyX => String::from("X"),
-        rdev::Key::KeyC => String::from("C"),
-        rdev::Key::KeyV => String::from("V"),
-        rdev::Key::KeyB => String::from("B"),
-        rdev::Key::KeyN => String::from("N"),
-        rdev::Key::KeyM => String::from("M"),
-        rdev::Key::Comma => String::from(","),
-        rdev::Key::Dot => String::from("."),
-        rdev::Key::Slash => String::from("/"),
-        rdev::Key::Insert => String::from("Insert"),
-        rdev::Key::KpReturn => String::from("KpReturn"),
-        rdev::Key::KpMinus => String::from("KpMinus"),
-        rdev::Key::KpPlus => String::from("KpPlus"),
-        rdev::Key::KpMultiply => String::from("KpMultiply"),
-        rdev::Key::KpDivide => String::from("KpDivide"),
-        rdev::Key::Kp0 => String::from("Kp0"),
-        rdev::Key::Kp1 => String::from("Kp1"),
-        rdev::Key::Kp2 => String::from("Kp2"),
-        rdev::Key::Kp3 => String::from("Kp3"),
-        rdev::Key::Kp4 => String::from("Kp4"),
-        rdev::Key::Kp5 => String::from("Kp5"),
-        rdev::Key::Kp6 => String::from("Kp6"),
-        rdev::Key::Kp7 => String::from("Kp7"),
-        rdev::Key::Kp8 => String::from("Kp8"),
-        rdev::Key::Kp9 => String::from("Kp9"),
-        rdev::Key::KpDelete => String::from("KpDelete"),
-        rdev::Key::Function => String::from("Function"),
-        _ => String::from("Unknown"),
-    }
+use lazy_static::lazy_static;
+use std::sync::{mpsc, Mutex};
+
+use crate::hotstrings;
+use crate::inputsim::cancel_all_simulations;
+use crate::keygrab::request_grab_update;
+use crate::keys::{key_to_code, key_to_string};
+use crate::keystate::{query_lock_states, query_pressed_keys, LockStates};
+use crate::remap;
+use crate::script;
+use crate::shortcuts::{keybind_matches, trigger_shortcuts, validate_keybind};
+
+/*
+* Lazily initialized global instance of KeyboardState used for tracking the state of the keyboard
+* This has to be global because it needs to be accessible from tauri commands
+*/
+lazy_static! {
+    static ref KEYBOARD_STATE: Mutex<KeyboardState> = Mutex::new(KeyboardState::new());
+}
+
+/*
+* Get the global instance of KeyboardState
+*/
+fn get_keyboard_state() -> &'static Mutex<KeyboardState> {
+    &KEYBOARD_STATE
 }
 
 // List of modifier keys
@@ -136,92 +47,601 @@ const RDEV_MODIFIER_KEYS: [rdev::Key; 8] = [
     rdev::Key::ShiftRight,
 ];
 
+// Modifier keys turning typed keys into commands, AltGr is not included as it types characters
+const COMMAND_MODIFIER_KEYS: [rdev::Key; 5] = [
+    rdev::Key::MetaLeft,
+    rdev::Key::MetaRight,
+    rdev::Key::ControlLeft,
+    rdev::Key::ControlRight,
+    rdev::Key::Alt,
+];
+
+// Lock keys whose states are part of the keyboard state
+const LOCK_KEYS: [rdev::Key; 2] = [rdev::Key::CapsLock, rdev::Key::NumLock];
+
+// Default time window for detecting a double-tap in milliseconds
+const DEFAULT_DOUBLE_TAP_WINDOW_MS: u64 = 300;
+
+// Prefix and suffix of the key combination string of a double-tap
+pub const DOUBLE_TAP_PREFIX: &str = "DoubleTap(";
+pub const DOUBLE_TAP_SUFFIX: &str = ")";
+
+// Time in milliseconds after which a held modifier key is verified against the system
+// when another key is pressed, a missed release would otherwise leak into every key combination
+const SUSPICIOUS_MODIFIER_HOLD_MS: u128 = 2000;
+
+// Time in milliseconds after which a key without any events is considered possibly stuck
+const STALE_KEY_TIMEOUT_MS: u128 = 10000;
+
+// Interval in milliseconds between checks for stuck keys
+const STALE_KEY_CHECK_INTERVAL_MS: u64 = 1000;
+
+// Default key combination resuming paused shortcuts
+const DEFAULT_RESUME_COMBO: &str = "LCtrl+LShft+Pause";
+
+// Default key combination stopping all input simulations
+const DEFAULT_PANIC_COMBO: &str = "LCtrl+LShft+End";
+
+// ID and title of the tray menu item for pausing shortcuts
+pub const PAUSE_TRAY_ITEM_ID: &str = "Pause";
+pub const PAUSE_TRAY_ITEM_TITLE: &str = "Pause Shortcuts";
+
 // Struct for keypress payload
 #[derive(Clone, serde::Serialize)]
 struct PayloadKeypress {
     key: String,
+    character: String,
+    physical_key: String,
+    modifiers: Vec<String>,
+    repeat: bool,
+    scancode: Option<u32>,
+    timestamp: u128,
+}
+
+/**
+ * @brief Function to get the character produced by a key event in the current keyboard layout
+ * @param name - name of the rdev::Event
+ * @return String - the produced character, empty if the key doesn't produce a printable character
+ */
+fn event_character(name: &Option<String>) -> String {
+    match name {
+        Some(name) => {
+            // Only accept a single printable character
+            let mut characters = name.chars();
+            match (characters.next(), characters.next()) {
+                (Some(character), None)
+                    if !character.is_control() && !character.is_whitespace() =>
+                {
+                    character.to_string()
+                }
+                _ => String::new(),
+            }
+        }
+        None => String::new(),
+    }
+}
+
+// Struct for the result of a keybind capture
+#[derive(Clone, serde::Serialize)]
+pub struct KeybindCaptureResult {
+    successful: bool,
+    key: String,
+    character: String,
+    message: String,
+}
+
+// Struct for returning results from setting the resume and panic combos
+#[derive(Clone, serde::Serialize)]
+pub struct ComboResult {
+    successful: bool,
+    message: String,
+}
+
+// Struct for an ongoing keybind capture
+struct KeybindCapture {
+    id: u64,
+    sender: mpsc::Sender<PayloadKeypress>,
+}
+
+// Struct for the state of the keyboard, also the payload of the keyboard state change event
+#[derive(Clone, serde::Serialize)]
+pub struct KeyboardStateSnapshot {
+    pressed_keys: Vec<String>,
+    caps_lock: Option<bool>,
+    num_lock: Option<bool>,
+}
+
+// Struct for the payload of the event notifying about pausing or resuming shortcuts
+#[derive(Clone, serde::Serialize)]
+struct PayloadPaused {
+    paused: bool,
+}
+
+/**
+ * @brief Function to create the key combination string of a double-tap
+ * @param key - key combination that was tapped twice
+ * @return String
+ */
+fn double_tap_key(key: &str) -> String {
+    format!("{}{}{}", DOUBLE_TAP_PREFIX, key, DOUBLE_TAP_SUFFIX)
+}
+
+/**
+ * @brief Function to emit a KeyPress event to the Overfloat window and trigger the shortcuts bound to it
+ * @param handle - tauri::AppHandle
+ * @param payload - payload of the KeyPress event
+ * @note Auto-repeated key presses don't trigger shortcuts
+ */
+fn emit_keypress(handle: &tauri::AppHandle, payload: PayloadKeypress) {
+    if !payload.repeat {
+        trigger_shortcuts(handle, &payload.key, payload.clone());
+    }
+
+    let _ = handle.emit_to("Overfloat", "Overfloat://GlobalKeypress", payload);
+}
+
+/**
+ * @brief Function to notify the windows and the tray menu about pausing or resuming shortcuts
+ * @param handle - tauri::AppHandle
+ * @param paused - whether shortcuts are paused
+ */
+fn emit_paused(handle: &tauri::AppHandle, paused: bool) {
+    let title: String = if paused {
+        format!("{}{}", "✔ ", PAUSE_TRAY_ITEM_TITLE)
+    } else {
+        String::from(PAUSE_TRAY_ITEM_TITLE)
+    };
+    let _ = handle
+        .tray_handle()
+        .get_item(PAUSE_TRAY_ITEM_ID)
+        .set_title(title);
+
+    let _ = handle.emit_all("Overfloat://ShortcutsPaused", PayloadPaused { paused: paused });
 }
 
 // Struct for keyboard state representation
-pub struct KeyboardState {
+struct KeyboardState {
     pressed_keys: HashMap<rdev::Key, bool>,
+    press_times: HashMap<rdev::Key, std::time::Instant>,
+    bind_by_character: bool,
+    capture: Option<KeybindCapture>,
+    last_capture_id: u64,
+    modifier_tap: Vec<String>,
+    last_tap: Option<(String, u128)>,
+    double_tap_window_ms: u64,
+    paused: bool,
+    resume_combo: String,
+    panic_combo: String,
+    state_event_windows: HashSet<String>,
+    // Cached states of the lock keys, refreshed on lock key events and when queried
+    lock_states: Option<LockStates>,
 }
 
 // Implementation of KeyboardState
 impl KeyboardState {
-    pub fn new() -> KeyboardState {
+    fn new() -> KeyboardState {
         KeyboardState {
             pressed_keys: HashMap::new(),
+            press_times: HashMap::new(),
+            bind_by_character: false,
+            capture: None,
+            last_capture_id: 0,
+            modifier_tap: Vec::new(),
+            last_tap: None,
+            double_tap_window_ms: DEFAULT_DOUBLE_TAP_WINDOW_MS,
+            paused: false,
+            resume_combo: String::from(DEFAULT_RESUME_COMBO),
+            panic_combo: String::from(DEFAULT_PANIC_COMBO),
+            state_event_windows: HashSet::new(),
+            lock_states: None,
         }
     }
 
     /**
-     * @brief Function to handle key down event
+     * @brief Function to start capturing the next key combination
+     * @param sender - channel to send the captured key combinations to
+     * @return u64 - ID of the capture
+     * @note An ongoing capture is cancelled by dropping its sender
+     */
+    fn start_capture(&mut self, sender: mpsc::Sender<PayloadKeypress>) -> u64 {
+        self.last_capture_id += 1;
+        self.capture = Some(KeybindCapture {
+            id: self.last_capture_id,
+            sender: sender,
+        });
+
+        self.last_capture_id
+    }
+
+    /**
+     * @brief Function to stop capturing key combinations
+     * @param id - ID of the capture to stop, None stops any ongoing capture
+     */
+    fn stop_capture(&mut self, id: Option<u64>) {
+        match (&self.capture, id) {
+            (Some(capture), Some(id)) if capture.id != id => {}
+            _ => self.capture = None,
+        }
+    }
+
+    /**
+     * @brief Function to get the names of currently pressed modifier keys
+     * @return Vec<String> - names of the pressed modifier keys in the order of RDEV_MODIFIER_KEYS
+     */
+    fn pressed_modifiers(&self) -> Vec<String> {
+        RDEV_MODIFIER_KEYS
+            .iter()
+            .filter(|modifier_key| *self.pressed_keys.get(modifier_key).unwrap_or(&false))
+            .map(|modifier_key| key_to_string(*modifier_key))
+            .collect()
+    }
+
+    /**
+     * @brief Function to check whether any non-modifier key is currently pressed
+     * @return bool
+     */
+    fn non_modifier_pressed(&self) -> bool {
+        self.pressed_keys
+            .iter()
+            .any(|(key, pressed)| *pressed && !RDEV_MODIFIER_KEYS.contains(key))
+    }
+
+    /**
+     * @brief Function to get the names of currently pressed keys
+     * @return Vec<String> - sorted names of the pressed keys
+     */
+    fn pressed_key_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .pressed_keys
+            .iter()
+            .filter(|(_, pressed)| **pressed)
+            .map(|(key, _)| key_to_string(*key))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /**
+     * @brief Function to refresh the cached states of the lock keys from the system
+     */
+    fn refresh_lock_states(&mut self) {
+        self.lock_states = query_lock_states();
+    }
+
+    /**
+     * @brief Function to get the current state of the keyboard
+     * @return KeyboardStateSnapshot
+     * @note The lock key states are cached, they are None if the system can't be queried
+     */
+    fn snapshot(&self) -> KeyboardStateSnapshot {
+        KeyboardStateSnapshot {
+            pressed_keys: self.pressed_key_names(),
+            caps_lock: self.lock_states.map(|states| states.caps_lock),
+            num_lock: self.lock_states.map(|states| states.num_lock),
+        }
+    }
+
+    /**
+     * @brief Function to notify the subscribed windows about a change of the keyboard state
      * @param handle - tauri::AppHandle
+     * @note Windows that no longer exist are unsubscribed
+     */
+    fn emit_state_change(&mut self, handle: &tauri::AppHandle) {
+        self.state_event_windows
+            .retain(|label| handle.get_window(label).is_some());
+        if self.state_event_windows.is_empty() {
+            return;
+        }
+
+        let snapshot: KeyboardStateSnapshot = self.snapshot();
+        for label in &self.state_event_windows {
+            let _ = handle.emit_to(label, "Overfloat://KeyboardState", snapshot.clone());
+        }
+    }
+
+    /**
+     * @brief Function to set a key as released
      * @param key - rdev::Key
      */
-    fn keydown(&mut self, handle: tauri::AppHandle, key: rdev::Key) {
-        // Check if key is already pressed
-        let pressed_keys = &mut self.pressed_keys;
-        let pressed = pressed_keys.entry(key).or_insert(false);
-
-        // If key is not pressed
-        if !*pressed {
-            // Set key as pressed
-            *pressed = true;
-
-            // If key is not modifier key
-            if !RDEV_MODIFIER_KEYS.contains(&key) {
-                // If the key is unknown, return
-                match key {
-                    rdev::Key::Unknown(_) => return,
-                    _ => {}
-                }
+    fn release_key(&mut self, key: rdev::Key) {
+        self.pressed_keys.insert(key, false);
+        self.press_times.remove(&key);
+    }
 
-                // Create string to hold resulting key combination
-                let mut result_string: String = String::new();
-
-                // Iterate over all modifier keys
-                for modifier_key in RDEV_MODIFIER_KEYS {
-                    match pressed_keys.get(&modifier_key) {
-                        // If modifier key is pressed, add it to the resulting string
-                        Some(modifier_pressed) => {
-                            if *modifier_pressed {
-                                result_string =
-                                    format!("{}{}+", result_string, key_to_string(modifier_key));
-                            }
-                        }
-                        None => {}
-                    }
+    /**
+     * @brief Function to get the keys that have been marked as pressed for longer than given time
+     * @param held_ms - minimum time since the last event of the key in milliseconds
+     * @param modifiers_only - whether to only consider modifier keys
+     * @return Vec<rdev::Key>
+     */
+    fn keys_held_longer_than(&self, held_ms: u128, modifiers_only: bool) -> Vec<rdev::Key> {
+        self.pressed_keys
+            .iter()
+            .filter(|(key, pressed)| **pressed && (!modifiers_only || RDEV_MODIFIER_KEYS.contains(key)))
+            .filter(|(key, _)| match self.press_times.get(key) {
+                Some(press_time) => press_time.elapsed().as_millis() > held_ms,
+                None => true,
+            })
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /**
+     * @brief Function to resynchronize the pressed keys with the state of the keyboard reported by the system
+     * @param keys - keys to verify, all pressed keys if None
+     * @return bool - whether the system could be queried for the state of the keyboard
+     * @note Keys that the system reports as released are set as released, this fixes keys
+     * stuck as pressed because their release event was missed (e.g. focus change, lock screen)
+     */
+    fn resync(&mut self, keys: Option<Vec<rdev::Key>>) -> bool {
+        // Get the keys to verify and their keycodes
+        let keys: Vec<(rdev::Key, u32)> = keys
+            .unwrap_or_else(|| {
+                self.pressed_keys
+                    .iter()
+                    .filter(|(_, pressed)| **pressed)
+                    .map(|(key, _)| *key)
+                    .collect()
+            })
+            .into_iter()
+            .filter_map(|key| key_to_code(key).map(|code| (key, code)))
+            .collect();
+
+        if keys.is_empty() {
+            return true;
+        }
+
+        // Query the system for the state of the keys
+        let codes: Vec<u32> = keys.iter().map(|(_, code)| *code).collect();
+        let states: Vec<bool> = match query_pressed_keys(&codes) {
+            Some(states) => states,
+            None => return false,
+        };
+
+        // Release the keys that aren't pressed anymore
+        let mut released: bool = false;
+        for ((key, _), pressed) in keys.iter().zip(states) {
+            if !pressed {
+                self.release_key(*key);
+                released = true;
+            }
+        }
+
+        // A released key means the modifier-only tap in progress is not valid
+        if released {
+            self.modifier_tap.clear();
+        }
+
+        true
+    }
+
+    /**
+     * @brief Function to check for keys that may be stuck as pressed
+     * @note Keys held without any events for longer than STALE_KEY_TIMEOUT_MS are verified
+     * against the system, if the system can't be queried they are released
+     */
+    fn check_stale_keys(&mut self) {
+        let stale_keys: Vec<rdev::Key> = self.keys_held_longer_than(STALE_KEY_TIMEOUT_MS, false);
+        if stale_keys.is_empty() {
+            return;
+        }
+
+        if self.resync(Some(stale_keys.clone())) {
+            // Keys that are really held are checked again after another timeout
+            for key in stale_keys {
+                if *self.pressed_keys.get(&key).unwrap_or(&false) {
+                    self.press_times.insert(key, std::time::Instant::now());
                 }
+            }
+        } else {
+            for key in stale_keys {
+                self.release_key(key);
+            }
+            self.modifier_tap.clear();
+        }
+    }
+
+    /**
+     * @brief Function to check whether a completed key combination is the panic combo
+     * @param payload - payload describing the key combination
+     * @return bool
+     * @note Additional held modifiers are allowed, a running simulation may be holding some
+     */
+    fn is_panic_combo(&self, payload: &PayloadKeypress) -> bool {
+        keybind_matches(&self.panic_combo, &payload.key, true)
+    }
+
+    /**
+     * @brief Function to dispatch a completed key combination
+     * @param handle - tauri::AppHandle
+     * @param payload - payload describing the key combination
+     * @note The panic combo stops all input simulations in any state and is not dispatched.
+     * While capturing, the key combination is sent to the capture instead of being dispatched.
+     * While paused, only the resume combo is handled
+     */
+    fn dispatch(&mut self, handle: tauri::AppHandle, payload: PayloadKeypress) {
+        // Stop all input simulations before anything else, this has to work even if a simulation went wrong
+        if !payload.repeat && self.is_panic_combo(&payload) {
+            let cancelled: usize = cancel_all_simulations();
+            println!("Emergency stop, cancelled {} input simulation(s)", cancelled);
+            return;
+        }
 
-                // Add pressed key to the resulting string
-                result_string = format!("{}{}", result_string, key_to_string(key));
-                
-                // Emit a KeyPress event to the Overfloat window
-                handle
-                    .emit_to(
-                        "Overfloat",
-                        "Overfloat://GlobalKeypress",
-                        PayloadKeypress { key: result_string },
-                    )
-                    .unwrap();
+        // If capturing a key combination, send it to the capture instead of dispatching it
+        if let Some(capture) = &self.capture {
+            if !payload.repeat {
+                let _ = capture.sender.send(payload);
             }
+            return;
+        }
+
+        // If paused, suppress the key combination unless it resumes the shortcuts
+        if self.paused {
+            if !payload.repeat && keybind_matches(&self.resume_combo, &payload.key, false) {
+                self.set_paused(&handle, false);
+            }
+            return;
+        }
+
+        // Auto-repeated key presses neither start nor finish a double-tap
+        if payload.repeat {
+            emit_keypress(&handle, payload);
+            return;
+        }
+
+        // Check if the same key combination was completed within the double-tap window
+        let double_tap: bool = match &self.last_tap {
+            Some((key, timestamp)) => {
+                *key == payload.key
+                    && payload.timestamp.saturating_sub(*timestamp)
+                        <= self.double_tap_window_ms as u128
+            }
+            None => false,
+        };
+
+        if double_tap {
+            // Emit the second tap followed by the double-tap,
+            // forget the tap so a third tap starts a new double-tap
+            self.last_tap = None;
+            let mut double_tap_payload: PayloadKeypress = payload.clone();
+            double_tap_payload.key = double_tap_key(&payload.key);
+
+            emit_keypress(&handle, payload);
+            emit_keypress(&handle, double_tap_payload);
+        } else {
+            self.last_tap = Some((payload.key.clone(), payload.timestamp));
+            emit_keypress(&handle, payload);
         }
     }
 
+    /**
+     * @brief Function to handle key down event
+     * @param handle - tauri::AppHandle
+     * @param key - rdev::Key
+     * @param name - name of the rdev::Event, character produced by the key in the current layout
+     * @param time - time of the rdev::Event
+     */
+    fn keydown(
+        &mut self,
+        handle: tauri::AppHandle,
+        key: rdev::Key,
+        name: Option<String>,
+        time: std::time::SystemTime,
+    ) {
+        // Check if key is already pressed, a key down event of a pressed key is an auto-repeat
+        let pressed = self.pressed_keys.entry(key).or_insert(false);
+        let repeat: bool = *pressed;
+
+        // Set key as pressed
+        *pressed = true;
+        self.press_times.insert(key, std::time::Instant::now());
+
+        // Wake up the input simulation scripts waiting for the key
+        if !repeat {
+            script::key_pressed(key);
+        }
+
+        // If key is modifier key, remember the held modifiers for a modifier-only tap and return
+        if is_modifier_key(key) {
+            if !repeat && !self.non_modifier_pressed() {
+                self.modifier_tap = self.pressed_modifiers();
+            }
+            return;
+        }
+
+        // Any other key cancels the modifier-only tap
+        self.modifier_tap.clear();
+
+        // Verify modifier keys held for a long time, their release may have been missed
+        if !repeat {
+            let held_modifiers: Vec<rdev::Key> =
+                self.keys_held_longer_than(SUSPICIOUS_MODIFIER_HOLD_MS, true);
+            if !held_modifiers.is_empty() {
+                self.resync(Some(held_modifiers));
+            }
+        }
+
+        // Get the pressed modifier keys and the character produced by the key in the current layout
+        let modifiers: Vec<String> = self.pressed_modifiers();
+        let character: String = event_character(&name);
+        let physical_key: String = key_to_string(key);
+
+        // Create string to hold resulting key combination, starting with the modifier keys
+        let mut result_string: String = String::new();
+        for modifier in &modifiers {
+            result_string = format!("{}{}+", result_string, modifier);
+        }
+
+        // Add pressed key to the resulting string,
+        // use the produced character instead of the physical key if binding by character,
+        // except for the separator of key combinations, which couldn't be parsed back
+        if self.bind_by_character && !character.is_empty() && character != "+" {
+            // Letters are uppercased like key names, unless the uppercase form has more characters (e.g. ß is SS)
+            let uppercase: String = character.to_uppercase();
+            if uppercase.chars().count() == 1 {
+                result_string = format!("{}{}", result_string, uppercase);
+            } else {
+                result_string = format!("{}{}", result_string, character);
+            }
+        } else {
+            result_string = format!("{}{}", result_string, physical_key);
+        }
+
+        // Dispatch the key combination
+        self.dispatch(
+            handle,
+            PayloadKeypress {
+                key: result_string,
+                character: character,
+                physical_key: physical_key,
+                modifiers: modifiers,
+                repeat: repeat,
+                scancode: key_to_code(key),
+                timestamp: time
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis(),
+            },
+        );
+    }
+
     /**
      * @brief Function to handle key up event
-     * @param _handle - tauri::AppHandle
+     * @param handle - tauri::AppHandle
      * @param key - rdev::Key
+     * @param time - time of the rdev::Event
      */
-    fn keyup(&mut self, _handle: tauri::AppHandle, key: rdev::Key) {
+    fn keyup(&mut self, handle: tauri::AppHandle, key: rdev::Key, time: std::time::SystemTime) {
         // Check if key is pressed
-        let pressed = self.pressed_keys.entry(key).or_insert(true);
+        let pressed: bool = *self.pressed_keys.get(&key).unwrap_or(&false);
+
+        // Set key as not pressed
+        self.release_key(key);
 
-        // If key is pressed set it as not pressed
-        if *pressed {
-            *pressed = false;
+        // Release of a key that isn't pressed means events were missed, resynchronize the other keys
+        if !pressed {
+            self.resync(None);
+        }
+
+        // Releasing a modifier key with no other key pressed in between completes a modifier-only tap
+        if is_modifier_key(key) && !self.modifier_tap.is_empty() {
+            let modifiers: Vec<String> = std::mem::take(&mut self.modifier_tap);
+
+            self.dispatch(
+                handle,
+                PayloadKeypress {
+                    key: modifiers.join("+"),
+                    character: String::new(),
+                    physical_key: key_to_string(key),
+                    modifiers: modifiers,
+                    repeat: false,
+                    scancode: key_to_code(key),
+                    timestamp: time
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis(),
+                },
+            );
         }
     }
 
@@ -229,12 +649,352 @@ impl KeyboardState {
      * @brief Function to handle key press event
      * @param handle - tauri::AppHandle
      * @param event - rdev::Event
+     * @param simulated - whether the event was simulated by an input simulation
+     * @note Mouse button presses are handled as well, since they cancel a modifier-only tap
      */
-    pub fn handle_key_press_event(&mut self, handle: tauri::AppHandle, event: rdev::Event) {
+    fn handle_key_press_event(&mut self, handle: tauri::AppHandle, event: rdev::Event, simulated: bool) {
+        // Watch the typed text for hotstrings unless shortcuts are paused or a key combination is being captured,
+        // text typed by simulations (including hotstring replacements) isn't watched
+        if !self.paused && self.capture.is_none() && !simulated {
+            let command_modifier_held: bool = COMMAND_MODIFIER_KEYS
+                .iter()
+                .any(|key| self.pressed_keys.get(key) == Some(&true));
+            hotstrings::handle_event(&handle, &event, command_modifier_held);
+        }
+
+        // Remember the pressed keys and lock states to detect changes of the keyboard state
+        let previous: Option<(Vec<String>, Option<LockStates>)> = if self.state_event_windows.is_empty() {
+            None
+        } else {
+            Some((self.pressed_key_names(), self.lock_states))
+        };
+
         match event.event_type {
-            rdev::EventType::KeyPress(key) => self.keydown(handle, key),
-            rdev::EventType::KeyRelease(key) => self.keyup(handle, key),
+            rdev::EventType::KeyPress(key) => self.keydown(handle.clone(), key, event.name, event.time),
+            rdev::EventType::KeyRelease(key) => self.keyup(handle.clone(), key, event.time),
+            rdev::EventType::ButtonPress(_) => self.modifier_tap.clear(),
             _ => {}
         }
+
+        // Lock keys toggle on press, the new state may only be visible to the system after the event (e.g. on Windows),
+        // so the states are refreshed on both the press and the release
+        if let rdev::EventType::KeyPress(key) | rdev::EventType::KeyRelease(key) = event.event_type {
+            if LOCK_KEYS.contains(&key) {
+                self.refresh_lock_states();
+            }
+        }
+
+        if let Some((previous_keys, previous_lock_states)) = previous {
+            if previous_keys != self.pressed_key_names() || previous_lock_states != self.lock_states {
+                self.emit_state_change(&handle);
+            }
+        }
+    }
+
+    /**
+     * @brief Function to set whether key combinations use the produced character instead of the physical key
+     * @param enabled - true to bind by character, false to bind by physical key position
+     */
+    fn set_bind_by_character(&mut self, enabled: bool) {
+        self.bind_by_character = enabled;
+    }
+
+    /**
+     * @brief Function to set the time window for detecting a double-tap
+     * @param window_ms - maximum time between the two taps in milliseconds
+     */
+    fn set_double_tap_window(&mut self, window_ms: u64) {
+        self.double_tap_window_ms = window_ms;
+    }
+
+    /**
+     * @brief Function to pause or resume dispatching of shortcuts
+     * @param handle - tauri::AppHandle
+     * @param paused - true to pause, false to resume
+     */
+    fn set_paused(&mut self, handle: &tauri::AppHandle, paused: bool) {
+        if self.paused == paused {
+            return;
+        }
+
+        self.paused = paused;
+
+        // A double-tap can't span pausing or resuming
+        self.last_tap = None;
+        emit_paused(handle, paused);
+
+        // Release or restore the grabs of consuming shortcuts
+        request_grab_update();
+    }
+
+    /**
+     * @brief Function to set the key combination resuming paused shortcuts
+     * @param combo - key combination string
+     * @return Result<(), String> - error message if the key combination is invalid, the previous one is kept then
+     */
+    fn set_resume_combo(&mut self, combo: String) -> Result<(), String> {
+        validate_combo(&combo)?;
+        self.resume_combo = combo;
+        Ok(())
+    }
+
+    /**
+     * @brief Function to set the key combination stopping all input simulations
+     * @param combo - key combination string
+     * @return Result<(), String> - error message if the key combination is invalid, the previous one is kept then
+     */
+    fn set_panic_combo(&mut self, combo: String) -> Result<(), String> {
+        validate_combo(&combo)?;
+        self.panic_combo = combo;
+        Ok(())
     }
 }
+
+/**
+ * @brief Function to validate the resume or the panic combo
+ * @param combo - key combination string
+ * @return Result<(), String> - error message if the key combination is invalid
+ * @note Double-taps are not allowed, the combos are handled before double-taps are detected
+ */
+fn validate_combo(combo: &str) -> Result<(), String> {
+    if combo.starts_with(DOUBLE_TAP_PREFIX) {
+        return Err(String::from("Double-taps can't be used"));
+    }
+    validate_keybind(combo)
+}
+
+/**
+ * @brief Function to check whether a key is a modifier key
+ * @param key - rdev::Key
+ * @return bool
+ */
+pub fn is_modifier_key(key: rdev::Key) -> bool {
+    RDEV_MODIFIER_KEYS.contains(&key)
+}
+
+/**
+ * @brief Function to handle key press event
+ * @param handle - tauri::AppHandle
+ * @param event - rdev::Event
+ * @param simulated - whether the event was simulated by an input simulation
+ * @note Exposes the operation to the keyboard listener. Remapped keys are replaced by their targets
+ * and are not handled further
+ */
+pub fn handle_key_press_event(handle: tauri::AppHandle, event: rdev::Event, simulated: bool) {
+    if remap::handle_event(&event.event_type) {
+        return;
+    }
+
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    keyboard_state.handle_key_press_event(handle, event, simulated);
+}
+
+/**
+ * @brief Function to get the current state of the keyboard
+ * @return KeyboardStateSnapshot - pressed keys and states of the lock keys
+ * @note Exposes the operation to tauri commands
+ */
+pub fn query_keyboard_state() -> KeyboardStateSnapshot {
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    keyboard_state.refresh_lock_states();
+    keyboard_state.snapshot()
+}
+
+/**
+ * @brief Function to check whether any key is currently pressed
+ * @return bool
+ * @note Exposes the operation to input simulations
+ */
+pub fn any_key_pressed() -> bool {
+    let keyboard_state = get_keyboard_state().lock().unwrap();
+    keyboard_state.pressed_keys.values().any(|pressed| *pressed)
+}
+
+/**
+ * @brief Function to subscribe or unsubscribe a window to keyboard state change events
+ * @param window_label - label of the window
+ * @param enabled - true to subscribe, false to unsubscribe
+ * @note Exposes the operation to tauri commands, the lock states are refreshed on subscribing
+ * since they are only tracked by the lock key events
+ */
+pub fn set_keyboard_state_events(window_label: &str, enabled: bool) {
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    if enabled {
+        keyboard_state.refresh_lock_states();
+        keyboard_state.state_event_windows.insert(window_label.to_string());
+    } else {
+        keyboard_state.state_event_windows.remove(window_label);
+    }
+}
+
+/**
+ * @brief Function to start a thread periodically checking for keys stuck as pressed
+ */
+pub fn start_stale_key_watcher() {
+    std::thread::spawn(|| loop {
+        std::thread::sleep(std::time::Duration::from_millis(STALE_KEY_CHECK_INTERVAL_MS));
+        get_keyboard_state().lock().unwrap().check_stale_keys();
+    });
+}
+
+/**
+ * @brief Function to set whether key combinations use the produced character instead of the physical key
+ * @param enabled - true to bind by character, false to bind by physical key position
+ * @note Exposes the operation to tauri commands
+ */
+pub fn set_bind_by_character(enabled: bool) {
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    keyboard_state.set_bind_by_character(enabled);
+}
+
+/**
+ * @brief Function to set the time window for detecting a double-tap
+ * @param window_ms - maximum time between the two taps in milliseconds
+ * @note Exposes the operation to tauri commands
+ */
+pub fn set_double_tap_window(window_ms: u64) {
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    keyboard_state.set_double_tap_window(window_ms);
+}
+
+/**
+ * @brief Function to pause or resume dispatching of shortcuts
+ * @param handle - tauri::AppHandle
+ * @param paused - true to pause, false to resume
+ * @note Exposes the operation to tauri commands
+ */
+pub fn set_paused(handle: tauri::AppHandle, paused: bool) {
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    keyboard_state.set_paused(&handle, paused);
+}
+
+/**
+ * @brief Function to toggle between pausing and resuming dispatching of shortcuts
+ * @param handle - tauri::AppHandle
+ * @note Exposes the operation to the tray menu
+ */
+pub fn toggle_paused(handle: tauri::AppHandle) {
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    let paused: bool = !keyboard_state.paused;
+    keyboard_state.set_paused(&handle, paused);
+}
+
+/**
+ * @brief Function to get whether dispatching of shortcuts is paused
+ * @return bool
+ * @note Exposes the operation to tauri commands
+ */
+pub fn is_paused() -> bool {
+    get_keyboard_state().lock().unwrap().paused
+}
+
+/**
+ * @brief Function to set the key combination resuming paused shortcuts
+ * @param combo - key combination string
+ * @return ComboResult - unsuccessful if the key combination is invalid
+ * @note Exposes the operation to tauri commands
+ */
+pub fn set_resume_combo(combo: String) -> ComboResult {
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    match keyboard_state.set_resume_combo(combo) {
+        Ok(()) => ComboResult {
+            successful: true,
+            message: String::new(),
+        },
+        Err(message) => ComboResult {
+            successful: false,
+            message: message,
+        },
+    }
+}
+
+/**
+ * @brief Function to set the key combination stopping all input simulations
+ * @param combo - key combination string
+ * @return ComboResult - unsuccessful if the key combination is invalid
+ * @note Exposes the operation to tauri commands
+ */
+pub fn set_panic_combo(combo: String) -> ComboResult {
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    match keyboard_state.set_panic_combo(combo) {
+        Ok(()) => ComboResult {
+            successful: true,
+            message: String::new(),
+        },
+        Err(message) => ComboResult {
+            successful: false,
+            message: message,
+        },
+    }
+}
+
+/**
+ * @brief Function to capture the next complete key combination
+ * @param timeout_ms - time to wait for the key combination in milliseconds, None waits indefinitely
+ * @return KeybindCaptureResult - the captured key combination, or the reason why none was captured
+ * @note Shortcuts are not dispatched while capturing. Completing the same key combination
+ * again within the double-tap window captures a double-tap of it
+ */
+pub async fn capture_keybind(timeout_ms: Option<u64>) -> KeybindCaptureResult {
+    // Start the capture, this cancels any ongoing capture
+    let (sender, receiver) = mpsc::channel::<PayloadKeypress>();
+    let (id, double_tap_window_ms) = {
+        let mut keyboard_state = get_keyboard_state().lock().unwrap();
+        (keyboard_state.start_capture(sender), keyboard_state.double_tap_window_ms)
+    };
+
+    // Wait for the key combination on a blocking thread
+    let received = tauri::async_runtime::spawn_blocking(
+        move || -> Result<(String, String), mpsc::RecvTimeoutError> {
+            // Wait for the first key combination
+            let first: PayloadKeypress = match timeout_ms {
+                Some(timeout_ms) => {
+                    receiver.recv_timeout(std::time::Duration::from_millis(timeout_ms))?
+                }
+                None => receiver
+                    .recv()
+                    .map_err(|_| mpsc::RecvTimeoutError::Disconnected)?,
+            };
+
+            // Wait for the same key combination to be completed again as a double-tap
+            match receiver.recv_timeout(std::time::Duration::from_millis(double_tap_window_ms)) {
+                Ok(second) if second.key == first.key => Ok((double_tap_key(&first.key), String::new())),
+                _ => Ok((first.key, first.character)),
+            }
+        },
+    )
+    .await;
+
+    // Stop the capture if it is still ongoing
+    get_keyboard_state().lock().unwrap().stop_capture(Some(id));
+
+    // Handle the result of the capture
+    let message: &str = match received {
+        Ok(Ok((key, character))) => {
+            return KeybindCaptureResult {
+                successful: true,
+                key: key,
+                character: character,
+                message: String::new(),
+            }
+        }
+        Ok(Err(mpsc::RecvTimeoutError::Timeout)) => "Capture timed out",
+        _ => "Capture cancelled",
+    };
+
+    KeybindCaptureResult {
+        successful: false,
+        key: String::new(),
+        character: String::new(),
+        message: String::from(message),
+    }
+}
+
+/**
+ * @brief Function to cancel the ongoing keybind capture
+ * @note Exposes the operation to tauri commands
+ */
+pub fn cancel_keybind_capture() {
+    let mut keyboard_state = get_keyboard_state().lock().unwrap();
+    keyboard_state.stop_capture(None);
+}
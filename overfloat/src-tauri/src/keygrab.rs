@@ -0,0 +1,427 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/keygrab.rs                         *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+// Interval in milliseconds between checks for focus changes and update requests
+#[cfg(target_os = "linux")]
+const EVENT_POLL_INTERVAL_MS: u64 = 20;
+
+// Maximum time in milliseconds to wait for the key grabber to release the keyboard
+#[cfg(target_os = "linux")]
+const RELEASE_TIMEOUT_MS: u64 = 100;
+
+// Modifier of key combinations grabbed regardless of the held modifiers
+const ANY_MODIFIER: &str = "Any";
+
+/**
+ * @brief Function to create the key combination string grabbing a key regardless of the held modifiers
+ * @param key - name of the key
+ * @return String
+ */
+pub fn any_modifier_combo(key: &str) -> String {
+    format!("{}+{}", ANY_MODIFIER, key)
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{ANY_MODIFIER, EVENT_POLL_INTERVAL_MS, RELEASE_TIMEOUT_MS};
+    use crate::keybinds::is_paused;
+    use crate::keys::{key_to_code, string_to_key};
+    use crate::remap::grabbed_sources;
+    use crate::shortcuts::consumed_keybinds;
+    use lazy_static::lazy_static;
+    use std::collections::{HashMap, HashSet};
+    use std::ffi::CString;
+    use std::os::raw::{c_int, c_uint};
+    use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+    use std::sync::{mpsc, Mutex};
+    use x11::xlib;
+
+    /*
+    * Lazily initialized global state of the key grabber shared with the keyboard listener
+    * GRABBED holds the currently grabbed key combinations,
+    * UPDATE_SENDER wakes up the key grabber to update the grabs immediately
+    */
+    lazy_static! {
+        static ref GRABBED: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+        static ref UPDATE_SENDER: Mutex<Option<mpsc::Sender<GrabRequest>>> = Mutex::new(None);
+    }
+
+    /*
+    * Lazily initialized global state of the X11 error handling
+    * OWN_DISPLAYS holds the connections to the X server opened by Overfloat, stored as addresses,
+    * PREVIOUS_ERROR_HANDLER holds the error handler that was installed before, e.g. by GDK
+    */
+    lazy_static! {
+        static ref OWN_DISPLAYS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+        static ref PREVIOUS_ERROR_HANDLER: Mutex<Option<ErrorHandler>> = Mutex::new(None);
+    }
+
+    // Type of X11 error handlers
+    type ErrorHandler = unsafe extern "C" fn(*mut xlib::Display, *mut xlib::XErrorEvent) -> c_int;
+
+    // Request sent to the key grabber
+    enum GrabRequest {
+        // Update the grabbed key combinations immediately
+        Update,
+        // Release the active keyboard grab, the sender is notified once it is released
+        ReleaseKeyboard(mpsc::Sender<()>),
+    }
+
+    // Connection to the X server used for grabbing, errors on it mean a grab failed
+    static GRAB_DISPLAY: AtomicPtr<xlib::Display> = AtomicPtr::new(std::ptr::null_mut());
+    static GRAB_FAILED: AtomicBool = AtomicBool::new(false);
+
+    // Lock modifiers, key combinations are grabbed with every combination of them
+    // so they are consumed regardless of the state of CapsLock and NumLock
+    const LOCK_MASKS: [c_uint; 4] = [
+        0,
+        xlib::LockMask,
+        xlib::Mod2Mask,
+        xlib::LockMask | xlib::Mod2Mask,
+    ];
+
+    /**
+     * @brief Function to handle X11 errors
+     * @note The default error handler exits the application. Errors on the grabbing connection
+     * are recorded, other errors on the connections of Overfloat (e.g. querying a window that was just destroyed)
+     * are ignored. Errors on other connections, e.g. the one of the UI, are passed to the previous error handler
+     */
+    unsafe extern "C" fn handle_x_error(display: *mut xlib::Display, event: *mut xlib::XErrorEvent) -> c_int {
+        if display == GRAB_DISPLAY.load(Ordering::SeqCst) {
+            GRAB_FAILED.store(true, Ordering::SeqCst);
+            return 0;
+        }
+
+        if OWN_DISPLAYS.lock().unwrap().contains(&(display as usize)) {
+            return 0;
+        }
+
+        let previous_handler: Option<ErrorHandler> = *PREVIOUS_ERROR_HANDLER.lock().unwrap();
+        match previous_handler {
+            Some(previous_handler) => previous_handler(display, event),
+            None => 0,
+        }
+    }
+
+    /**
+     * @brief Function to install the X11 error handler, errors on other connections are passed to the previous one
+     * @note Has to be called once on startup, before any connection of Overfloat is used
+     */
+    pub fn install_x_error_handler() {
+        unsafe {
+            *PREVIOUS_ERROR_HANDLER.lock().unwrap() = xlib::XSetErrorHandler(Some(handle_x_error));
+        }
+    }
+
+    /**
+     * @brief Function to register a connection to the X server opened by Overfloat, its errors are ignored
+     * @param display - connection to the X server
+     */
+    pub fn register_display(display: *mut xlib::Display) {
+        OWN_DISPLAYS.lock().unwrap().push(display as usize);
+    }
+
+    /**
+     * @brief Function to unregister a connection to the X server before it is closed
+     * @param display - connection to the X server
+     * @note Pending requests should be synchronized first, so their errors are still ignored
+     */
+    pub fn unregister_display(display: *mut xlib::Display) {
+        OWN_DISPLAYS.lock().unwrap().retain(|own| *own != display as usize);
+    }
+
+    /**
+     * @brief Function to convert a key combination string to a keycode and X11 modifier mask
+     * @param combo - key combination string
+     * @return Option<(c_int, c_uint)> - None if the key combination can't be grabbed,
+     * e.g. double-taps and modifier-only taps
+     * @note X11 doesn't distinguish left and right modifiers, grabbing LCtrl+F3 grabs RCtrl+F3 as well
+     */
+    fn combo_to_grab(combo: &str) -> Option<(c_int, c_uint)> {
+        let mut parts: Vec<&str> = combo.split('+').collect();
+        let key: &str = parts.pop()?;
+
+        // Get the modifier mask
+        let mut mask: c_uint = 0;
+        for modifier in parts {
+            mask |= match modifier {
+                ANY_MODIFIER => xlib::AnyModifier,
+                "Ctrl" | "LCtrl" | "RCtrl" => xlib::ControlMask,
+                "Shft" | "LShft" | "RShft" => xlib::ShiftMask,
+                "Alt" => xlib::Mod1Mask,
+                "AltGr" => xlib::Mod5Mask,
+                "Meta" | "LMeta" | "RMeta" => xlib::Mod4Mask,
+                _ => return None,
+            };
+        }
+
+        // Get the keycode of the key, modifier keys alone can't be grabbed
+        let key: rdev::Key = string_to_key(key).ok()?;
+        if crate::keybinds::is_modifier_key(key) {
+            return None;
+        }
+
+        Some((key_to_code(key)? as c_int, mask))
+    }
+
+    /**
+     * @brief Function to grab or ungrab a key combination on the root window
+     * @param display - connection to the X server
+     * @param grab - (keycode, modifier mask) of the key combination
+     * @param grab_key - true to grab, false to ungrab
+     * @return bool - whether the operation was successful
+     */
+    unsafe fn set_grab(display: *mut xlib::Display, grab: (c_int, c_uint), grab_key: bool) -> bool {
+        let root: xlib::Window = xlib::XDefaultRootWindow(display);
+        GRAB_FAILED.store(false, Ordering::SeqCst);
+
+        // Grabs with any modifier already include the lock modifiers
+        let lock_masks: &[c_uint] = if grab.1 & xlib::AnyModifier != 0 {
+            &LOCK_MASKS[..1]
+        } else {
+            &LOCK_MASKS
+        };
+
+        for lock_mask in lock_masks {
+            let modifiers: c_uint = if grab.1 & xlib::AnyModifier != 0 {
+                xlib::AnyModifier
+            } else {
+                grab.1 | lock_mask
+            };
+
+            if grab_key {
+                xlib::XGrabKey(
+                    display,
+                    grab.0,
+                    modifiers,
+                    root,
+                    xlib::False,
+                    xlib::GrabModeAsync,
+                    xlib::GrabModeAsync,
+                );
+            } else {
+                xlib::XUngrabKey(display, grab.0, modifiers, root);
+            }
+        }
+
+        // Wait for the X server to process the requests, failures are reported to the error handler
+        xlib::XSync(display, xlib::False);
+        !GRAB_FAILED.load(Ordering::SeqCst)
+    }
+
+    /**
+     * @brief Function to check whether a key combination is currently grabbed
+     * @param combo - key combination string
+     * @return bool
+     */
+    pub fn is_grabbed(combo: &str) -> bool {
+        GRABBED.lock().unwrap().contains(combo)
+    }
+
+    /**
+     * @brief Function to check whether key combinations can be grabbed on this platform
+     * @return bool - always true, key combinations are grabbed on the X server
+     */
+    pub fn grabbing_supported() -> bool {
+        true
+    }
+
+    /**
+     * @brief Function to wake up the key grabber to update the grabbed key combinations immediately
+     */
+    pub fn request_grab_update() {
+        if let Some(sender) = UPDATE_SENDER.lock().unwrap().as_ref() {
+            let _ = sender.send(GrabRequest::Update);
+        }
+    }
+
+    /**
+     * @brief Function to release the active keyboard grab and wait until the X server processed it
+     * @note Pressing a grabbed key activates its grab, so all key events, including the simulated ones,
+     * are delivered to Overfloat until the key is released. Events simulated after the release are
+     * delivered to the focused window. Pressing the grabbed key again, including its auto-repeat, grabs the keyboard again
+     */
+    pub fn release_keyboard_grab() {
+        let sender: Option<mpsc::Sender<GrabRequest>> = UPDATE_SENDER.lock().unwrap().clone();
+        let sender: mpsc::Sender<GrabRequest> = match sender {
+            Some(sender) => sender,
+            None => return,
+        };
+
+        let (released_sender, released_receiver) = mpsc::channel::<()>();
+        if sender.send(GrabRequest::ReleaseKeyboard(released_sender)).is_ok() {
+            let _ = released_receiver.recv_timeout(std::time::Duration::from_millis(RELEASE_TIMEOUT_MS));
+        }
+    }
+
+    /**
+     * @brief Function to start a thread grabbing the key combinations of consuming shortcuts and remapped keys
+     * @note Grabbed key combinations are delivered only to Overfloat, the keyboard listener still
+     * observes them. Key combinations that can't be grabbed are only observed.
+     * No shortcuts are grabbed while shortcuts are paused. The grabs are updated when the focused window
+     * changes and on request, key combinations that failed to be grabbed are retried when the focused window changes
+     */
+    pub fn start_key_grabber() {
+        let (sender, receiver) = mpsc::channel::<GrabRequest>();
+        *UPDATE_SENDER.lock().unwrap() = Some(sender);
+
+        std::thread::spawn(move || unsafe {
+            // Open a connection to the X server
+            let display = xlib::XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                println!("Error: Couldn't connect to the X server, shortcuts won't be consumed");
+                return;
+            }
+            GRAB_DISPLAY.store(display, Ordering::SeqCst);
+
+            // Get notified about focus changes, the window manager sets the focused window as a property of the root window
+            let root: xlib::Window = xlib::XDefaultRootWindow(display);
+            let active_window_name: CString = CString::new("_NET_ACTIVE_WINDOW").unwrap();
+            let active_window_atom: xlib::Atom = xlib::XInternAtom(display, active_window_name.as_ptr(), xlib::False);
+            xlib::XSelectInput(display, root, xlib::PropertyChangeMask);
+
+            let mut grabbed: HashMap<String, (c_int, c_uint)> = HashMap::new();
+            let mut failed: HashSet<String> = HashSet::new();
+            let mut ungrabbable: HashSet<String> = HashSet::new();
+
+            let mut update: bool = true;
+            let mut focus_changed: bool = false;
+
+            loop {
+                if update {
+                    // Get the key combinations that should be grabbed
+                    let mut desired: HashSet<String> = if is_paused() {
+                        HashSet::new()
+                    } else {
+                        consumed_keybinds()
+                    };
+                    desired.extend(grabbed_sources());
+
+                    // Ungrab the key combinations that shouldn't be grabbed anymore
+                    grabbed.retain(|combo, grab| {
+                        if desired.contains(combo) {
+                            return true;
+                        }
+                        set_grab(display, *grab, false);
+                        false
+                    });
+                    failed.retain(|combo| desired.contains(combo));
+
+                    // Grab the new key combinations, failed ones are retried only after a focus change,
+                    // the other client holding the grab may have released it
+                    for combo in desired {
+                        if grabbed.contains_key(&combo)
+                            || ungrabbable.contains(&combo)
+                            || (failed.contains(&combo) && !focus_changed)
+                        {
+                            continue;
+                        }
+
+                        match combo_to_grab(&combo) {
+                            Some(grab) if set_grab(display, grab, true) => {
+                                failed.remove(&combo);
+                                grabbed.insert(combo, grab);
+                            }
+                            Some(grab) => {
+                                // Release the partially successful grabs
+                                set_grab(display, grab, false);
+                                if !failed.contains(&combo) {
+                                    println!("Error: Couldn't grab {}, it will only be observed", combo);
+                                    failed.insert(combo);
+                                }
+                            }
+                            None => {
+                                println!("Error: {} can't be grabbed, it will only be observed", combo);
+                                ungrabbable.insert(combo);
+                            }
+                        }
+                    }
+
+                    // Share the grabbed key combinations with the keyboard listener
+                    *GRABBED.lock().unwrap() = grabbed.keys().cloned().collect();
+
+                    update = false;
+                    focus_changed = false;
+                }
+
+                // Check for focus changes, discard the grabbed key events, the keyboard listener handles them
+                let mut event: xlib::XEvent = std::mem::zeroed();
+                while xlib::XPending(display) > 0 {
+                    xlib::XNextEvent(display, &mut event);
+                    if event.get_type() == xlib::PropertyNotify && event.property.atom == active_window_atom {
+                        update = true;
+                        focus_changed = true;
+                    }
+                }
+
+                if update {
+                    continue;
+                }
+
+                // Wait for a request, or for the next check for focus changes
+                match receiver.recv_timeout(std::time::Duration::from_millis(EVENT_POLL_INTERVAL_MS)) {
+                    Ok(GrabRequest::Update) => update = true,
+                    Ok(GrabRequest::ReleaseKeyboard(released_sender)) => {
+                        xlib::XUngrabKeyboard(display, xlib::CurrentTime);
+                        xlib::XSync(display, xlib::False);
+                        let _ = released_sender.send(());
+                    }
+                    Err(_) => {}
+                }
+            }
+        });
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    /**
+     * @brief Function to check whether a key combination is currently grabbed
+     * @param _combo - key combination string
+     * @return bool - always false, grabbing is not supported on this platform
+     */
+    pub fn is_grabbed(_combo: &str) -> bool {
+        false
+    }
+
+    /**
+     * @brief Function to wake up the key grabber to update the grabbed key combinations immediately
+     */
+    pub fn request_grab_update() {}
+
+    /**
+     * @brief Function to release the active keyboard grab
+     * @note Grabbing is not supported on this platform, there is nothing to release
+     */
+    pub fn release_keyboard_grab() {}
+
+    /**
+     * @brief Function to start a thread grabbing the key combinations of consuming shortcuts
+     * @note Grabbing is not supported on this platform, key combinations are only observed
+     */
+    pub fn start_key_grabber() {}
+
+    /**
+     * @brief Function to check whether key combinations can be grabbed on this platform
+     * @return bool - always false, grabbing is not supported on this platform
+     */
+    pub fn grabbing_supported() -> bool {
+        false
+    }
+
+    /**
+     * @brief Function to install the X11 error handler
+     * @note There is no X server on this platform
+     */
+    pub fn install_x_error_handler() {}
+}
+
+#[cfg(target_os = "linux")]
+pub use platform::{register_display, unregister_display};
+pub use platform::{
+    grabbing_supported, install_x_error_handler, is_grabbed, release_keyboard_grab, request_grab_update,
+    start_key_grabber,
+};
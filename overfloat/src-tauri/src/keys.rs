@@ -0,0 +1,544 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/keys.rs                            *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+use rdev;
+
+/*
+* Registry of all named keys
+* This is the only place where the string representation of a key is defined,
+* both the keyboard listener and the input simulation use it for conversions
+*/
+const KEY_NAMES: [(rdev::Key, &str); 105] = [
+    (rdev::Key::Alt, "Alt"),
+    (rdev::Key::AltGr, "AltGr"),
+    (rdev::Key::Backspace, "Backspace"),
+    (rdev::Key::CapsLock, "CapsLock"),
+    (rdev::Key::ControlLeft, "LCtrl"),
+    (rdev::Key::ControlRight, "RCtrl"),
+    (rdev::Key::Delete, "Del"),
+    (rdev::Key::DownArrow, "DownArrow"),
+    (rdev::Key::End, "End"),
+    (rdev::Key::Escape, "Escape"),
+    (rdev::Key::F1, "F1"),
+    (rdev::Key::F2, "F2"),
+    (rdev::Key::F3, "F3"),
+    (rdev::Key::F4, "F4"),
+    (rdev::Key::F5, "F5"),
+    (rdev::Key::F6, "F6"),
+    (rdev::Key::F7, "F7"),
+    (rdev::Key::F8, "F8"),
+    (rdev::Key::F9, "F9"),
+    (rdev::Key::F10, "F10"),
+    (rdev::Key::F11, "F11"),
+    (rdev::Key::F12, "F12"),
+    (rdev::Key::Home, "Home"),
+    (rdev::Key::LeftArrow, "LeftArrow"),
+    (rdev::Key::MetaLeft, "LMeta"),
+    (rdev::Key::MetaRight, "RMeta"),
+    (rdev::Key::PageDown, "PgDown"),
+    (rdev::Key::PageUp, "PgUp"),
+    (rdev::Key::Return, "Return"),
+    (rdev::Key::RightArrow, "RightArrow"),
+    (rdev::Key::ShiftLeft, "LShft"),
+    (rdev::Key::ShiftRight, "RShft"),
+    (rdev::Key::Space, "Space"),
+    (rdev::Key::Tab, "Tab"),
+    (rdev::Key::UpArrow, "UpArrow"),
+    (rdev::Key::PrintScreen, "PrintScreen"),
+    (rdev::Key::ScrollLock, "ScrollLock"),
+    (rdev::Key::Pause, "Pause"),
+    (rdev::Key::NumLock, "NumLock"),
+    (rdev::Key::BackQuote, "`"),
+    (rdev::Key::Num1, "1"),
+    (rdev::Key::Num2, "2"),
+    (rdev::Key::Num3, "3"),
+    (rdev::Key::Num4, "4"),
+    (rdev::Key::Num5, "5"),
+    (rdev::Key::Num6, "6"),
+    (rdev::Key::Num7, "7"),
+    (rdev::Key::Num8, "8"),
+    (rdev::Key::Num9, "9"),
+    (rdev::Key::Num0, "0"),
+    (rdev::Key::Minus, "-"),
+    (rdev::Key::Equal, "="),
+    (rdev::Key::KeyQ, "Q"),
+    (rdev::Key::KeyW, "W"),
+    (rdev::Key::KeyE, "E"),
+    (rdev::Key::KeyR, "R"),
+    (rdev::Key::KeyT, "T"),
+    (rdev::Key::KeyY, "Y"),
+    (rdev::Key::KeyU, "U"),
+    (rdev::Key::KeyI, "I"),
+    (rdev::Key::KeyO, "O"),
+    (rdev::Key::KeyP, "P"),
+    (rdev::Key::LeftBracket, "("),
+    (rdev::Key::RightBracket, ")"),
+    (rdev::Key::KeyA, "A"),
+    (rdev::Key::KeyS, "S"),
+    (rdev::Key::KeyD, "D"),
+    (rdev::Key::KeyF, "F"),
+    (rdev::Key::KeyG, "G"),
+    (rdev::Key::KeyH, "H"),
+    (rdev::Key::KeyJ, "J"),
+    (rdev::Key::KeyK, "K"),
+    (rdev::Key::KeyL, "L"),
+    (rdev::Key::SemiColon, ";"),
+    (rdev::Key::Quote, "\""),
+    (rdev::Key::BackSlash, "\\"),
+    (rdev::Key::IntlBackslash, "IntlBackslash"),
+    (rdev::Key::KeyZ, "Z"),
+    (rdev::Key::KeyX, "X"),
+    (rdev::Key::KeyC, "C"),
+    (rdev::Key::KeyV, "V"),
+    (rdev::Key::KeyB, "B"),
+    (rdev::Key::KeyN, "N"),
+    (rdev::Key::KeyM, "M"),
+    (rdev::Key::Comma, ","),
+    (rdev::Key::Dot, "."),
+    (rdev::Key::Slash, "/"),
+    (rdev::Key::Insert, "Insert"),
+    (rdev::Key::KpReturn, "KpReturn"),
+    (rdev::Key::KpMinus, "KpMinus"),
+    (rdev::Key::KpPlus, "KpPlus"),
+    (rdev::Key::KpMultiply, "KpMultiply"),
+    (rdev::Key::KpDivide, "KpDivide"),
+    (rdev::Key::Kp0, "Kp0"),
+    (rdev::Key::Kp1, "Kp1"),
+    (rdev::Key::Kp2, "Kp2"),
+    (rdev::Key::Kp3, "Kp3"),
+    (rdev::Key::Kp4, "Kp4"),
+    (rdev::Key::Kp5, "Kp5"),
+    (rdev::Key::Kp6, "Kp6"),
+    (rdev::Key::Kp7, "Kp7"),
+    (rdev::Key::Kp8, "Kp8"),
+    (rdev::Key::Kp9, "Kp9"),
+    (rdev::Key::KpDelete, "KpDelete"),
+    (rdev::Key::Function, "Function"),
+];
+
+/*
+* Platform specific keycodes of the named keys
+* X11 keycodes on Linux and virtual-key codes on Windows, matching the codes used by rdev
+*/
+#[cfg(target_os = "linux")]
+const KEY_CODES: [(rdev::Key, u32); 104] = [
+    (rdev::Key::Alt, 64),
+    (rdev::Key::AltGr, 108),
+    (rdev::Key::Backspace, 22),
+    (rdev::Key::CapsLock, 66),
+    (rdev::Key::ControlLeft, 37),
+    (rdev::Key::ControlRight, 105),
+    (rdev::Key::Delete, 119),
+    (rdev::Key::DownArrow, 116),
+    (rdev::Key::End, 115),
+    (rdev::Key::Escape, 9),
+    (rdev::Key::F1, 67),
+    (rdev::Key::F10, 76),
+    (rdev::Key::F11, 95),
+    (rdev::Key::F12, 96),
+    (rdev::Key::F2, 68),
+    (rdev::Key::F3, 69),
+    (rdev::Key::F4, 70),
+    (rdev::Key::F5, 71),
+    (rdev::Key::F6, 72),
+    (rdev::Key::F7, 73),
+    (rdev::Key::F8, 74),
+    (rdev::Key::F9, 75),
+    (rdev::Key::Home, 110),
+    (rdev::Key::LeftArrow, 113),
+    (rdev::Key::MetaLeft, 133),
+    (rdev::Key::MetaRight, 134),
+    (rdev::Key::PageDown, 117),
+    (rdev::Key::PageUp, 112),
+    (rdev::Key::Return, 36),
+    (rdev::Key::RightArrow, 114),
+    (rdev::Key::ShiftLeft, 50),
+    (rdev::Key::ShiftRight, 62),
+    (rdev::Key::Space, 65),
+    (rdev::Key::Tab, 23),
+    (rdev::Key::UpArrow, 111),
+    (rdev::Key::PrintScreen, 107),
+    (rdev::Key::ScrollLock, 78),
+    (rdev::Key::Pause, 127),
+    (rdev::Key::NumLock, 77),
+    (rdev::Key::BackQuote, 49),
+    (rdev::Key::Num1, 10),
+    (rdev::Key::Num2, 11),
+    (rdev::Key::Num3, 12),
+    (rdev::Key::Num4, 13),
+    (rdev::Key::Num5, 14),
+    (rdev::Key::Num6, 15),
+    (rdev::Key::Num7, 16),
+    (rdev::Key::Num8, 17),
+    (rdev::Key::Num9, 18),
+    (rdev::Key::Num0, 19),
+    (rdev::Key::Minus, 20),
+    (rdev::Key::Equal, 21),
+    (rdev::Key::KeyQ, 24),
+    (rdev::Key::KeyW, 25),
+    (rdev::Key::KeyE, 26),
+    (rdev::Key::KeyR, 27),
+    (rdev::Key::KeyT, 28),
+    (rdev::Key::KeyY, 29),
+    (rdev::Key::KeyU, 30),
+    (rdev::Key::KeyI, 31),
+    (rdev::Key::KeyO, 32),
+    (rdev::Key::KeyP, 33),
+    (rdev::Key::LeftBracket, 34),
+    (rdev::Key::RightBracket, 35),
+    (rdev::Key::KeyA, 38),
+    (rdev::Key::KeyS, 39),
+    (rdev::Key::KeyD, 40),
+    (rdev::Key::KeyF, 41),
+    (rdev::Key::KeyG, 42),
+    (rdev::Key::KeyH, 43),
+    (rdev::Key::KeyJ, 44),
+    (rdev::Key::KeyK, 45),
+    (rdev::Key::KeyL, 46),
+    (rdev::Key::SemiColon, 47),
+    (rdev::Key::Quote, 48),
+    (rdev::Key::BackSlash, 51),
+    (rdev::Key::IntlBackslash, 94),
+    (rdev::Key::KeyZ, 52),
+    (rdev::Key::KeyX, 53),
+    (rdev::Key::KeyC, 54),
+    (rdev::Key::KeyV, 55),
+    (rdev::Key::KeyB, 56),
+    (rdev::Key::KeyN, 57),
+    (rdev::Key::KeyM, 58),
+    (rdev::Key::Comma, 59),
+    (rdev::Key::Dot, 60),
+    (rdev::Key::Slash, 61),
+    (rdev::Key::Insert, 118),
+    (rdev::Key::KpReturn, 104),
+    (rdev::Key::KpMinus, 82),
+    (rdev::Key::KpPlus, 86),
+    (rdev::Key::KpMultiply, 63),
+    (rdev::Key::KpDivide, 106),
+    (rdev::Key::Kp0, 90),
+    (rdev::Key::Kp1, 87),
+    (rdev::Key::Kp2, 88),
+    (rdev::Key::Kp3, 89),
+    (rdev::Key::Kp4, 83),
+    (rdev::Key::Kp5, 84),
+    (rdev::Key::Kp6, 85),
+    (rdev::Key::Kp7, 79),
+    (rdev::Key::Kp8, 80),
+    (rdev::Key::Kp9, 81),
+    (rdev::Key::KpDelete, 91),
+];
+
+#[cfg(target_os = "windows")]
+const KEY_CODES: [(rdev::Key, u32); 103] = [
+    (rdev::Key::Alt, 164),
+    (rdev::Key::AltGr, 165),
+    (rdev::Key::Backspace, 8),
+    (rdev::Key::CapsLock, 20),
+    (rdev::Key::ControlLeft, 162),
+    (rdev::Key::ControlRight, 163),
+    (rdev::Key::Delete, 46),
+    (rdev::Key::DownArrow, 40),
+    (rdev::Key::End, 35),
+    (rdev::Key::Escape, 27),
+    (rdev::Key::F1, 112),
+    (rdev::Key::F10, 121),
+    (rdev::Key::F11, 122),
+    (rdev::Key::F12, 123),
+    (rdev::Key::F2, 113),
+    (rdev::Key::F3, 114),
+    (rdev::Key::F4, 115),
+    (rdev::Key::F5, 116),
+    (rdev::Key::F6, 117),
+    (rdev::Key::F7, 118),
+    (rdev::Key::F8, 119),
+    (rdev::Key::F9, 120),
+    (rdev::Key::Home, 36),
+    (rdev::Key::LeftArrow, 37),
+    (rdev::Key::MetaLeft, 91),
+    (rdev::Key::MetaRight, 92),
+    (rdev::Key::PageDown, 34),
+    (rdev::Key::PageUp, 33),
+    (rdev::Key::Return, 13),
+    (rdev::Key::RightArrow, 39),
+    (rdev::Key::ShiftLeft, 160),
+    (rdev::Key::ShiftRight, 161),
+    (rdev::Key::Space, 32),
+    (rdev::Key::Tab, 9),
+    (rdev::Key::UpArrow, 38),
+    (rdev::Key::PrintScreen, 44),
+    (rdev::Key::ScrollLock, 145),
+    (rdev::Key::Pause, 19),
+    (rdev::Key::NumLock, 144),
+    (rdev::Key::BackQuote, 192),
+    (rdev::Key::Num1, 49),
+    (rdev::Key::Num2, 50),
+    (rdev::Key::Num3, 51),
+    (rdev::Key::Num4, 52),
+    (rdev::Key::Num5, 53),
+    (rdev::Key::Num6, 54),
+    (rdev::Key::Num7, 55),
+    (rdev::Key::Num8, 56),
+    (rdev::Key::Num9, 57),
+    (rdev::Key::Num0, 48),
+    (rdev::Key::Minus, 189),
+    (rdev::Key::Equal, 187),
+    (rdev::Key::KeyQ, 81),
+    (rdev::Key::KeyW, 87),
+    (rdev::Key::KeyE, 69),
+    (rdev::Key::KeyR, 82),
+    (rdev::Key::KeyT, 84),
+    (rdev::Key::KeyY, 89),
+    (rdev::Key::KeyU, 85),
+    (rdev::Key::KeyI, 73),
+    (rdev::Key::KeyO, 79),
+    (rdev::Key::KeyP, 80),
+    (rdev::Key::LeftBracket, 219),
+    (rdev::Key::RightBracket, 221),
+    (rdev::Key::KeyA, 65),
+    (rdev::Key::KeyS, 83),
+    (rdev::Key::KeyD, 68),
+    (rdev::Key::KeyF, 70),
+    (rdev::Key::KeyG, 71),
+    (rdev::Key::KeyH, 72),
+    (rdev::Key::KeyJ, 74),
+    (rdev::Key::KeyK, 75),
+    (rdev::Key::KeyL, 76),
+    (rdev::Key::SemiColon, 186),
+    (rdev::Key::Quote, 222),
+    (rdev::Key::BackSlash, 220),
+    (rdev::Key::IntlBackslash, 226),
+    (rdev::Key::KeyZ, 90),
+    (rdev::Key::KeyX, 88),
+    (rdev::Key::KeyC, 67),
+    (rdev::Key::KeyV, 86),
+    (rdev::Key::KeyB, 66),
+    (rdev::Key::KeyN, 78),
+    (rdev::Key::KeyM, 77),
+    (rdev::Key::Comma, 188),
+    (rdev::Key::Dot, 190),
+    (rdev::Key::Slash, 191),
+    (rdev::Key::Insert, 45),
+    (rdev::Key::KpMinus, 109),
+    (rdev::Key::KpPlus, 107),
+    (rdev::Key::KpMultiply, 106),
+    (rdev::Key::KpDivide, 111),
+    (rdev::Key::Kp0, 96),
+    (rdev::Key::Kp1, 97),
+    (rdev::Key::Kp2, 98),
+    (rdev::Key::Kp3, 99),
+    (rdev::Key::Kp4, 100),
+    (rdev::Key::Kp5, 101),
+    (rdev::Key::Kp6, 102),
+    (rdev::Key::Kp7, 103),
+    (rdev::Key::Kp8, 104),
+    (rdev::Key::Kp9, 105),
+    (rdev::Key::KpDelete, 110),
+];
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+const KEY_CODES: [(rdev::Key, u32); 0] = [];
+
+/*
+* Registry of named keys that rdev reports as rdev::Key::Unknown
+* The codes are platform specific, X11 keycodes (as assigned by the evdev driver) on Linux
+* and virtual-key codes on Windows, rdev passes both of them through unchanged
+*/
+#[cfg(target_os = "linux")]
+const RAW_KEY_NAMES: [(u32, &str); 19] = [
+    (191, "F13"),
+    (192, "F14"),
+    (193, "F15"),
+    (194, "F16"),
+    (195, "F17"),
+    (196, "F18"),
+    (197, "F19"),
+    (198, "F20"),
+    (199, "F21"),
+    (200, "F22"),
+    (201, "F23"),
+    (202, "F24"),
+    (172, "MediaPlayPause"),
+    (174, "MediaStop"),
+    (171, "MediaNextTrack"),
+    (173, "MediaPrevTrack"),
+    (121, "VolumeMute"),
+    (122, "VolumeDown"),
+    (123, "VolumeUp"),
+];
+
+#[cfg(target_os = "windows")]
+const RAW_KEY_NAMES: [(u32, &str); 19] = [
+    (0x7c, "F13"),
+    (0x7d, "F14"),
+    (0x7e, "F15"),
+    (0x7f, "F16"),
+    (0x80, "F17"),
+    (0x81, "F18"),
+    (0x82, "F19"),
+    (0x83, "F20"),
+    (0x84, "F21"),
+    (0x85, "F22"),
+    (0x86, "F23"),
+    (0x87, "F24"),
+    (0xb3, "MediaPlayPause"),
+    (0xb2, "MediaStop"),
+    (0xb0, "MediaNextTrack"),
+    (0xb1, "MediaPrevTrack"),
+    (0xad, "VolumeMute"),
+    (0xae, "VolumeDown"),
+    (0xaf, "VolumeUp"),
+];
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+const RAW_KEY_NAMES: [(u32, &str); 0] = [];
+
+/*
+* Registry of mouse button names
+* Side buttons are reported as rdev::Button::Unknown with platform specific codes,
+* X11 button numbers on Linux and XBUTTON numbers on Windows
+*/
+#[cfg(target_os = "linux")]
+const BUTTON_NAMES: [(rdev::Button, &str); 5] = [
+    (rdev::Button::Left, "MouseLeft"),
+    (rdev::Button::Middle, "MouseMiddle"),
+    (rdev::Button::Right, "MouseRight"),
+    (rdev::Button::Unknown(8), "Mouse4"),
+    (rdev::Button::Unknown(9), "Mouse5"),
+];
+
+#[cfg(not(target_os = "linux"))]
+const BUTTON_NAMES: [(rdev::Button, &str); 5] = [
+    (rdev::Button::Left, "MouseLeft"),
+    (rdev::Button::Middle, "MouseMiddle"),
+    (rdev::Button::Right, "MouseRight"),
+    (rdev::Button::Unknown(1), "Mouse4"),
+    (rdev::Button::Unknown(2), "Mouse5"),
+];
+
+// Prefix and suffix of the string representation of keys without a name
+const RAW_KEY_PREFIX: &str = "Raw(";
+const RAW_KEY_SUFFIX: &str = ")";
+
+/**
+ * @brief Function to convert rdev::Key to string
+ * @param key - rdev::Key to convert
+ * @return String
+ * @note Keys without a name are represented as Raw(<code>),
+ * where code is the platform specific keycode
+ */
+pub fn key_to_string(key: rdev::Key) -> String {
+    if let rdev::Key::Unknown(code) = key {
+        // Find the name of the keycode in the platform specific registry
+        if let Some((_, name)) = RAW_KEY_NAMES.iter().find(|(raw_code, _)| *raw_code == code) {
+            return String::from(*name);
+        }
+
+        // Keys without a name are represented by their raw keycode
+        return format!("{}{}{}", RAW_KEY_PREFIX, code, RAW_KEY_SUFFIX);
+    }
+
+    // Find the name of the key in the registry
+    match KEY_NAMES.iter().find(|(registry_key, _)| *registry_key == key) {
+        Some((_, name)) => String::from(*name),
+        None => unreachable!("Key {:?} is missing from the key registry", key),
+    }
+}
+
+/**
+ * @brief Function to convert string to rdev::Key
+ * @param key - string to convert
+ * @return rdev::Key
+ */
+pub fn string_to_key(key: &str) -> Result<rdev::Key, &str> {
+    // Find the key in the registry
+    if let Some((registry_key, _)) = KEY_NAMES.iter().find(|(_, name)| *name == key) {
+        return Ok(*registry_key);
+    }
+
+    // Find the key in the platform specific registry
+    if let Some((code, _)) = RAW_KEY_NAMES.iter().find(|(_, name)| *name == key) {
+        return Ok(rdev::Key::Unknown(*code));
+    }
+
+    // Parse the raw keycode of keys without a name
+    match key
+        .strip_prefix(RAW_KEY_PREFIX)
+        .and_then(|rest| rest.strip_suffix(RAW_KEY_SUFFIX))
+        .map(|code| code.parse::<u32>())
+    {
+        Some(Ok(code)) => Ok(code_to_key(code)),
+        _ => Err("Unknown Key"),
+    }
+}
+
+/**
+ * @brief Function to convert a platform specific keycode to rdev::Key
+ * @param code - X11 keycode on Linux, virtual-key code on Windows
+ * @return rdev::Key - the named key with the keycode, rdev::Key::Unknown if the keycode has no name
+ * @note The keyboard listener reports keys with a name by their name, so raw keycodes are normalized to it
+ */
+fn code_to_key(code: u32) -> rdev::Key {
+    match KEY_CODES.iter().find(|(_, key_code)| *key_code == code) {
+        Some((key, _)) => *key,
+        None => rdev::Key::Unknown(code),
+    }
+}
+
+/**
+ * @brief Function to get the names of all keys in the registry
+ * @return Vec<String> - names of all keys
+ * @note Keys without a name are not included, since their Raw(<code>) names are platform specific
+ */
+pub fn get_key_names() -> Vec<String> {
+    KEY_NAMES
+        .iter()
+        .map(|(_, name)| name)
+        .chain(RAW_KEY_NAMES.iter().map(|(_, name)| name))
+        .map(|name| String::from(*name))
+        .collect()
+}
+
+/**
+ * @brief Function to get the platform specific keycode of a key
+ * @param key - rdev::Key to convert
+ * @return Option<u32> - X11 keycode on Linux, virtual-key code on Windows, None if the key has no keycode
+ */
+pub fn key_to_code(key: rdev::Key) -> Option<u32> {
+    // Keys without a name already carry their keycode
+    if let rdev::Key::Unknown(code) = key {
+        return Some(code);
+    }
+
+    // Find the keycode of the key in the platform specific registry
+    KEY_CODES
+        .iter()
+        .find(|(code_key, _)| *code_key == key)
+        .map(|(_, code)| *code)
+}
+
+/**
+ * @brief Function to convert string to rdev::Button
+ * @param button - string to convert
+ * @return rdev::Button
+ */
+pub fn string_to_button(button: &str) -> Result<rdev::Button, &str> {
+    match BUTTON_NAMES.iter().find(|(_, name)| *name == button) {
+        Some((registry_button, _)) => Ok(*registry_button),
+        None => Err("Unknown Button"),
+    }
+}
+
+/**
+ * @brief Function to convert rdev::Button to string
+ * @param button - rdev::Button to convert
+ * @return Option<String> - None if the button has no name
+ */
+pub fn button_to_string(button: rdev::Button) -> Option<String> {
+    BUTTON_NAMES
+        .iter()
+        .find(|(registry_button, _)| *registry_button == button)
+        .map(|(_, name)| String::from(*name))
+}
@@ -0,0 +1,189 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/keystate.rs                        *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+#[cfg(target_os = "linux")]
+use lazy_static::lazy_static;
+#[cfg(target_os = "linux")]
+use std::sync::Mutex;
+
+/*
+* Lazily initialized global connection to the X server used for querying the keyboard, stored as an address
+* It is opened on the first query and kept open, the lock serializes the queries using it
+*/
+#[cfg(target_os = "linux")]
+lazy_static! {
+    static ref QUERY_DISPLAY: Mutex<usize> = Mutex::new(0);
+}
+
+// Struct for the states of the lock keys
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LockStates {
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+/**
+ * @brief Function to run a query on the connection to the X server used for querying the keyboard
+ * @param query - the query
+ * @return Option<T> - result of the query, None if the X server can't be reached
+ * @note The connection is opened on the first query and registered, so its errors don't reach the error handler of GDK
+ */
+#[cfg(target_os = "linux")]
+fn with_query_display<T>(query: impl FnOnce(*mut x11::xlib::Display) -> T) -> Option<T> {
+    use crate::keygrab::register_display;
+    use x11::xlib;
+
+    let mut query_display = QUERY_DISPLAY.lock().unwrap();
+
+    unsafe {
+        // Open a connection to the X server on the first query
+        if *query_display == 0 {
+            let display = xlib::XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+
+            register_display(display);
+            *query_display = display as usize;
+        }
+    }
+
+    Some(query(*query_display as *mut xlib::Display))
+}
+
+/**
+ * @brief Query the system for the physical state of keys
+ * @param codes - platform specific keycodes of the keys to query
+ * @return Option<Vec<bool>> - for each keycode whether the key is currently pressed,
+ * None if the keyboard state couldn't be queried
+ * @note Uses XQueryKeymap on the shared query connection on Linux
+ */
+#[cfg(target_os = "linux")]
+pub fn query_pressed_keys(codes: &[u32]) -> Option<Vec<bool>> {
+    use std::os::raw::c_char;
+    use x11::xlib;
+
+    // Bit vector of the pressed keys, one bit for each of the 256 keycodes
+    let mut keymap: [c_char; 32] = [0; 32];
+
+    // Query the state of the keyboard
+    with_query_display(|display| unsafe {
+        xlib::XQueryKeymap(display, keymap.as_mut_ptr());
+    })?;
+
+    // Look up the bit of every requested keycode
+    Some(
+        codes
+            .iter()
+            .map(|code| match keymap.get((*code / 8) as usize) {
+                Some(byte) => (*byte as u8) & (1 << (*code % 8)) != 0,
+                None => false,
+            })
+            .collect(),
+    )
+}
+
+/**
+ * @brief Query the system for the physical state of keys
+ * @param codes - platform specific keycodes of the keys to query
+ * @return Option<Vec<bool>> - for each keycode whether the key is currently pressed,
+ * None if the keyboard state couldn't be queried
+ * @note Uses GetAsyncKeyState on Windows
+ */
+#[cfg(target_os = "windows")]
+pub fn query_pressed_keys(codes: &[u32]) -> Option<Vec<bool>> {
+    use winapi::um::winuser;
+
+    // The most significant bit of the result is set if the key is down
+    Some(
+        codes
+            .iter()
+            .map(|code| unsafe { winuser::GetAsyncKeyState(*code as i32) } as u16 & 0x8000 != 0)
+            .collect(),
+    )
+}
+
+/**
+ * @brief Query the system for the physical state of keys
+ * @param _codes - platform specific keycodes of the keys to query
+ * @return Option<Vec<bool>> - always None, querying is not supported on this platform
+ */
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn query_pressed_keys(_codes: &[u32]) -> Option<Vec<bool>> {
+    None
+}
+
+/**
+ * @brief Query the system for the states of the lock keys
+ * @return Option<LockStates> - None if the states couldn't be queried
+ * @note Uses the modifier state of XQueryPointer on the shared query connection on Linux,
+ * NumLock is assumed to be Mod2 as on most layouts
+ */
+#[cfg(target_os = "linux")]
+pub fn query_lock_states() -> Option<LockStates> {
+    use std::os::raw::{c_int, c_uint};
+    use x11::xlib;
+
+    let mut root_return: xlib::Window = 0;
+    let mut child_return: xlib::Window = 0;
+    let mut root_x: c_int = 0;
+    let mut root_y: c_int = 0;
+    let mut window_x: c_int = 0;
+    let mut window_y: c_int = 0;
+    let mut mask: c_uint = 0;
+
+    // Query the modifier state, the lock keys are modifiers in X11
+    let result = with_query_display(|display| unsafe {
+        let root: xlib::Window = xlib::XDefaultRootWindow(display);
+        xlib::XQueryPointer(
+            display,
+            root,
+            &mut root_return,
+            &mut child_return,
+            &mut root_x,
+            &mut root_y,
+            &mut window_x,
+            &mut window_y,
+            &mut mask,
+        )
+    })?;
+
+    if result == 0 {
+        return None;
+    }
+
+    Some(LockStates {
+        caps_lock: mask & xlib::LockMask != 0,
+        num_lock: mask & xlib::Mod2Mask != 0,
+    })
+}
+
+/**
+ * @brief Query the system for the states of the lock keys
+ * @return Option<LockStates> - None if the states couldn't be queried
+ * @note Uses GetKeyState on Windows
+ */
+#[cfg(target_os = "windows")]
+pub fn query_lock_states() -> Option<LockStates> {
+    use winapi::um::winuser;
+
+    // The least significant bit of the result is set if the key is toggled on
+    unsafe {
+        Some(LockStates {
+            caps_lock: winuser::GetKeyState(winuser::VK_CAPITAL) & 1 != 0,
+            num_lock: winuser::GetKeyState(winuser::VK_NUMLOCK) & 1 != 0,
+        })
+    }
+}
+
+/**
+ * @brief Query the system for the states of the lock keys
+ * @return Option<LockStates> - always None, querying is not supported on this platform
+ */
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn query_lock_states() -> Option<LockStates> {
+    None
+}
@@ -0,0 +1,336 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/macros.rs                          *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+use std::fs;
+
+use crate::inputsim::{self, SimulationResult, DEFAULT_EVENT_DELAY_MS, MAX_DELAY_MS};
+use crate::shortcuts::{set_macro_shortcuts, validate_keybind};
+
+// Directory with the macro files in the user data directory
+const MACROS_DIR_NAME: &str = "macros";
+
+// Extension of the macro files
+const MACRO_FILE_EXTENSION: &str = "json";
+
+// Label of the window notified about the simulations of macros run by their key combination
+const OVERFLOAT_WINDOW_LABEL: &str = "Overfloat";
+
+// Priority of the simulations of macros run by their key combination
+const BOUND_MACRO_PRIORITY: i32 = 0;
+
+// Struct for the content of a macro file
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct SavedMacro {
+    // Key combination running the macro without any module window, None if not bound
+    #[serde(default)]
+    keybind: Option<String>,
+    // Delay in milliseconds after each simulated event
+    #[serde(default = "default_delay_ms")]
+    delay_ms: u64,
+    // Simulation steps in their JSON representation
+    steps: Vec<serde_json::Value>,
+}
+
+// Struct for a macro in the list of saved macros
+#[derive(Clone, serde::Serialize)]
+pub struct MacroInfo {
+    name: String,
+    keybind: Option<String>,
+}
+
+// Struct for returning results from macro library operations
+#[derive(Clone, serde::Serialize)]
+pub struct MacroResult {
+    successful: bool,
+    message: String,
+    saved_macro: Option<SavedMacro>,
+}
+
+/**
+ * @brief Function to get the default delay between simulated events of a macro
+ * @return u64
+ */
+fn default_delay_ms() -> u64 {
+    DEFAULT_EVENT_DELAY_MS
+}
+
+/**
+ * @brief Function to get the directory with the macro files
+ * @param handle - tauri::AppHandle
+ * @return Result<std::path::PathBuf, String> - directory in the user data directory,
+ * error message if the user data directory can't be determined
+ */
+fn macros_dir(handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    match handle.path_resolver().app_data_dir() {
+        Some(app_data_dir) => Ok(app_data_dir.join(MACROS_DIR_NAME)),
+        None => Err("Couldn't determine the user data directory".to_string()),
+    }
+}
+
+/**
+ * @brief Function to get the path of a macro file
+ * @param handle - tauri::AppHandle
+ * @param name - name of the macro
+ * @return Result<std::path::PathBuf, String> - error message if the name is invalid
+ * @note Names can only contain alphanumerical characters, '-' and '_', so they can't escape the directory
+ */
+fn macro_path(handle: &tauri::AppHandle, name: &str) -> Result<std::path::PathBuf, String> {
+    if !is_valid_name(name) {
+        return Err(format!("Invalid macro name: {}", name));
+    }
+
+    Ok(macros_dir(handle)?.join(format!("{}.{}", name, MACRO_FILE_EXTENSION)))
+}
+
+/**
+ * @brief Function to check whether a macro name is valid
+ * @param name - name of the macro
+ * @return bool
+ */
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/**
+ * @brief Function to read a macro file
+ * @param handle - tauri::AppHandle
+ * @param name - name of the macro
+ * @return Result<SavedMacro, String> - error message if the macro doesn't exist or is invalid
+ */
+fn read_macro(handle: &tauri::AppHandle, name: &str) -> Result<SavedMacro, String> {
+    let path: std::path::PathBuf = macro_path(handle, name)?;
+
+    let content: String = fs::read_to_string(&path)
+        .map_err(|error| format!("Couldn't read macro {}: {}", name, error))?;
+
+    serde_json::from_str::<SavedMacro>(&content).map_err(|error| format!("Invalid macro {}: {}", name, error))
+}
+
+/**
+ * @brief Function to get the names of all saved macros
+ * @param handle - tauri::AppHandle
+ * @return Vec<String> - sorted names
+ */
+fn macro_names(handle: &tauri::AppHandle) -> Vec<String> {
+    let entries = match macros_dir(handle).and_then(|dir| fs::read_dir(dir).map_err(|error| error.to_string())) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |extension| extension == MACRO_FILE_EXTENSION))
+        .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(String::from))
+        .filter(|name| is_valid_name(name))
+        .collect();
+
+    names.sort();
+    names
+}
+
+/**
+ * @brief Function to queue the simulation of a saved macro
+ * @param handle - tauri::AppHandle
+ * @param window_label - label of the window running the macro, it is notified about the progress
+ * @param name - name of the macro
+ * @param priority - priority of the simulation
+ * @return SimulationResult
+ */
+fn start_macro(handle: tauri::AppHandle, window_label: String, name: &str, priority: i32) -> SimulationResult {
+    match read_macro(&handle, name) {
+        Ok(saved_macro) => {
+            inputsim::start_simulation(handle, window_label, saved_macro.steps, saved_macro.delay_ms, priority)
+        }
+        Err(message) => SimulationResult::failed(message),
+    }
+}
+
+/**
+ * @brief Function to get the list of saved macros
+ * @param handle - tauri::AppHandle
+ * @return Vec<MacroInfo> - names and key combinations of the macros, sorted by name
+ * @note Invalid macro files are skipped
+ * Exposes the operation to tauri commands
+ */
+pub fn list_macros(handle: &tauri::AppHandle) -> Vec<MacroInfo> {
+    macro_names(handle)
+        .into_iter()
+        .filter_map(|name| match read_macro(handle, &name) {
+            Ok(saved_macro) => Some(MacroInfo {
+                name: name,
+                keybind: saved_macro.keybind,
+            }),
+            Err(message) => {
+                println!("{}", message);
+                None
+            }
+        })
+        .collect()
+}
+
+/**
+ * @brief Function to save a macro, an existing macro with the same name is overwritten
+ * @param handle - tauri::AppHandle
+ * @param name - name of the macro
+ * @param saved_macro - content of the macro
+ * @return MacroResult - error message if the name or the steps are invalid
+ * @note Exposes the operation to tauri commands
+ */
+pub fn save_macro(handle: &tauri::AppHandle, name: String, saved_macro: SavedMacro) -> MacroResult {
+    let path: std::path::PathBuf = match macro_path(handle, &name) {
+        Ok(path) => path,
+        Err(message) => return MacroResult::failed(message),
+    };
+
+    if saved_macro.delay_ms > MAX_DELAY_MS {
+        return MacroResult::failed(format!(
+            "Delay too long: {} ms, maximum is {} ms",
+            saved_macro.delay_ms, MAX_DELAY_MS
+        ));
+    }
+
+    if let Err(message) = inputsim::validate_steps(&saved_macro.steps) {
+        return MacroResult::failed(format!("Invalid steps: {}", message));
+    }
+
+    if let Some(Err(message)) = saved_macro.keybind.as_deref().map(validate_keybind) {
+        return MacroResult::failed(format!("Invalid keybind: {}", message));
+    }
+
+    // Create the macro directory if it doesn't exist
+    if let Some(Err(error)) = path.parent().map(fs::create_dir_all) {
+        return MacroResult::failed(format!("Couldn't create the macro directory: {}", error));
+    }
+
+    let content: String = match serde_json::to_string_pretty(&saved_macro) {
+        Ok(content) => content,
+        Err(error) => return MacroResult::failed(error.to_string()),
+    };
+
+    if let Err(error) = fs::write(&path, content) {
+        return MacroResult::failed(format!("Couldn't write macro {}: {}", name, error));
+    }
+
+    load_macro_bindings(handle);
+
+    MacroResult {
+        successful: true,
+        message: String::new(),
+        saved_macro: Some(saved_macro),
+    }
+}
+
+/**
+ * @brief Function to load a saved macro
+ * @param handle - tauri::AppHandle
+ * @param name - name of the macro
+ * @return MacroResult - content of the macro, error message if it doesn't exist or is invalid
+ * @note Exposes the operation to tauri commands
+ */
+pub fn load_macro(handle: &tauri::AppHandle, name: String) -> MacroResult {
+    match read_macro(handle, &name) {
+        Ok(saved_macro) => MacroResult {
+            successful: true,
+            message: String::new(),
+            saved_macro: Some(saved_macro),
+        },
+        Err(message) => MacroResult::failed(message),
+    }
+}
+
+/**
+ * @brief Function to delete a saved macro
+ * @param handle - tauri::AppHandle
+ * @param name - name of the macro
+ * @return MacroResult - error message if the macro couldn't be deleted
+ * @note Exposes the operation to tauri commands
+ */
+pub fn delete_macro(handle: &tauri::AppHandle, name: String) -> MacroResult {
+    let path: std::path::PathBuf = match macro_path(handle, &name) {
+        Ok(path) => path,
+        Err(message) => return MacroResult::failed(message),
+    };
+
+    if let Err(error) = fs::remove_file(&path) {
+        return MacroResult::failed(format!("Couldn't delete macro {}: {}", name, error));
+    }
+
+    load_macro_bindings(handle);
+
+    MacroResult {
+        successful: true,
+        message: String::new(),
+        saved_macro: None,
+    }
+}
+
+/**
+ * @brief Function to queue the simulation of a saved macro
+ * @param handle - tauri::AppHandle
+ * @param window_label - label of the window running the macro, it is notified about the progress
+ * @param name - name of the macro
+ * @param priority - priority of the simulation
+ * @return SimulationResult - ID of the queued simulation, or the reason why it wasn't queued
+ * @note Exposes the operation to tauri commands
+ */
+pub fn run_macro(handle: tauri::AppHandle, window_label: String, name: String, priority: i32) -> SimulationResult {
+    start_macro(handle, window_label, &name, priority)
+}
+
+/**
+ * @brief Function to reload the key combinations bound to saved macros from the macro files
+ * @param handle - tauri::AppHandle
+ * @note Called on startup and whenever a macro is saved or deleted.
+ * The key combinations are registered as shortcuts, so they are matched, consumed and checked for conflicts like them
+ */
+pub fn load_macro_bindings(handle: &tauri::AppHandle) {
+    let bindings: Vec<(String, String)> = list_macros(handle)
+        .into_iter()
+        .filter_map(|info| info.keybind.map(|keybind| (info.name, keybind)))
+        .collect();
+
+    set_macro_shortcuts(bindings);
+}
+
+/**
+ * @brief Function to run a saved macro bound to a pressed key combination
+ * @param handle - tauri::AppHandle
+ * @param name - name of the macro
+ * @note The macro runs without any module window, the Overfloat window is notified about its progress.
+ * Exposes the operation to the shortcut registry
+ */
+pub fn run_bound_macro(handle: &tauri::AppHandle, name: String) {
+    // Read the macro file on a separate thread, so the keyboard listener isn't blocked
+    let handle: tauri::AppHandle = handle.clone();
+    std::thread::spawn(move || {
+        let result: SimulationResult = start_macro(
+            handle,
+            OVERFLOAT_WINDOW_LABEL.to_string(),
+            &name,
+            BOUND_MACRO_PRIORITY,
+        );
+        if let Some(message) = result.error_message() {
+            println!("Couldn't run macro {}: {}", name, message);
+        }
+    });
+}
+
+// Implementation of MacroResult
+impl MacroResult {
+    /**
+     * @brief Function to create an unsuccessful result
+     * @param message - error message
+     * @return MacroResult
+     */
+    fn failed(message: String) -> MacroResult {
+        MacroResult {
+            successful: false,
+            message: message,
+            saved_macro: None,
+        }
+    }
+}
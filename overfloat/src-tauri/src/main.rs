@@ -17,9 +17,20 @@ use tauri::{
     SystemTrayMenuItemHandle,
 };
 
+mod activewindow;
+mod appactions;
 mod fswatch;
+mod hotstrings;
 mod inputsim;
 mod keybinds;
+mod keygrab;
+mod keys;
+mod keystate;
+mod macros;
+mod recorder;
+mod remap;
+mod script;
+mod shortcuts;
 
 // Struct for returning results from file system operations
 #[derive(Clone, serde::Serialize)]
@@ -180,6 +191,7 @@ fn hide_app(handle: tauri::AppHandle) {
  */
 #[tauri::command]
 fn quit_app(handle: tauri::AppHandle) {
+    inputsim::release_all_inputs();
     handle.exit(0);
 }
 
@@ -254,6 +266,155 @@ fn save_config(config_json: String) {
         },
         Err(error) => println!("Error while opening a config file{}", error),
     };
+
+    // Update the key combinations of the app-level actions
+    appactions::load_app_actions(&config_json);
+}
+
+/**
+ * @brief Gets the content of the remaps file
+ * @return String: Content of the remaps file
+ */
+#[tauri::command]
+fn get_remaps() -> String {
+    
+    let path_string: String;
+    let mode = std::env::var("TAURI_MODE").unwrap_or_else(|_| "unknown".to_string());
+    
+    // Use the remaps file from the development directory if the application is running in development mode
+    if mode == "development"{
+        path_string = format!("../config/remaps.json");
+    } 
+    // Use the remaps file from the installation directory otherwise
+    else {
+        path_string = format!("{}/config/remaps.json", get_install_dir());
+    }
+    
+    // Create a path object
+    let path = std::path::Path::new(&path_string);
+
+    // Try to read the file and handle the result
+    match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            println!("Error while reading the remaps file: {}", err);
+            String::new()},
+    }
+}
+
+/**
+ * @brief Saves the content to the remaps file
+ * @param remaps_json: Content to save
+ * @return String: Content of the remaps file
+ */
+#[tauri::command]
+fn save_remaps(remaps_json: String) {
+
+    let path_string: String;
+    let mode = std::env::var("TAURI_MODE").unwrap_or_else(|_| "unknown".to_string());
+    
+    // Use the remaps file from the development directory if the application is running in development mode
+    if mode == "development"{
+        path_string = format!("../config/remaps.json");
+    } 
+    // Use the remaps file from the installation directory otherwise
+    else {
+        path_string = format!("{}/config/remaps.json", get_install_dir());
+    }
+
+    // Create parent directories if they don't exist
+    if let Some(parent) = std::path::Path::new(&path_string).parent() {
+        if let Err(err) = std::fs::create_dir_all(parent) {
+            println!("Error while creating directories for remaps file: {}", err);
+        }
+    }
+
+    // Try to write the content to the remaps file and handle the result
+    match fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path_string)
+    {
+        Ok(mut file) => match file.write_all(remaps_json.as_bytes()) {
+            Ok(_) => {}
+            Err(error) => println!("Error while writing into a remaps file: {}", error),
+        },
+        Err(error) => println!("Error while opening a remaps file{}", error),
+    };
+}
+
+/**
+ * @brief Gets the content of the hotstrings file
+ * @return String: Content of the hotstrings file
+ */
+#[tauri::command]
+fn get_hotstrings() -> String {
+    
+    let path_string: String;
+    let mode = std::env::var("TAURI_MODE").unwrap_or_else(|_| "unknown".to_string());
+    
+    // Use the hotstrings file from the development directory if the application is running in development mode
+    if mode == "development"{
+        path_string = format!("../config/hotstrings.json");
+    } 
+    // Use the hotstrings file from the installation directory otherwise
+    else {
+        path_string = format!("{}/config/hotstrings.json", get_install_dir());
+    }
+    
+    // Create a path object
+    let path = std::path::Path::new(&path_string);
+
+    // Try to read the file and handle the result
+    match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            println!("Error while reading the hotstrings file: {}", err);
+            String::new()},
+    }
+}
+
+/**
+ * @brief Saves the content to the hotstrings file
+ * @param hotstrings_json: Content to save
+ * @return String: Content of the hotstrings file
+ */
+#[tauri::command]
+fn save_hotstrings(hotstrings_json: String) {
+
+    let path_string: String;
+    let mode = std::env::var("TAURI_MODE").unwrap_or_else(|_| "unknown".to_string());
+    
+    // Use the hotstrings file from the development directory if the application is running in development mode
+    if mode == "development"{
+        path_string = format!("../config/hotstrings.json");
+    } 
+    // Use the hotstrings file from the installation directory otherwise
+    else {
+        path_string = format!("{}/config/hotstrings.json", get_install_dir());
+    }
+
+    // Create parent directories if they don't exist
+    if let Some(parent) = std::path::Path::new(&path_string).parent() {
+        if let Err(err) = std::fs::create_dir_all(parent) {
+            println!("Error while creating directories for hotstrings file: {}", err);
+        }
+    }
+
+    // Try to write the content to the hotstrings file and handle the result
+    match fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path_string)
+    {
+        Ok(mut file) => match file.write_all(hotstrings_json.as_bytes()) {
+            Ok(_) => {}
+            Err(error) => println!("Error while writing into a hotstrings file: {}", error),
+        },
+        Err(error) => println!("Error while opening a hotstrings file{}", error),
+    };
 }
 
 /**
@@ -367,12 +528,352 @@ async fn stop_watching(window_label: String, id: String) {
 }
 
 /**
- * @brief Simulates input events
+ * @brief Queues simulating input events in the background, nothing is simulated if any step is invalid
+ * @param handle: Tauri AppHandle
+ * @param window: Window starting the simulation, it is notified about the progress
  * @param simulation_steps: Vector of simulation steps
+ * @param delay_ms: Delay in milliseconds after each simulated event, defaults to 1 ms
+ * @param priority: Priority of the simulation, queued simulations with higher priority are simulated first, defaults to 0
+ * @return SimulationResult: Struct containing the ID of the simulation or the reason why it wasn't queued
  */
 #[tauri::command]
-fn input_simulation(simulation_steps: Vec<inputsim::SimulationStep>) {
-    inputsim::simulate_inputs(simulation_steps);
+fn input_simulation(
+    handle: tauri::AppHandle,
+    window: tauri::Window,
+    simulation_steps: Vec<serde_json::Value>,
+    delay_ms: Option<u64>,
+    priority: Option<i32>,
+) -> inputsim::SimulationResult {
+    let delay_ms: u64 = delay_ms.unwrap_or(inputsim::DEFAULT_EVENT_DELAY_MS);
+    inputsim::start_simulation(
+        handle,
+        window.label().to_string(),
+        simulation_steps,
+        delay_ms,
+        priority.unwrap_or(0),
+    )
+}
+
+/**
+ * @brief Sets the policy of the module of the window for starting a simulation while another one of the module is queued or running
+ * @param window: Window of the module
+ * @param policy: Policy, one of "queue", "reject" or "replace"
+ */
+#[tauri::command]
+fn set_simulation_policy(window: tauri::Window, policy: inputsim::SimulationPolicy) {
+    inputsim::set_simulation_policy(window.label(), policy);
+}
+
+/**
+ * @brief Cancels a running input simulation, keys and buttons held by it are released
+ * @param window: Window cancelling the simulation, only simulations started by it can be cancelled
+ * @param job_id: ID of the simulation
+ * @return bool: False if no such simulation was started by the window
+ */
+#[tauri::command]
+fn cancel_input_simulation(window: tauri::Window, job_id: u64) -> bool {
+    inputsim::cancel_simulation(window.label(), job_id)
+}
+
+/**
+ * @brief Checks an input simulation script for errors without running it
+ * @param source: Source of the script
+ * @return Vec<ScriptError>: Errors of all invalid lines with their line numbers, empty if the script is valid
+ */
+#[tauri::command]
+fn check_input_script(source: String) -> Vec<script::ScriptError> {
+    script::check_script(&source)
+}
+
+/**
+ * @brief Queues running an input simulation script in the background, nothing is run if any line is invalid
+ * @param handle: Tauri AppHandle
+ * @param window: Window starting the script, it is notified about the progress
+ * @param source: Source of the script
+ * @param delay_ms: Delay in milliseconds after each simulated event, defaults to 1 ms
+ * @param priority: Priority of the simulation, queued simulations with higher priority are simulated first, defaults to 0
+ * @return ScriptResult: Struct containing the ID of the simulation or the errors of the script
+ */
+#[tauri::command]
+fn run_input_script(
+    handle: tauri::AppHandle,
+    window: tauri::Window,
+    source: String,
+    delay_ms: Option<u64>,
+    priority: Option<i32>,
+) -> script::ScriptResult {
+    let delay_ms: u64 = delay_ms.unwrap_or(inputsim::DEFAULT_EVENT_DELAY_MS);
+    script::run_script(
+        handle,
+        window.label().to_string(),
+        &source,
+        delay_ms,
+        priority.unwrap_or(0),
+    )
+}
+
+/**
+ * @brief Gets the list of saved macros
+ * @param handle: Tauri AppHandle
+ * @return Vec<MacroInfo>: Names and key combinations of the macros, sorted by name
+ */
+#[tauri::command]
+fn list_macros(handle: tauri::AppHandle) -> Vec<macros::MacroInfo> {
+    macros::list_macros(&handle)
+}
+
+/**
+ * @brief Saves a macro to the macro library, an existing macro with the same name is overwritten
+ * @param handle: Tauri AppHandle
+ * @param name: Name of the macro, can only contain alphanumerical characters, '-' and '_'
+ * @param saved_macro: Content of the macro
+ * @return MacroResult: Struct containing the result of the operation
+ */
+#[tauri::command]
+fn save_macro(handle: tauri::AppHandle, name: String, saved_macro: macros::SavedMacro) -> macros::MacroResult {
+    macros::save_macro(&handle, name, saved_macro)
+}
+
+/**
+ * @brief Loads a macro from the macro library
+ * @param handle: Tauri AppHandle
+ * @param name: Name of the macro
+ * @return MacroResult: Struct containing the content of the macro
+ */
+#[tauri::command]
+fn load_macro(handle: tauri::AppHandle, name: String) -> macros::MacroResult {
+    macros::load_macro(&handle, name)
+}
+
+/**
+ * @brief Deletes a macro from the macro library
+ * @param handle: Tauri AppHandle
+ * @param name: Name of the macro
+ * @return MacroResult: Struct containing the result of the operation
+ */
+#[tauri::command]
+fn delete_macro(handle: tauri::AppHandle, name: String) -> macros::MacroResult {
+    macros::delete_macro(&handle, name)
+}
+
+/**
+ * @brief Queues the simulation of a macro from the macro library
+ * @param handle: Tauri AppHandle
+ * @param window: Window running the macro, it is notified about the progress
+ * @param name: Name of the macro
+ * @param priority: Priority of the simulation, queued simulations with higher priority are simulated first, defaults to 0
+ * @return SimulationResult: Struct containing the ID of the simulation or the reason why it wasn't queued
+ */
+#[tauri::command]
+fn run_macro(
+    handle: tauri::AppHandle,
+    window: tauri::Window,
+    name: String,
+    priority: Option<i32>,
+) -> inputsim::SimulationResult {
+    macros::run_macro(handle, window.label().to_string(), name, priority.unwrap_or(0))
+}
+
+/**
+ * @brief Starts recording input events into a simulation sequence, an ongoing recording is discarded
+ * @param window: Window receiving the recording when it is stopped by the stop key
+ * @param options: Options of the recording
+ * @return RecordingResult: Struct containing the result of the operation
+ */
+#[tauri::command]
+fn start_macro_recording(window: tauri::Window, options: recorder::RecordingOptions) -> recorder::RecordingResult {
+    recorder::start_recording(window.label().to_string(), options)
+}
+
+/**
+ * @brief Stops recording input events
+ * @return RecordingResult: Struct containing the recorded simulation sequence
+ */
+#[tauri::command]
+fn stop_macro_recording() -> recorder::RecordingResult {
+    recorder::stop_recording()
+}
+
+/**
+ * @brief Gets the names of all keys that can be used in key combinations and input simulation
+ * @return Vec<String>: Names of all keys
+ */
+#[tauri::command]
+fn get_key_names() -> Vec<String> {
+    keys::get_key_names()
+}
+
+/**
+ * @brief Sets whether key combinations use the character produced by the keyboard layout instead of the physical key
+ * @param enabled: True to bind by character, false to bind by physical key position
+ */
+#[tauri::command]
+fn set_bind_by_character(enabled: bool) {
+    keybinds::set_bind_by_character(enabled);
+}
+
+/**
+ * @brief Sets the time window for detecting double-tap shortcuts
+ * @param window_ms: Maximum time between the two taps in milliseconds
+ */
+#[tauri::command]
+fn set_double_tap_window(window_ms: u64) {
+    keybinds::set_double_tap_window(window_ms);
+}
+
+/**
+ * @brief Registers a shortcut of a module window or updates an already registered one
+ * @param window_label: Label of the window the shortcut belongs to
+ * @param id: ID of the shortcut
+ * @param keybinds: Key combinations bound to the shortcut
+ * @param condition: Condition on the focused window (WM_CLASS, title regex or process name), the shortcut is only triggered while it is met
+ * @param consume: If true, the key combinations are grabbed so they are not passed to the focused application, falls back to only observing them if grabbing fails
+ * @param priority: Priority of the shortcut, shortcuts bound to the same key combination are triggered in order of priority
+ * @param exclusive: If true, shortcuts with lower priority bound to the same key combination are not triggered
+ * @return ShortcutResult: Struct containing the result of the operation
+ */
+#[tauri::command]
+fn register_shortcut(
+    window_label: String,
+    id: String,
+    keybinds: Vec<String>,
+    condition: Option<shortcuts::WindowCondition>,
+    consume: bool,
+    priority: Option<i32>,
+    exclusive: Option<bool>,
+) -> shortcuts::ShortcutResult {
+    shortcuts::register_shortcut(
+        window_label,
+        id,
+        keybinds,
+        condition,
+        consume,
+        priority.unwrap_or(0),
+        exclusive.unwrap_or(false),
+    )
+}
+
+/**
+ * @brief Gets the conflicts between the key combinations of the registered shortcuts
+ * @return Vec<ShortcutConflict>: Pairs of shortcuts triggered by the same key presses, including generic modifiers (Ctrl+A and LCtrl+A) and first taps of double-taps
+ */
+#[tauri::command]
+fn get_shortcut_conflicts() -> Vec<shortcuts::ShortcutConflict> {
+    shortcuts::get_shortcut_conflicts()
+}
+
+/**
+ * @brief Unregisters a shortcut of a module window
+ * @param id: ID of the shortcut
+ */
+#[tauri::command]
+fn unregister_shortcut(id: String) {
+    shortcuts::unregister_shortcut(id);
+}
+
+/**
+ * @brief Sets the key remapping rules of the active profile, the source keys are grabbed and replaced by the target keys
+ * @param rules: Remapping rules, empty to disable remapping
+ * @return RemapResult: Struct containing the result of the operation
+ */
+#[tauri::command]
+fn set_remap_rules(rules: Vec<remap::RemapRule>) -> remap::RemapResult {
+    remap::set_remap_rules(rules)
+}
+
+/**
+ * @brief Sets the hotstrings, typed abbreviations are replaced by their replacement text in any application
+ * @param hotstrings: Hotstrings, empty to disable text expansion
+ * @return HotstringResult: Struct containing the result of the operation
+ */
+#[tauri::command]
+fn set_hotstrings(hotstrings: Vec<hotstrings::Hotstring>) -> hotstrings::HotstringResult {
+    hotstrings::set_hotstrings(hotstrings)
+}
+
+/**
+ * @brief Pauses or resumes dispatching of shortcuts, only the resume combo is handled while paused
+ * @param handle: Tauri AppHandle
+ * @param paused: True to pause, false to resume
+ */
+#[tauri::command]
+fn set_shortcuts_paused(handle: tauri::AppHandle, paused: bool) {
+    keybinds::set_paused(handle, paused);
+}
+
+/**
+ * @brief Gets whether dispatching of shortcuts is paused
+ * @return bool: True if shortcuts are paused
+ */
+#[tauri::command]
+fn get_shortcuts_paused() -> bool {
+    keybinds::is_paused()
+}
+
+/**
+ * @brief Sets the key combination resuming paused shortcuts
+ * @param combo: Key combination string
+ * @return ComboResult: Unsuccessful if the key combination is invalid, the previous one is kept then
+ */
+#[tauri::command]
+fn set_resume_combo(combo: String) -> keybinds::ComboResult {
+    keybinds::set_resume_combo(combo)
+}
+
+/**
+ * @brief Sets the key combination stopping all input simulations and releasing the keys they hold
+ * @param combo: Key combination string
+ * @return ComboResult: Unsuccessful if the key combination is invalid, the previous one is kept then
+ */
+#[tauri::command]
+fn set_panic_combo(combo: String) -> keybinds::ComboResult {
+    keybinds::set_panic_combo(combo)
+}
+
+/**
+ * @brief Takes the modules that were open when the Overfloat window was reloaded by the app-level action
+ * @return Option<Vec<ReopenedModule>>: Modules serialized like in profiles, None if the window wasn't reloaded
+ */
+#[tauri::command]
+fn take_reopened_modules() -> Option<Vec<appactions::ReopenedModule>> {
+    appactions::take_reopened_modules()
+}
+
+/**
+ * @brief Gets the current state of the keyboard
+ * @return KeyboardStateSnapshot: Struct containing the pressed keys and the states of the lock keys
+ */
+#[tauri::command]
+fn get_keyboard_state() -> keybinds::KeyboardStateSnapshot {
+    keybinds::query_keyboard_state()
+}
+
+/**
+ * @brief Subscribes or unsubscribes a window to keyboard state change events
+ * @param window: Window receiving the events
+ * @param enabled: True to subscribe, false to unsubscribe
+ */
+#[tauri::command]
+fn set_keyboard_state_events(window: tauri::Window, enabled: bool) {
+    keybinds::set_keyboard_state_events(window.label(), enabled);
+}
+
+/**
+ * @brief Captures the next complete key combination, shortcuts are not dispatched while capturing
+ * @param timeout_ms: Time to wait for the key combination in milliseconds, waits indefinitely if not set
+ * @return KeybindCaptureResult: Struct containing the captured key combination
+ */
+#[tauri::command]
+async fn capture_keybind(timeout_ms: Option<u64>) -> keybinds::KeybindCaptureResult {
+    keybinds::capture_keybind(timeout_ms).await
+}
+
+/**
+ * @brief Cancels the ongoing key combination capture
+ */
+#[tauri::command]
+fn cancel_keybind_capture() {
+    keybinds::cancel_keybind_capture();
 }
 
 /**
@@ -438,28 +939,49 @@ fn get_install_dir() -> String{
 fn main() {
     // Create the tray menu
     let overfloat = CustomMenuItem::new("Overfloat".to_string(), "✔ Overfloat");
+    let pause = CustomMenuItem::new(
+        keybinds::PAUSE_TRAY_ITEM_ID.to_string(),
+        keybinds::PAUSE_TRAY_ITEM_TITLE,
+    );
     let quit = CustomMenuItem::new("Quit".to_string(), "Quit");
     let tray_menu = SystemTrayMenu::new()
         .add_item(overfloat)
+        .add_item(pause)
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(quit);
 
-    // Create a keyboard state object
-    let mut keyboard_state = keybinds::KeyboardState::new();
-
     // Run the Tauri application
     tauri::Builder::default()
         .setup(|app| {
+            // Handle the errors of the connections to the X server before any of them is opened
+            keygrab::install_x_error_handler();
+
             // Setup callback for keyboard events
             let handle = app.handle();
-            let callback = move |event: rdev::Event| match event.event_type {
-                rdev::EventType::KeyPress(_) => {
-                    keyboard_state.handle_key_press_event(handle.clone(), event)
-                }
-                rdev::EventType::KeyRelease(_) => {
-                    keyboard_state.handle_key_press_event(handle.clone(), event)
+            let callback = move |event: rdev::Event| {
+                // Events simulated by input simulations are observed as well, recognize them by their identity
+                let simulated: bool = inputsim::take_simulated_event(&event.event_type);
+
+                // Record all events of the user while recording a macro, including mouse moves,
+                // events simulated by the remapper are recognized by the remapper until it handles them
+                let synthetic: bool = simulated || remap::is_synthetic(&event.event_type);
+                recorder::handle_event(&handle, &event, synthetic);
+
+                match event.event_type {
+                    rdev::EventType::KeyPress(_) => {
+                        keybinds::handle_key_press_event(handle.clone(), event, simulated)
+                    }
+                    rdev::EventType::KeyRelease(_) => {
+                        keybinds::handle_key_press_event(handle.clone(), event, simulated)
+                    }
+                    rdev::EventType::ButtonPress(_) => {
+                        keybinds::handle_key_press_event(handle.clone(), event, simulated)
+                    }
+                    rdev::EventType::ButtonRelease(_) => {
+                        keybinds::handle_key_press_event(handle.clone(), event, simulated)
+                    }
+                    _ => {}
                 }
-                _ => {}
             };
 
             // Spawn a new async task for listening to rdev keyboard events
@@ -469,6 +991,18 @@ fn main() {
                 }
             });
 
+            // Load the key combinations of the app-level actions, they don't depend on the frontend
+            appactions::load_app_actions(&get_config());
+
+            // Load the key combinations of the saved macros, they run without the frontend
+            macros::load_macro_bindings(&app.handle());
+
+            // Periodically check for keys stuck as pressed because their release was missed
+            keybinds::start_stale_key_watcher();
+
+            // Grab the key combinations of consuming shortcuts
+            keygrab::start_key_grabber();
+
             Ok(())
         })
         .system_tray(SystemTray::new().with_menu(tray_menu))
@@ -476,11 +1010,13 @@ fn main() {
             // Handle system tray events
             SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
                 "Quit" => {
+                    inputsim::release_all_inputs();
                     app.exit(0);
                 }
                 "Overfloat" => {
                     tray_toggle_window(app.clone(), "Overfloat", "Overfloat", "Overfloat")
                 }
+                "Pause" => keybinds::toggle_paused(app.clone()),
                 _ => {}
             },
             _ => {}
@@ -488,17 +1024,61 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             watch_path,
             input_simulation,
+            cancel_input_simulation,
+            set_simulation_policy,
+            start_macro_recording,
+            stop_macro_recording,
+            list_macros,
+            save_macro,
+            load_macro,
+            delete_macro,
+            run_macro,
+            check_input_script,
+            run_input_script,
+            get_key_names,
+            set_bind_by_character,
+            set_double_tap_window,
+            capture_keybind,
+            cancel_keybind_capture,
+            register_shortcut,
+            unregister_shortcut,
+            get_shortcut_conflicts,
+            set_remap_rules,
+            set_hotstrings,
+            set_shortcuts_paused,
+            get_shortcuts_paused,
+            set_resume_combo,
+            set_panic_combo,
+            get_keyboard_state,
+            set_keyboard_state_events,
+            take_reopened_modules,
             get_profiles,
             save_profiles,
             get_config,
             save_config,
+            get_remaps,
+            save_remaps,
+            get_hotstrings,
+            save_hotstrings,
             hide_app,
             quit_app,
             read_file,
             write_file,
             stop_watching,
         ])
+        .on_window_event(|event| {
+            // Release the keys held by the simulations of closed modules
+            if let tauri::WindowEvent::Destroyed = event.event() {
+                inputsim::window_destroyed(&event.window().app_handle(), event.window().label());
+            }
+        })
         .device_event_filter(tauri::DeviceEventFilter::Always)
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_handle, event| {
+            // Release the keys held by simulations, they would stay held system-wide otherwise
+            if let tauri::RunEvent::Exit = event {
+                inputsim::release_all_inputs();
+            }
+        });
 }
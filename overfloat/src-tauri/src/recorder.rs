@@ -0,0 +1,290 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/recorder.rs                        *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+use rdev;
+use tauri::Manager;
+
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::inputsim::{SimulationStep, MAX_DELAY_MS};
+use crate::keys::{button_to_string, key_to_string, string_to_key};
+
+/*
+* Lazily initialized global instance of MacroRecorder holding the ongoing recording
+* This has to be global because it needs to be accessible from tauri commands and the input listener
+*/
+lazy_static! {
+    static ref MACRO_RECORDER: Mutex<MacroRecorder> = Mutex::new(MacroRecorder::new());
+}
+
+/*
+* Get the global instance of MacroRecorder
+*/
+fn get_macro_recorder() -> &'static Mutex<MacroRecorder> {
+    &MACRO_RECORDER
+}
+
+// Struct for the options of a recording
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct RecordingOptions {
+    // Whether mouse moves are recorded, they make up most of the events otherwise
+    #[serde(default)]
+    record_mouse_moves: bool,
+    // Delays are rounded to a multiple of this many milliseconds, 0 keeps them exact
+    #[serde(default)]
+    quantize_ms: u64,
+    // Key stopping the recording, it is not recorded
+    #[serde(default)]
+    stop_key: Option<String>,
+}
+
+// Struct for returning results from recording operations
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingResult {
+    successful: bool,
+    message: String,
+    steps: Vec<SimulationStep>,
+}
+
+// Struct for an ongoing recording
+struct Recording {
+    window_label: String,
+    options: RecordingOptions,
+    stop_key: Option<rdev::Key>,
+    steps: Vec<SimulationStep>,
+    last_event_time: Option<std::time::SystemTime>,
+    // Names of the keys and buttons pressed since the recording started
+    pressed: HashSet<String>,
+}
+
+// Struct for the recorder state
+struct MacroRecorder {
+    recording: Option<Recording>,
+}
+
+/**
+ * @brief Function to convert an input event to a simulation step
+ * @param event_type - the input event
+ * @param record_mouse_moves - whether mouse moves are recorded
+ * @return Option<SimulationStep> - None if the event isn't recorded
+ */
+fn event_to_step(event_type: &rdev::EventType, record_mouse_moves: bool) -> Option<SimulationStep> {
+    match event_type {
+        rdev::EventType::KeyPress(key) => Some(SimulationStep::KeyDown {
+            key: key_to_string(*key),
+        }),
+        rdev::EventType::KeyRelease(key) => Some(SimulationStep::KeyUp {
+            key: key_to_string(*key),
+        }),
+        rdev::EventType::ButtonPress(button) => Some(SimulationStep::MouseDown {
+            button: button_to_string(*button)?,
+        }),
+        rdev::EventType::ButtonRelease(button) => Some(SimulationStep::MouseUp {
+            button: button_to_string(*button)?,
+        }),
+        rdev::EventType::Wheel { delta_x, delta_y } => Some(SimulationStep::MouseScroll {
+            delta_x: *delta_x,
+            delta_y: *delta_y,
+        }),
+        rdev::EventType::MouseMove { x, y } if record_mouse_moves => {
+            Some(SimulationStep::MouseMove { x: *x, y: *y })
+        }
+        _ => None,
+    }
+}
+
+// Implementation of Recording
+impl Recording {
+    /**
+     * @brief Function to record an input event preceded by the delay since the previous event
+     * @param event - rdev::Event
+     */
+    fn record(&mut self, event: &rdev::Event) {
+        let step: SimulationStep = match event_to_step(&event.event_type, self.options.record_mouse_moves) {
+            Some(step) => step,
+            None => return,
+        };
+
+        // Releases of keys and buttons pressed before the recording started (e.g. the click starting it) are skipped
+        match &step {
+            SimulationStep::KeyDown { key } => {
+                self.pressed.insert(key.clone());
+            }
+            SimulationStep::MouseDown { button } => {
+                self.pressed.insert(button.clone());
+            }
+            SimulationStep::KeyUp { key } => {
+                if !self.pressed.remove(key) {
+                    return;
+                }
+            }
+            SimulationStep::MouseUp { button } => {
+                if !self.pressed.remove(button) {
+                    return;
+                }
+            }
+            _ => {}
+        }
+
+        // The delay before the first event is not recorded
+        if let Some(last_event_time) = self.last_event_time {
+            let mut delay_ms: u64 = event
+                .time
+                .duration_since(last_event_time)
+                .map(|delay| delay.as_millis() as u64)
+                .unwrap_or(0);
+
+            if self.options.quantize_ms > 0 {
+                let quantum: u64 = self.options.quantize_ms;
+                delay_ms = (delay_ms + quantum / 2) / quantum * quantum;
+            }
+
+            if delay_ms > 0 {
+                self.steps.push(SimulationStep::Delay {
+                    ms: delay_ms.min(MAX_DELAY_MS),
+                });
+            }
+        }
+
+        self.last_event_time = Some(event.time);
+        self.steps.push(step);
+    }
+}
+
+// Implementation of MacroRecorder
+impl MacroRecorder {
+    fn new() -> MacroRecorder {
+        MacroRecorder { recording: None }
+    }
+
+    /**
+     * @brief Function to start recording, an ongoing recording is discarded
+     * @param window_label - label of the window receiving the recording when it is stopped by the stop key
+     * @param options - options of the recording
+     * @return Result<(), String> - error message if the options are invalid
+     */
+    fn start(&mut self, window_label: String, options: RecordingOptions) -> Result<(), String> {
+        let stop_key: Option<rdev::Key> = match &options.stop_key {
+            Some(key) => Some(string_to_key(key).map_err(|_| format!("Unknown stop key: {}", key))?),
+            None => None,
+        };
+
+        self.recording = Some(Recording {
+            window_label: window_label,
+            options: options,
+            stop_key: stop_key,
+            steps: Vec::new(),
+            last_event_time: None,
+            pressed: HashSet::new(),
+        });
+
+        Ok(())
+    }
+
+    /**
+     * @brief Function to stop recording
+     * @return Option<Recording> - the stopped recording, None if not recording
+     */
+    fn stop(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    /**
+     * @brief Function to handle an input event
+     * @param handle - tauri::AppHandle
+     * @param event - rdev::Event
+     * @param synthetic - whether the event was simulated by Overfloat (an input simulation or the remapper)
+     * @note Pressing the stop key stops the recording and sends it to the window that started it.
+     * Synthetic events are not recorded, the recording contains only the input of the user
+     */
+    fn handle_event(&mut self, handle: &tauri::AppHandle, event: &rdev::Event, synthetic: bool) {
+        let recording: &mut Recording = match &mut self.recording {
+            Some(recording) => recording,
+            None => return,
+        };
+
+        if synthetic {
+            return;
+        }
+
+        if let rdev::EventType::KeyPress(key) = event.event_type {
+            if Some(key) == recording.stop_key {
+                if let Some(recording) = self.stop() {
+                    let _ = handle.emit_to(
+                        &recording.window_label,
+                        "Overfloat://MacroRecorded",
+                        RecordingResult {
+                            successful: true,
+                            message: String::new(),
+                            steps: recording.steps,
+                        },
+                    );
+                }
+                return;
+            }
+        }
+
+        recording.record(event);
+    }
+}
+
+/**
+ * @brief Function to start recording input events into a simulation sequence
+ * @param window_label - label of the window receiving the recording when it is stopped by the stop key
+ * @param options - options of the recording
+ * @return RecordingResult - error message if the options are invalid
+ * @note Exposes the operation to tauri commands
+ */
+pub fn start_recording(window_label: String, options: RecordingOptions) -> RecordingResult {
+    let mut macro_recorder = get_macro_recorder().lock().unwrap();
+    match macro_recorder.start(window_label, options) {
+        Ok(()) => RecordingResult {
+            successful: true,
+            message: String::new(),
+            steps: Vec::new(),
+        },
+        Err(message) => RecordingResult {
+            successful: false,
+            message: message,
+            steps: Vec::new(),
+        },
+    }
+}
+
+/**
+ * @brief Function to stop recording
+ * @return RecordingResult - the recorded simulation sequence
+ * @note Exposes the operation to tauri commands
+ */
+pub fn stop_recording() -> RecordingResult {
+    let mut macro_recorder = get_macro_recorder().lock().unwrap();
+    match macro_recorder.stop() {
+        Some(recording) => RecordingResult {
+            successful: true,
+            message: String::new(),
+            steps: recording.steps,
+        },
+        None => RecordingResult {
+            successful: false,
+            message: String::from("Not recording"),
+            steps: Vec::new(),
+        },
+    }
+}
+
+/**
+ * @brief Function to record an input event if recording
+ * @param handle - tauri::AppHandle
+ * @param event - rdev::Event
+ * @param synthetic - whether the event was simulated by Overfloat (an input simulation or the remapper)
+ * @note Exposes the operation to the input listener
+ */
+pub fn handle_event(handle: &tauri::AppHandle, event: &rdev::Event, synthetic: bool) {
+    let mut macro_recorder = get_macro_recorder().lock().unwrap();
+    macro_recorder.handle_event(handle, event, synthetic);
+}
@@ -0,0 +1,412 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/remap.rs                           *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+use rdev;
+use std::collections::{HashMap, HashSet};
+use std::time;
+
+use lazy_static::lazy_static;
+use std::sync::{mpsc, Mutex};
+
+use crate::inputsim::{same_event, SIMULATED_EVENT_TIMEOUT_MS};
+use crate::keygrab::{
+    any_modifier_combo, grabbing_supported, is_grabbed, release_keyboard_grab, request_grab_update,
+};
+use crate::keys::{key_to_string, string_to_button, string_to_key};
+
+/*
+* Lazily initialized global instance of Remapper holding the remapping rules of the active profile
+* This has to be global because it needs to be accessible from tauri commands, the keyboard listener and the key grabber
+*/
+lazy_static! {
+    static ref REMAPPER: Mutex<Remapper> = Mutex::new(Remapper::new());
+}
+
+/*
+* Get the global instance of Remapper
+*/
+fn get_remapper() -> &'static Mutex<Remapper> {
+    &REMAPPER
+}
+
+// Lock keys toggle their lock state even when grabbed, the state is restored after remapping them
+const LOCK_KEYS: [rdev::Key; 3] = [rdev::Key::CapsLock, rdev::Key::NumLock, rdev::Key::ScrollLock];
+
+// Struct for a remapping rule as declared in a profile
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct RemapRule {
+    source: String,
+    target: String,
+    while_held: Option<String>,
+}
+
+// Struct for returning results from remapping operations
+#[derive(Clone, serde::Serialize)]
+pub struct RemapResult {
+    successful: bool,
+    message: String,
+}
+
+// Key or mouse button that has to be held for a remapping rule to apply
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Holder {
+    Key(rdev::Key),
+    Button(rdev::Button),
+}
+
+// Struct for a parsed remapping rule
+struct Rule {
+    source: rdev::Key,
+    target: rdev::Key,
+    while_held: Option<Holder>,
+}
+
+// Struct for an event simulated by the remapper that the keyboard listener is yet to observe
+struct SyntheticEvent {
+    event_type: rdev::EventType,
+    hidden: bool,
+    simulated_at: time::Instant,
+}
+
+// Struct for the remapping state
+struct Remapper {
+    rules: Vec<Rule>,
+    held: HashSet<Holder>,
+    active: HashMap<rdev::Key, rdev::Key>,
+    synthetic: Vec<SyntheticEvent>,
+    sender: mpsc::Sender<rdev::EventType>,
+}
+
+/**
+ * @brief Function to parse a key or mouse button name of a holder
+ * @param name - name of the key or mouse button
+ * @return Result<Holder, String>
+ */
+fn parse_holder(name: &str) -> Result<Holder, String> {
+    if let Ok(key) = string_to_key(name) {
+        return Ok(Holder::Key(key));
+    }
+
+    match string_to_button(name) {
+        Ok(button) => Ok(Holder::Button(button)),
+        Err(_) => Err(format!("Unknown key or mouse button: {}", name)),
+    }
+}
+
+// Implementation of Remapper
+impl Remapper {
+    fn new() -> Remapper {
+        // Simulate the target events on a separate thread so the keyboard listener isn't blocked
+        let (sender, receiver) = mpsc::channel::<rdev::EventType>();
+        std::thread::spawn(move || {
+            for event_type in receiver {
+                // The held source key keeps the keyboard grabbed by Overfloat,
+                // release the grab so the focused window receives the target press
+                if let rdev::EventType::KeyPress(_) = event_type {
+                    release_keyboard_grab();
+                }
+
+                if rdev::simulate(&event_type).is_err() {
+                    println!("Failed to send event: {:?}", event_type);
+                }
+            }
+        });
+
+        Remapper {
+            rules: Vec::new(),
+            held: HashSet::new(),
+            active: HashMap::new(),
+            synthetic: Vec::new(),
+            sender: sender,
+        }
+    }
+
+    /**
+     * @brief Function to simulate an event
+     * @param event_type - type of the event
+     * @param hidden - whether the event is hidden from the shortcuts when the keyboard listener observes it
+     */
+    fn simulate(&mut self, event_type: rdev::EventType, hidden: bool) {
+        self.synthetic.push(SyntheticEvent {
+            event_type: event_type,
+            hidden: hidden,
+            simulated_at: time::Instant::now(),
+        });
+        let _ = self.sender.send(event_type);
+    }
+
+    /**
+     * @brief Function to set the remapping rules
+     * @param rules - remapping rules, empty to disable remapping
+     * @return Result<(), String> - error message if a rule is invalid, the previous rules are kept in that case
+     */
+    fn set_rules(&mut self, rules: Vec<RemapRule>) -> Result<(), String> {
+        // Parse the rules
+        let mut parsed: Vec<Rule> = Vec::new();
+        for rule in rules {
+            parsed.push(Rule {
+                source: string_to_key(&rule.source)
+                    .map_err(|_| format!("Unknown key: {}", rule.source))?,
+                target: string_to_key(&rule.target)
+                    .map_err(|_| format!("Unknown key: {}", rule.target))?,
+                while_held: match &rule.while_held {
+                    Some(name) => Some(parse_holder(name)?),
+                    None => None,
+                },
+            });
+        }
+
+        // Release the targets of the currently remapped keys
+        self.release_active();
+
+        self.rules = parsed;
+        request_grab_update();
+
+        Ok(())
+    }
+
+    /**
+     * @brief Function to release the targets of all currently remapped keys
+     * @note The lock states of remapped lock keys are restored like when they are released
+     */
+    fn release_active(&mut self) {
+        let active: Vec<(rdev::Key, rdev::Key)> = self.active.drain().collect();
+        for (source, target) in active {
+            self.simulate(rdev::EventType::KeyRelease(target), false);
+
+            if LOCK_KEYS.contains(&source) {
+                self.simulate(rdev::EventType::KeyPress(source), true);
+                self.simulate(rdev::EventType::KeyRelease(source), true);
+            }
+        }
+    }
+
+    /**
+     * @brief Function to get the key combinations of the source keys that should currently be grabbed
+     * @return HashSet<String>
+     * @note Source keys are grabbed while their rule applies and while they are held
+     */
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn grabbed_sources(&self) -> HashSet<String> {
+        self.rules
+            .iter()
+            .filter(|rule| match &rule.while_held {
+                Some(holder) => self.held.contains(holder),
+                None => true,
+            })
+            .map(|rule| rule.source)
+            .chain(self.active.keys().cloned())
+            .map(|source| any_modifier_combo(&key_to_string(source)))
+            .collect()
+    }
+
+    /**
+     * @brief Function to update the held keys and mouse buttons that rules depend on
+     * @param holder - key or mouse button
+     * @param pressed - whether it was pressed or released
+     */
+    fn update_holder(&mut self, holder: Holder, pressed: bool) {
+        if !self.rules.iter().any(|rule| rule.while_held == Some(holder)) {
+            return;
+        }
+
+        let changed: bool = if pressed {
+            self.held.insert(holder)
+        } else {
+            self.held.remove(&holder)
+        };
+
+        // Grab or ungrab the source keys of the rules depending on the holder
+        if changed {
+            request_grab_update();
+        }
+    }
+
+    /**
+     * @brief Function to handle key press event
+     * @param key - rdev::Key
+     * @return bool - whether the event was remapped and shouldn't be handled further
+     */
+    fn keydown(&mut self, key: rdev::Key) -> bool {
+        // Auto-repeat of a remapped key repeats the target
+        if let Some(target) = self.active.get(&key).cloned() {
+            self.simulate(rdev::EventType::KeyPress(target), false);
+            return true;
+        }
+
+        self.update_holder(Holder::Key(key), true);
+
+        // Find a rule that applies to the key, keys that aren't grabbed are only observed and can't be remapped
+        let target: Option<rdev::Key> = self
+            .rules
+            .iter()
+            .find(|rule| {
+                rule.source == key
+                    && match &rule.while_held {
+                        Some(holder) => self.held.contains(holder),
+                        None => true,
+                    }
+            })
+            .map(|rule| rule.target);
+
+        match target {
+            Some(target) if is_grabbed(&any_modifier_combo(&key_to_string(key))) => {
+                self.active.insert(key, target);
+                self.simulate(rdev::EventType::KeyPress(target), false);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /**
+     * @brief Function to handle key release event
+     * @param key - rdev::Key
+     * @return bool - whether the event was remapped and shouldn't be handled further
+     */
+    fn keyup(&mut self, key: rdev::Key) -> bool {
+        self.update_holder(Holder::Key(key), false);
+
+        // Release the target of the key, even if the rule doesn't apply anymore
+        let target: rdev::Key = match self.active.remove(&key) {
+            Some(target) => target,
+            None => return false,
+        };
+        self.simulate(rdev::EventType::KeyRelease(target), false);
+
+        // Toggle the lock key again to restore its lock state
+        if LOCK_KEYS.contains(&key) {
+            self.simulate(rdev::EventType::KeyPress(key), true);
+            self.simulate(rdev::EventType::KeyRelease(key), true);
+        }
+
+        // Ungrab the key if its rule doesn't apply anymore
+        request_grab_update();
+        true
+    }
+
+    /**
+     * @brief Function to forget the simulated events the keyboard listener missed
+     */
+    fn forget_missed_synthetic(&mut self) {
+        let timeout: time::Duration = time::Duration::from_millis(SIMULATED_EVENT_TIMEOUT_MS);
+        self.synthetic.retain(|synthetic| synthetic.simulated_at.elapsed() < timeout);
+    }
+
+    /**
+     * @brief Function to check whether an event was simulated by the remapper
+     * @param event_type - type of the rdev::Event
+     * @return bool
+     * @note Keys are compared by their keycodes like simulated events of input simulations.
+     * The event is still recognized by handle_event afterwards
+     */
+    fn is_synthetic(&mut self, event_type: &rdev::EventType) -> bool {
+        self.forget_missed_synthetic();
+        self.synthetic
+            .iter()
+            .any(|synthetic| same_event(&synthetic.event_type, event_type))
+    }
+
+    /**
+     * @brief Function to handle an input event
+     * @param event_type - type of the rdev::Event
+     * @return bool - whether the event shouldn't be handled further
+     * @note Events simulated by the remapper are not remapped again
+     */
+    fn handle_event(&mut self, event_type: &rdev::EventType) -> bool {
+        // Check if the event was simulated by the remapper, keys are compared by their keycodes
+        self.forget_missed_synthetic();
+        if let Some(position) = self
+            .synthetic
+            .iter()
+            .position(|synthetic| same_event(&synthetic.event_type, event_type))
+        {
+            return self.synthetic.remove(position).hidden;
+        }
+
+        match event_type {
+            rdev::EventType::KeyPress(key) => self.keydown(*key),
+            rdev::EventType::KeyRelease(key) => self.keyup(*key),
+            rdev::EventType::ButtonPress(button) => {
+                self.update_holder(Holder::Button(*button), true);
+                false
+            }
+            rdev::EventType::ButtonRelease(button) => {
+                self.update_holder(Holder::Button(*button), false);
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+/**
+ * @brief Function to set the remapping rules of the active profile
+ * @param rules - remapping rules, empty to disable remapping
+ * @return RemapResult - unsuccessful if a rule is invalid or key combinations can't be grabbed on this platform
+ * @note Exposes the operation to tauri commands
+ */
+pub fn set_remap_rules(rules: Vec<RemapRule>) -> RemapResult {
+    // Source keys have to be grabbed to be remapped, disabling remapping is always possible
+    if !rules.is_empty() && !grabbing_supported() {
+        return RemapResult {
+            successful: false,
+            message: String::from("Remapping is not supported on this platform"),
+        };
+    }
+
+    let mut remapper = get_remapper().lock().unwrap();
+    match remapper.set_rules(rules) {
+        Ok(()) => RemapResult {
+            successful: true,
+            message: String::new(),
+        },
+        Err(message) => RemapResult {
+            successful: false,
+            message: message,
+        },
+    }
+}
+
+/**
+ * @brief Function to release the targets of all currently remapped keys
+ * @note Exposes the operation to the emergency stop, the targets would stay held until their source keys are released
+ */
+pub fn release_remapped_keys() {
+    let mut remapper = get_remapper().lock().unwrap();
+    remapper.release_active();
+    request_grab_update();
+}
+
+/**
+ * @brief Function to get the key combinations of the source keys that should currently be grabbed
+ * @return HashSet<String>
+ * @note Exposes the operation to the key grabber, grabbing is only supported on Linux
+ */
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn grabbed_sources() -> HashSet<String> {
+    get_remapper().lock().unwrap().grabbed_sources()
+}
+
+/**
+ * @brief Function to remap an input event
+ * @param event_type - type of the rdev::Event
+ * @return bool - whether the event was remapped and shouldn't be handled further
+ * @note Exposes the operation to the keyboard listener
+ */
+pub fn handle_event(event_type: &rdev::EventType) -> bool {
+    get_remapper().lock().unwrap().handle_event(event_type)
+}
+
+/**
+ * @brief Function to check whether an input event was simulated by the remapper
+ * @param event_type - type of the rdev::Event
+ * @return bool
+ * @note Exposes the operation to the input listener
+ */
+pub fn is_synthetic(event_type: &rdev::EventType) -> bool {
+    get_remapper().lock().unwrap().is_synthetic(event_type)
+}
@@ -0,0 +1,1373 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/script.rs                          *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+use rdev;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use lazy_static::lazy_static;
+use std::sync::{mpsc, Mutex};
+
+use crate::activewindow::{get_active_window, ActiveWindow};
+use crate::inputsim::{self, SimulationResult, SimulationStep};
+use crate::keys::string_to_key;
+
+/*
+* Lazily initialized global list of the keys running scripts are waiting for, with the senders waking them up
+* This has to be global because it needs to be accessible from the simulation worker and the keyboard listener
+*/
+lazy_static! {
+    static ref KEY_WAITERS: Mutex<Vec<(rdev::Key, mpsc::Sender<()>)>> = Mutex::new(Vec::new());
+}
+
+/*
+* Get the global list of key waiters
+*/
+fn get_key_waiters() -> &'static Mutex<Vec<(rdev::Key, mpsc::Sender<()>)>> {
+    &KEY_WAITERS
+}
+
+/*
+* Lazily initialized global connection to the X server used for reading pixels of the screen, stored as an address
+* It is opened on the first read and kept open, the lock serializes the reads using it
+*/
+#[cfg(target_os = "linux")]
+lazy_static! {
+    static ref PIXEL_DISPLAY: Mutex<usize> = Mutex::new(0);
+}
+
+// Interval in milliseconds between checks for cancellation when waiting for a key to be pressed
+const WAIT_FOR_KEY_INTERVAL_MS: u64 = 10;
+
+// Symbols of the language, two-character symbols have to be matched first
+const SYMBOLS: [&str; 17] = [
+    "==", "!=", "<=", ">=", "{", "}", "(", ")", ",", "+", "-", "*", "/", "%", "=", "<", ">",
+];
+
+// Comparison operators usable in conditions
+const COMPARISONS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+// Struct for an error of a script, lines are numbered from 1
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ScriptError {
+    line: usize,
+    message: String,
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "Line {}: {}", self.line, self.message)
+    }
+}
+
+// Struct for returning results from running a script
+#[derive(Clone, serde::Serialize)]
+pub struct ScriptResult {
+    successful: bool,
+    message: String,
+    errors: Vec<ScriptError>,
+    job_id: Option<u64>,
+}
+
+// Token of a line of a script
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(i64),
+    Variable(String),
+    Word(String),
+    Text(String),
+    Color(u32),
+    Symbol(&'static str),
+}
+
+// Integer expression, evaluated when the script runs
+#[derive(Clone, Debug)]
+enum Expression {
+    Number(i64),
+    Variable(String),
+    Negate(Box<Expression>),
+    Binary(Box<Expression>, &'static str, Box<Expression>),
+    Random(Box<Expression>, Box<Expression>),
+}
+
+// Condition of an if block, evaluated when the script runs
+#[derive(Clone, Debug)]
+enum Condition {
+    Not(Box<Condition>),
+    Compare(Expression, &'static str, Expression),
+    Pixel(Expression, Expression, u32),
+    WindowTitle(Regex),
+    WindowClass(String),
+    WindowProcess(String),
+}
+
+// Instruction of a compiled script, blocks are compiled to jumps
+#[derive(Clone, Debug)]
+enum Instruction {
+    // Simulation step without variable arguments, validated when compiling
+    Step(SimulationStep),
+    MouseMove(Expression, Expression),
+    MouseScroll(Expression, Expression),
+    Delay(Expression),
+    WaitForKey(rdev::Key),
+    Set(String, Expression),
+    Jump(usize),
+    JumpUnless(Condition, usize),
+}
+
+// Block opened by a line ending with '{'
+enum Block {
+    // Index of the instruction checking the counter of the loop and the name of the counter
+    Repeat(usize, String),
+    // Index of the instruction jumping over the block when the condition isn't met
+    If(usize),
+    // Index of the instruction jumping over the else block at the end of the if block
+    Else(usize),
+}
+
+// Struct for a compiled script
+#[derive(Clone, Debug)]
+pub struct Script {
+    // Instructions with the numbers of the lines they were compiled from
+    instructions: Vec<(usize, Instruction)>,
+}
+
+// Struct for the tokens of a line being parsed
+struct TokenStream {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+// Struct for the compilation of a script
+struct Compiler {
+    instructions: Vec<(usize, Instruction)>,
+    // Open blocks with the numbers of the lines that opened them
+    blocks: Vec<(usize, Block)>,
+    // Variables assigned on the previous lines
+    defined: HashSet<String>,
+    errors: Vec<ScriptError>,
+}
+
+// Struct for the state of a running script
+struct ScriptRun<'a> {
+    module: &'a str,
+    delay_ms: u64,
+    cancelled: &'a AtomicBool,
+    variables: HashMap<String, i64>,
+    random_state: u64,
+}
+
+/**
+ * @brief Function to split a line of a script into tokens
+ * @param text - the line without the command
+ * @return Result<Vec<Token>, String> - error message if the line contains an invalid token
+ */
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut index: usize = 0;
+
+    // Collect the characters from the index while they match the predicate
+    let take_while = |index: &mut usize, predicate: &dyn Fn(char) -> bool| -> String {
+        let start: usize = *index;
+        while *index < chars.len() && predicate(chars[*index]) {
+            *index += 1;
+        }
+        chars[start..*index].iter().collect()
+    };
+
+    while index < chars.len() {
+        let c: char = chars[index];
+
+        if c.is_whitespace() {
+            index += 1;
+        } else if c.is_ascii_digit() {
+            let digits: String = take_while(&mut index, &|c| c.is_ascii_digit());
+            let number: i64 = digits.parse().map_err(|_| format!("Number too large: {}", digits))?;
+            tokens.push(Token::Number(number));
+        } else if c == '$' {
+            index += 1;
+            let name: String = take_while(&mut index, &|c| c.is_ascii_alphanumeric() || c == '_');
+            if name.is_empty() {
+                return Err(String::from("Expected a variable name after '$'"));
+            }
+            tokens.push(Token::Variable(name));
+        } else if c == '#' {
+            index += 1;
+            let digits: String = take_while(&mut index, &|c| c.is_ascii_alphanumeric());
+            match u32::from_str_radix(&digits, 16) {
+                Ok(color) if digits.len() == 6 => tokens.push(Token::Color(color)),
+                _ => return Err(format!("Invalid color: #{}, expected #RRGGBB", digits)),
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let word: String = take_while(&mut index, &|c| c.is_alphanumeric() || c == '_');
+            tokens.push(Token::Word(word));
+        } else if c == '"' {
+            index += 1;
+            let mut text: String = String::new();
+            loop {
+                match chars.get(index) {
+                    Some('"') => break,
+                    Some('\\') => {
+                        let escaped: char = match chars.get(index + 1) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('"') => '"',
+                            Some('\\') => '\\',
+                            Some(other) => return Err(format!("Unknown escape sequence: \\{}", other)),
+                            None => return Err(String::from("Unterminated string")),
+                        };
+                        text.push(escaped);
+                        index += 2;
+                    }
+                    Some(other) => {
+                        text.push(*other);
+                        index += 1;
+                    }
+                    None => return Err(String::from("Unterminated string")),
+                }
+            }
+            index += 1;
+            tokens.push(Token::Text(text));
+        } else {
+            let rest: String = chars[index..].iter().collect();
+            match SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+                Some(symbol) => {
+                    tokens.push(Token::Symbol(symbol));
+                    index += symbol.chars().count();
+                }
+                None => return Err(format!("Unexpected character: {}", c)),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/**
+ * @brief Function to describe a token in error messages
+ * @param token - the token, None at the end of the line
+ * @return String
+ */
+fn describe_token(token: Option<&Token>) -> String {
+    match token {
+        Some(Token::Number(number)) => number.to_string(),
+        Some(Token::Variable(name)) => format!("${}", name),
+        Some(Token::Word(word)) => word.clone(),
+        Some(Token::Text(text)) => format!("\"{}\"", text),
+        Some(Token::Color(color)) => format!("#{:06X}", color),
+        Some(Token::Symbol(symbol)) => symbol.to_string(),
+        None => String::from("end of line"),
+    }
+}
+
+/**
+ * @brief Function to get the color of a pixel of the screen
+ * @param x - horizontal coordinate of the pixel
+ * @param y - vertical coordinate of the pixel
+ * @return Option<u32> - color as 0xRRGGBB, None if the pixel is outside of the screen or the screen couldn't be read
+ * @note Uses XGetImage on the root window on Linux. The connection is registered, so errors of reading the screen
+ * (e.g. BadMatch on composited roots) don't reach the error handler of GDK
+ */
+#[cfg(target_os = "linux")]
+fn query_pixel_color(x: i64, y: i64) -> Option<u32> {
+    use crate::keygrab::register_display;
+    use x11::xlib;
+
+    let mut pixel_display = PIXEL_DISPLAY.lock().unwrap();
+
+    unsafe {
+        // Open a connection to the X server on the first read
+        if *pixel_display == 0 {
+            let display = xlib::XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+
+            register_display(display);
+            *pixel_display = display as usize;
+        }
+        let display = *pixel_display as *mut xlib::Display;
+
+        // Reading outside of the root window is an X error, check the bounds first
+        let screen = xlib::XDefaultScreen(display);
+        if x < 0
+            || y < 0
+            || x >= xlib::XDisplayWidth(display, screen) as i64
+            || y >= xlib::XDisplayHeight(display, screen) as i64
+        {
+            return None;
+        }
+
+        let root = xlib::XDefaultRootWindow(display);
+        let image = xlib::XGetImage(
+            display,
+            root,
+            x as i32,
+            y as i32,
+            1,
+            1,
+            xlib::XAllPlanes(),
+            xlib::ZPixmap,
+        );
+        if image.is_null() {
+            return None;
+        }
+
+        // Extract the channels with the masks of the image
+        let pixel = xlib::XGetPixel(image, 0, 0);
+        let channel = |mask: std::os::raw::c_ulong| -> u32 {
+            if mask == 0 {
+                return 0;
+            }
+            let value = (pixel & mask) >> mask.trailing_zeros();
+            let bits = (mask >> mask.trailing_zeros()).count_ones();
+            (value * 255 / ((1 << bits) - 1)) as u32
+        };
+        let color: u32 = (channel((*image).red_mask) << 16)
+            | (channel((*image).green_mask) << 8)
+            | channel((*image).blue_mask);
+
+        xlib::XDestroyImage(image);
+        Some(color)
+    }
+}
+
+/**
+ * @brief Function to get the color of a pixel of the screen
+ * @param x - horizontal coordinate of the pixel
+ * @param y - vertical coordinate of the pixel
+ * @return Option<u32> - color as 0xRRGGBB, None if the pixel is outside of the screen or the screen couldn't be read
+ * @note Uses GetPixel on the screen device context on Windows
+ */
+#[cfg(target_os = "windows")]
+fn query_pixel_color(x: i64, y: i64) -> Option<u32> {
+    use winapi::um::{wingdi, winuser};
+
+    if x < i32::MIN as i64 || x > i32::MAX as i64 || y < i32::MIN as i64 || y > i32::MAX as i64 {
+        return None;
+    }
+
+    unsafe {
+        let device_context = winuser::GetDC(std::ptr::null_mut());
+        if device_context.is_null() {
+            return None;
+        }
+
+        let color = wingdi::GetPixel(device_context, x as i32, y as i32);
+        winuser::ReleaseDC(std::ptr::null_mut(), device_context);
+
+        if color == wingdi::CLR_INVALID {
+            return None;
+        }
+
+        // COLORREF is 0x00BBGGRR
+        Some(((color & 0xFF) << 16) | (color & 0xFF00) | ((color >> 16) & 0xFF))
+    }
+}
+
+/**
+ * @brief Function to get the color of a pixel of the screen
+ * @param x - horizontal coordinate of the pixel
+ * @param y - vertical coordinate of the pixel
+ * @return Option<u32> - always None, reading the screen is not supported on this platform
+ */
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn query_pixel_color(_x: i64, _y: i64) -> Option<u32> {
+    None
+}
+
+// Implementation of TokenStream
+impl TokenStream {
+    fn new(tokens: Vec<Token>) -> TokenStream {
+        TokenStream {
+            tokens: tokens,
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token: Option<Token> = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /**
+     * @brief Function to check whether the next token is a symbol and skip it if it is
+     * @param symbol - the symbol
+     * @return bool
+     */
+    fn accept(&mut self, symbol: &str) -> bool {
+        match self.peek() {
+            Some(Token::Symbol(next)) if *next == symbol => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /**
+     * @brief Function to skip the next token, which has to be the symbol
+     * @param symbol - the symbol
+     * @return Result<(), String> - error message if the next token is different
+     */
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        if self.accept(symbol) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}', found {}", symbol, describe_token(self.peek())))
+        }
+    }
+
+    /**
+     * @brief Function to check that all tokens were parsed
+     * @return Result<(), String> - error message if some tokens remain
+     */
+    fn expect_end(&self) -> Result<(), String> {
+        match self.peek() {
+            None => Ok(()),
+            token => Err(format!("Unexpected {}", describe_token(token))),
+        }
+    }
+
+    /**
+     * @brief Function to parse a sum or a difference
+     * @return Result<Expression, String>
+     */
+    fn expression(&mut self) -> Result<Expression, String> {
+        let mut expression: Expression = self.term()?;
+        loop {
+            let operator: &'static str = match self.peek() {
+                Some(Token::Symbol(symbol)) if *symbol == "+" || *symbol == "-" => symbol,
+                _ => return Ok(expression),
+            };
+            self.position += 1;
+            expression = Expression::Binary(Box::new(expression), operator, Box::new(self.term()?));
+        }
+    }
+
+    /**
+     * @brief Function to parse a product, a quotient or a remainder
+     * @return Result<Expression, String>
+     */
+    fn term(&mut self) -> Result<Expression, String> {
+        let mut expression: Expression = self.factor()?;
+        loop {
+            let operator: &'static str = match self.peek() {
+                Some(Token::Symbol(symbol)) if *symbol == "*" || *symbol == "/" || *symbol == "%" => symbol,
+                _ => return Ok(expression),
+            };
+            self.position += 1;
+            expression = Expression::Binary(Box::new(expression), operator, Box::new(self.factor()?));
+        }
+    }
+
+    /**
+     * @brief Function to parse a number, a variable, a negation, a parenthesized expression or a random number
+     * @return Result<Expression, String>
+     */
+    fn factor(&mut self) -> Result<Expression, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expression::Number(number)),
+            Some(Token::Variable(name)) => Ok(Expression::Variable(name)),
+            Some(Token::Symbol("-")) => Ok(Expression::Negate(Box::new(self.factor()?))),
+            Some(Token::Symbol("(")) => {
+                let expression: Expression = self.expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            Some(Token::Word(word)) if word == "random" => {
+                self.expect("(")?;
+                let min: Expression = self.expression()?;
+                self.expect(",")?;
+                let max: Expression = self.expression()?;
+                self.expect(")")?;
+                Ok(Expression::Random(Box::new(min), Box::new(max)))
+            }
+            token => Err(format!("Expected a number, found {}", describe_token(token.as_ref()))),
+        }
+    }
+
+    /**
+     * @brief Function to parse two expressions separated by a comma
+     * @return Result<(Expression, Expression), String>
+     */
+    fn pair(&mut self) -> Result<(Expression, Expression), String> {
+        let first: Expression = self.expression()?;
+        self.expect(",")?;
+        let second: Expression = self.expression()?;
+        Ok((first, second))
+    }
+
+    /**
+     * @brief Function to parse a string
+     * @return Result<String, String>
+     */
+    fn text(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Text(text)) => Ok(text),
+            token => Err(format!("Expected a string, found {}", describe_token(token.as_ref()))),
+        }
+    }
+
+    /**
+     * @brief Function to parse a condition
+     * @return Result<Condition, String>
+     */
+    fn condition(&mut self) -> Result<Condition, String> {
+        match self.peek() {
+            Some(Token::Word(word)) if word == "not" => {
+                self.position += 1;
+                Ok(Condition::Not(Box::new(self.condition()?)))
+            }
+            Some(Token::Word(word)) if word == "pixel" => {
+                self.position += 1;
+                let (x, y) = self.pair()?;
+                let equal: bool = if self.accept("==") {
+                    true
+                } else if self.accept("!=") {
+                    false
+                } else {
+                    return Err(format!("Expected '==' or '!=', found {}", describe_token(self.peek())));
+                };
+                let color: u32 = match self.next() {
+                    Some(Token::Color(color)) => color,
+                    token => return Err(format!("Expected a color, found {}", describe_token(token.as_ref()))),
+                };
+
+                let condition: Condition = Condition::Pixel(x, y, color);
+                if equal {
+                    Ok(condition)
+                } else {
+                    Ok(Condition::Not(Box::new(condition)))
+                }
+            }
+            Some(Token::Word(word)) if word == "window" => {
+                self.position += 1;
+                match self.next() {
+                    Some(Token::Word(property)) if property == "title" => {
+                        let pattern: String = self.text()?;
+                        Regex::new(&pattern)
+                            .map(Condition::WindowTitle)
+                            .map_err(|error| format!("Invalid title pattern: {}", error))
+                    }
+                    Some(Token::Word(property)) if property == "class" => Ok(Condition::WindowClass(self.text()?)),
+                    Some(Token::Word(property)) if property == "process" => {
+                        Ok(Condition::WindowProcess(self.text()?))
+                    }
+                    token => Err(format!(
+                        "Expected 'title', 'class' or 'process', found {}",
+                        describe_token(token.as_ref())
+                    )),
+                }
+            }
+            _ => {
+                let left: Expression = self.expression()?;
+                let operator: &'static str = match self.next() {
+                    Some(Token::Symbol(symbol)) if COMPARISONS.contains(&symbol) => symbol,
+                    token => {
+                        return Err(format!(
+                            "Expected a comparison operator, found {}",
+                            describe_token(token.as_ref())
+                        ))
+                    }
+                };
+                let right: Expression = self.expression()?;
+                Ok(Condition::Compare(left, operator, right))
+            }
+        }
+    }
+}
+
+// Implementation of Expression
+impl Expression {
+    /**
+     * @brief Function to check that all variables of the expression were assigned on the previous lines
+     * @param defined - the assigned variables
+     * @return Result<(), String> - error message naming the first undefined variable
+     */
+    fn check_variables(&self, defined: &HashSet<String>) -> Result<(), String> {
+        match self {
+            Expression::Number(_) => Ok(()),
+            Expression::Variable(name) => {
+                if defined.contains(name) {
+                    Ok(())
+                } else {
+                    Err(format!("Undefined variable: ${}", name))
+                }
+            }
+            Expression::Negate(expression) => expression.check_variables(defined),
+            Expression::Binary(left, _, right) | Expression::Random(left, right) => {
+                left.check_variables(defined)?;
+                right.check_variables(defined)
+            }
+        }
+    }
+}
+
+// Implementation of Condition
+impl Condition {
+    /**
+     * @brief Function to check that all variables of the condition were assigned on the previous lines
+     * @param defined - the assigned variables
+     * @return Result<(), String> - error message naming the first undefined variable
+     */
+    fn check_variables(&self, defined: &HashSet<String>) -> Result<(), String> {
+        match self {
+            Condition::Not(condition) => condition.check_variables(defined),
+            Condition::Compare(left, _, right) | Condition::Pixel(left, right, _) => {
+                left.check_variables(defined)?;
+                right.check_variables(defined)
+            }
+            Condition::WindowTitle(_) | Condition::WindowClass(_) | Condition::WindowProcess(_) => Ok(()),
+        }
+    }
+}
+
+// Implementation of Compiler
+impl Compiler {
+    fn new() -> Compiler {
+        Compiler {
+            instructions: Vec::new(),
+            blocks: Vec::new(),
+            defined: HashSet::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /**
+     * @brief Function to add an instruction compiled from a line
+     * @param line - number of the line
+     * @param instruction - the instruction
+     * @return usize - index of the instruction
+     */
+    fn emit(&mut self, line: usize, instruction: Instruction) -> usize {
+        self.instructions.push((line, instruction));
+        self.instructions.len() - 1
+    }
+
+    /**
+     * @brief Function to set the target of a jump to the next instruction
+     * @param index - index of the jump
+     */
+    fn patch_jump(&mut self, index: usize) {
+        let target: usize = self.instructions.len();
+        match &mut self.instructions[index].1 {
+            Instruction::Jump(jump_target) | Instruction::JumpUnless(_, jump_target) => *jump_target = target,
+            _ => {}
+        }
+    }
+
+    /**
+     * @brief Function to compile a simulation step without variable arguments
+     * @param line - number of the line
+     * @param step - the step
+     * @return Result<(), String> - error message if the step is invalid
+     */
+    fn step(&mut self, line: usize, step: SimulationStep) -> Result<(), String> {
+        inputsim::validate_step(&step)?;
+        self.emit(line, Instruction::Step(step));
+        Ok(())
+    }
+
+    /**
+     * @brief Function to open a block ending at the matching '}'
+     * @param line - number of the line
+     * @param block - the block
+     */
+    fn open_block(&mut self, line: usize, block: Block) {
+        self.blocks.push((line, block));
+    }
+
+    /**
+     * @brief Function to close the innermost block
+     * @param line - number of the line
+     * @return Result<(), String> - error message if no block is open
+     */
+    fn close_block(&mut self, line: usize) -> Result<(), String> {
+        match self.blocks.pop() {
+            Some((_, Block::Repeat(check, counter))) => {
+                // Decrement the counter and jump back to its check
+                let decrement: Expression = Expression::Binary(
+                    Box::new(Expression::Variable(counter.clone())),
+                    "-",
+                    Box::new(Expression::Number(1)),
+                );
+                self.emit(line, Instruction::Set(counter, decrement));
+                self.emit(line, Instruction::Jump(check));
+                self.patch_jump(check);
+                Ok(())
+            }
+            Some((_, Block::If(jump))) | Some((_, Block::Else(jump))) => {
+                self.patch_jump(jump);
+                Ok(())
+            }
+            None => Err(String::from("Unexpected '}', no block is open")),
+        }
+    }
+
+    /**
+     * @brief Function to compile a line
+     * @param line - number of the line
+     * @param text - content of the line
+     * @return Result<(), String> - error message if the line is invalid
+     */
+    fn compile_line(&mut self, line: usize, text: &str) -> Result<(), String> {
+        let text: &str = text.trim();
+
+        // Empty lines and comments are skipped
+        if text.is_empty() || text.starts_with('#') {
+            return Ok(());
+        }
+
+        // Closing a block, optionally followed by an else block
+        if let Some(rest) = text.strip_prefix('}') {
+            let rest: Vec<&str> = rest.split_whitespace().collect();
+            if rest.is_empty() {
+                return self.close_block(line);
+            }
+            if rest != ["else", "{"] {
+                return Err(format!("Unexpected {} after '}}'", rest.join(" ")));
+            }
+
+            return match self.blocks.pop() {
+                Some((if_line, Block::If(jump))) => {
+                    let else_jump: usize = self.emit(line, Instruction::Jump(0));
+                    self.patch_jump(jump);
+                    self.open_block(if_line, Block::Else(else_jump));
+                    Ok(())
+                }
+                Some(block) => {
+                    self.blocks.push(block);
+                    Err(String::from("Unexpected 'else', the closed block is not an if block"))
+                }
+                None => Err(String::from("Unexpected '}', no block is open")),
+            };
+        }
+
+        let (command, rest) = match text.find(char::is_whitespace) {
+            Some(index) => (&text[..index], text[index..].trim()),
+            None => (text, ""),
+        };
+
+        match command {
+            "repeat" | "if" => {
+                // The block is opened even if the line is invalid, so the following lines are matched correctly
+                let jump: usize = self.emit(line, Instruction::Jump(0));
+                let counter: String = format!("repeat:{}", line);
+                if command == "repeat" {
+                    self.open_block(line, Block::Repeat(jump, counter.clone()));
+                } else {
+                    self.open_block(line, Block::If(jump));
+                }
+
+                let rest: &str = rest
+                    .strip_suffix('{')
+                    .ok_or(String::from("Expected '{' at the end of the line"))?;
+                let mut tokens: TokenStream = TokenStream::new(tokenize(rest)?);
+
+                if command == "repeat" {
+                    // The counter is set before the check, which is repeated after each iteration
+                    let count: Expression = tokens.expression()?;
+                    tokens.expect_end()?;
+                    count.check_variables(&self.defined)?;
+
+                    let check: Condition = Condition::Compare(
+                        Expression::Variable(counter.clone()),
+                        ">",
+                        Expression::Number(0),
+                    );
+                    self.instructions[jump].1 = Instruction::Set(counter, count);
+                    let check_jump: usize = self.emit(line, Instruction::JumpUnless(check, 0));
+                    if let Some((_, Block::Repeat(index, _))) = self.blocks.last_mut() {
+                        *index = check_jump;
+                    }
+                } else {
+                    let condition: Condition = tokens.condition()?;
+                    tokens.expect_end()?;
+                    condition.check_variables(&self.defined)?;
+                    self.instructions[jump].1 = Instruction::JumpUnless(condition, 0);
+                }
+                Ok(())
+            }
+            "set" => {
+                let mut tokens: TokenStream = TokenStream::new(tokenize(rest)?);
+                let name: String = match tokens.next() {
+                    Some(Token::Variable(name)) => name,
+                    token => return Err(format!("Expected a variable, found {}", describe_token(token.as_ref()))),
+                };
+                tokens.expect("=")?;
+                let value: Expression = tokens.expression()?;
+                tokens.expect_end()?;
+                value.check_variables(&self.defined)?;
+
+                self.defined.insert(name.clone());
+                self.emit(line, Instruction::Set(name, value));
+                Ok(())
+            }
+            "press" => self.step(line, SimulationStep::KeyPress { key: rest.to_string() }),
+            "down" => self.step(line, SimulationStep::KeyDown { key: rest.to_string() }),
+            "up" => self.step(line, SimulationStep::KeyUp { key: rest.to_string() }),
+            "click" => self.step(line, SimulationStep::MouseClick { button: rest.to_string() }),
+            "mousedown" => self.step(line, SimulationStep::MouseDown { button: rest.to_string() }),
+            "mouseup" => self.step(line, SimulationStep::MouseUp { button: rest.to_string() }),
+            "waitkey" => {
+                let key: rdev::Key = string_to_key(rest).map_err(|_| format!("Unknown key: {}", rest))?;
+                self.emit(line, Instruction::WaitForKey(key));
+                Ok(())
+            }
+            "type" => {
+                let mut tokens: TokenStream = TokenStream::new(tokenize(rest)?);
+                let text: String = tokens.text()?;
+                tokens.expect_end()?;
+                self.step(line, SimulationStep::TypeText { text: text })
+            }
+            "wait" | "move" | "scroll" => {
+                let mut tokens: TokenStream = TokenStream::new(tokenize(rest)?);
+                let instruction: Instruction = match command {
+                    "wait" => Instruction::Delay(tokens.expression()?),
+                    "move" => {
+                        let (x, y) = tokens.pair()?;
+                        Instruction::MouseMove(x, y)
+                    }
+                    _ => {
+                        let (delta_x, delta_y) = tokens.pair()?;
+                        Instruction::MouseScroll(delta_x, delta_y)
+                    }
+                };
+                tokens.expect_end()?;
+
+                match &instruction {
+                    Instruction::Delay(ms) => ms.check_variables(&self.defined)?,
+                    Instruction::MouseMove(first, second) | Instruction::MouseScroll(first, second) => {
+                        first.check_variables(&self.defined)?;
+                        second.check_variables(&self.defined)?;
+                    }
+                    _ => {}
+                }
+
+                self.emit(line, instruction);
+                Ok(())
+            }
+            _ => Err(format!("Unknown command: {}", command)),
+        }
+    }
+}
+
+// Implementation of Script
+impl Script {
+    /**
+     * @brief Function to compile the source of a script
+     * @param source - the source
+     * @return Result<Script, Vec<ScriptError>> - errors of all invalid lines
+     * @note Lines are compiled one by one, so an invalid line doesn't hide errors of the following lines
+     */
+    pub fn compile(source: &str) -> Result<Script, Vec<ScriptError>> {
+        let mut compiler: Compiler = Compiler::new();
+
+        for (index, text) in source.lines().enumerate() {
+            if let Err(message) = compiler.compile_line(index + 1, text) {
+                compiler.errors.push(ScriptError {
+                    line: index + 1,
+                    message: message,
+                });
+            }
+        }
+
+        for (line, _) in &compiler.blocks {
+            compiler.errors.push(ScriptError {
+                line: *line,
+                message: String::from("Block is not closed with '}'"),
+            });
+        }
+
+        if compiler.errors.is_empty() {
+            Ok(Script {
+                instructions: compiler.instructions,
+            })
+        } else {
+            compiler.errors.sort_by_key(|error| error.line);
+            Err(compiler.errors)
+        }
+    }
+
+    /**
+     * @brief Function to run the script on the current thread
+     * @param module - module that started the script, the keys and buttons it holds are updated by the script
+     * @param delay_ms - delay in milliseconds after each simulated event
+     * @param cancelled - cancellation flag
+     * @return Result<bool, ScriptError> - false if cancelled, the error and its line if the script was stopped by an error
+     * @note Some errors are only detected when running, e.g. a negative delay or an unset variable
+     */
+    pub fn run(&self, module: &str, delay_ms: u64, cancelled: &AtomicBool) -> Result<bool, ScriptError> {
+        let seed: u64 = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0);
+
+        let mut run: ScriptRun = ScriptRun {
+            module: module,
+            delay_ms: delay_ms,
+            cancelled: cancelled,
+            variables: HashMap::new(),
+            random_state: seed | 1,
+        };
+
+        let mut index: usize = 0;
+        while let Some((line, instruction)) = self.instructions.get(index) {
+            if cancelled.load(Ordering::SeqCst) {
+                return Ok(false);
+            }
+
+            match run.execute(instruction) {
+                Ok(Some(target)) => index = target,
+                Ok(None) => index += 1,
+                Err(message) => {
+                    return Err(ScriptError {
+                        line: *line,
+                        message: message,
+                    })
+                }
+            }
+        }
+
+        Ok(!cancelled.load(Ordering::SeqCst))
+    }
+}
+
+// Implementation of ScriptRun
+impl ScriptRun<'_> {
+    /**
+     * @brief Function to generate a random number
+     * @param min - minimum
+     * @param max - maximum, inclusive
+     * @return Result<i64, String> - error message if the range is empty
+     * @note Uses xorshift, the numbers don't have to be unpredictable
+     */
+    fn random(&mut self, min: i64, max: i64) -> Result<i64, String> {
+        if min > max {
+            return Err(format!("Empty random range: {} to {}", min, max));
+        }
+
+        self.random_state ^= self.random_state << 13;
+        self.random_state ^= self.random_state >> 7;
+        self.random_state ^= self.random_state << 17;
+
+        let range: u128 = (max as i128 - min as i128) as u128 + 1;
+        Ok((min as i128 + (self.random_state as u128 % range) as i128) as i64)
+    }
+
+    /**
+     * @brief Function to evaluate an expression
+     * @param expression - the expression
+     * @return Result<i64, String> - error message if the expression can't be evaluated
+     */
+    fn evaluate(&mut self, expression: &Expression) -> Result<i64, String> {
+        let overflow = || String::from("Arithmetic overflow");
+
+        match expression {
+            Expression::Number(number) => Ok(*number),
+            Expression::Variable(name) => self
+                .variables
+                .get(name)
+                .copied()
+                .ok_or(format!("Variable ${} is not set", name)),
+            Expression::Negate(expression) => self.evaluate(expression)?.checked_neg().ok_or_else(overflow),
+            Expression::Binary(left, operator, right) => {
+                let left: i64 = self.evaluate(left)?;
+                let right: i64 = self.evaluate(right)?;
+                if (*operator == "/" || *operator == "%") && right == 0 {
+                    return Err(String::from("Division by zero"));
+                }
+
+                match *operator {
+                    "+" => left.checked_add(right),
+                    "-" => left.checked_sub(right),
+                    "*" => left.checked_mul(right),
+                    "/" => left.checked_div(right),
+                    _ => left.checked_rem(right),
+                }
+                .ok_or_else(overflow)
+            }
+            Expression::Random(min, max) => {
+                let min: i64 = self.evaluate(min)?;
+                let max: i64 = self.evaluate(max)?;
+                self.random(min, max)
+            }
+        }
+    }
+
+    /**
+     * @brief Function to evaluate a condition
+     * @param condition - the condition
+     * @return Result<bool, String> - error message if the condition can't be evaluated
+     * @note Window conditions aren't met if the focused window can't be determined
+     */
+    fn check(&mut self, condition: &Condition) -> Result<bool, String> {
+        match condition {
+            Condition::Not(condition) => Ok(!self.check(condition)?),
+            Condition::Compare(left, operator, right) => {
+                let left: i64 = self.evaluate(left)?;
+                let right: i64 = self.evaluate(right)?;
+                Ok(match *operator {
+                    "==" => left == right,
+                    "!=" => left != right,
+                    "<=" => left <= right,
+                    ">=" => left >= right,
+                    "<" => left < right,
+                    _ => left > right,
+                })
+            }
+            Condition::Pixel(x, y, color) => {
+                let x: i64 = self.evaluate(x)?;
+                let y: i64 = self.evaluate(y)?;
+                match query_pixel_color(x, y) {
+                    Some(pixel_color) => Ok(pixel_color == *color),
+                    None => Err(format!("Couldn't read the pixel at {}, {}", x, y)),
+                }
+            }
+            Condition::WindowTitle(title_regex) => {
+                Ok(get_active_window().map_or(false, |window: ActiveWindow| title_regex.is_match(&window.title)))
+            }
+            Condition::WindowClass(wm_class) => Ok(get_active_window().map_or(false, |window: ActiveWindow| {
+                window.wm_class.iter().any(|class| class.eq_ignore_ascii_case(wm_class))
+            })),
+            Condition::WindowProcess(process_name) => Ok(get_active_window().map_or(false, |window: ActiveWindow| {
+                window.process_name.iter().any(|name| name.eq_ignore_ascii_case(process_name))
+            })),
+        }
+    }
+
+    /**
+     * @brief Function to wait until a key is pressed, a key held when the wait starts has to be released first
+     * @param key - the key
+     * @note The keyboard listener wakes up the script on the press, so short presses aren't missed.
+     * Auto-repeats of a held key are not presses
+     */
+    fn wait_for_key(&self, key: rdev::Key) {
+        let (sender, receiver) = mpsc::channel::<()>();
+        get_key_waiters().lock().unwrap().push((key, sender));
+
+        // The waiter is forgotten on the next press of the key if the wait is cancelled
+        while !self.cancelled.load(Ordering::SeqCst) {
+            match receiver.recv_timeout(std::time::Duration::from_millis(WAIT_FOR_KEY_INTERVAL_MS)) {
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                _ => return,
+            }
+        }
+    }
+
+    /**
+     * @brief Function to simulate a step
+     * @param step - the step
+     * @return Result<(), String> - error message if the step is invalid
+     */
+    fn simulate(&self, step: SimulationStep) -> Result<(), String> {
+        inputsim::simulate_step(&step, self.module, self.delay_ms, self.cancelled).map(|_| ())
+    }
+
+    /**
+     * @brief Function to execute an instruction
+     * @param instruction - the instruction
+     * @return Result<Option<usize>, String> - index of the next instruction if the instruction jumps,
+     * error message if the instruction can't be executed
+     */
+    fn execute(&mut self, instruction: &Instruction) -> Result<Option<usize>, String> {
+        match instruction {
+            Instruction::Step(step) => self.simulate(step.clone())?,
+            Instruction::MouseMove(x, y) => {
+                let x: i64 = self.evaluate(x)?;
+                let y: i64 = self.evaluate(y)?;
+                self.simulate(SimulationStep::MouseMove {
+                    x: x as f64,
+                    y: y as f64,
+                })?;
+            }
+            Instruction::MouseScroll(delta_x, delta_y) => {
+                let delta_x: i64 = self.evaluate(delta_x)?;
+                let delta_y: i64 = self.evaluate(delta_y)?;
+                self.simulate(SimulationStep::MouseScroll {
+                    delta_x: delta_x,
+                    delta_y: delta_y,
+                })?;
+            }
+            Instruction::Delay(ms) => {
+                let ms: i64 = self.evaluate(ms)?;
+                if ms < 0 {
+                    return Err(format!("Negative delay: {} ms", ms));
+                }
+                self.simulate(SimulationStep::Delay { ms: ms as u64 })?;
+            }
+            Instruction::WaitForKey(key) => self.wait_for_key(*key),
+            Instruction::Set(name, value) => {
+                let value: i64 = self.evaluate(value)?;
+                self.variables.insert(name.clone(), value);
+            }
+            Instruction::Jump(target) => return Ok(Some(*target)),
+            Instruction::JumpUnless(condition, target) => {
+                if !self.check(condition)? {
+                    return Ok(Some(*target));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/**
+ * @brief Function to wake up the running scripts waiting for a key
+ * @param key - the pressed key
+ * @note Exposes the operation to the keyboard listener, auto-repeats shouldn't be passed
+ */
+pub fn key_pressed(key: rdev::Key) {
+    get_key_waiters().lock().unwrap().retain(|(waited_key, sender)| {
+        if *waited_key != key {
+            return true;
+        }
+        let _ = sender.send(());
+        false
+    });
+}
+
+/**
+ * @brief Function to check a script for errors without running it
+ * @param source - source of the script
+ * @return Vec<ScriptError> - errors of all invalid lines, empty if the script is valid
+ * @note Exposes the operation to tauri commands
+ */
+pub fn check_script(source: &str) -> Vec<ScriptError> {
+    match Script::compile(source) {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors,
+    }
+}
+
+/**
+ * @brief Function to queue running a script
+ * @param handle - tauri::AppHandle
+ * @param window_label - label of the window starting the script, it is notified about the progress
+ * @param source - source of the script
+ * @param delay_ms - delay in milliseconds after each simulated event
+ * @param priority - priority of the simulation, queued simulations with higher priority are simulated first
+ * @return ScriptResult - ID of the queued simulation, or the errors of the script
+ * @note Nothing is run if any line is invalid.
+ * Exposes the operation to tauri commands
+ */
+pub fn run_script(
+    handle: tauri::AppHandle,
+    window_label: String,
+    source: &str,
+    delay_ms: u64,
+    priority: i32,
+) -> ScriptResult {
+    let script: Script = match Script::compile(source) {
+        Ok(script) => script,
+        Err(errors) => {
+            return ScriptResult {
+                successful: false,
+                message: format!("{} invalid line(s)", errors.len()),
+                errors: errors,
+                job_id: None,
+            }
+        }
+    };
+
+    let result: SimulationResult = inputsim::start_script(handle, window_label, script, delay_ms, priority);
+    ScriptResult {
+        successful: result.error_message().is_none(),
+        message: result.error_message().unwrap_or("").to_string(),
+        errors: Vec::new(),
+        job_id: result.job_id(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * @brief Function to get the lines of the errors of a script
+     * @param source - source of the script
+     * @return Vec<usize> - numbers of the invalid lines
+     */
+    fn error_lines(source: &str) -> Vec<usize> {
+        check_script(source).iter().map(|error| error.line).collect()
+    }
+
+    /**
+     * @brief Function to parse an expression
+     * @param source - the expression
+     * @return Expression
+     */
+    fn parse(source: &str) -> Expression {
+        let mut tokens: TokenStream = TokenStream::new(tokenize(source).unwrap());
+        let expression: Expression = tokens.expression().unwrap();
+        tokens.expect_end().unwrap();
+        expression
+    }
+
+    /**
+     * @brief Function to create the state of a script run without variables
+     * @param cancelled - cancellation flag
+     * @return ScriptRun
+     */
+    fn new_run(cancelled: &AtomicBool) -> ScriptRun<'_> {
+        ScriptRun {
+            module: "test",
+            delay_ms: 0,
+            cancelled: cancelled,
+            variables: HashMap::new(),
+            random_state: 1,
+        }
+    }
+
+    /**
+     * @brief Function to evaluate an expression without variables
+     * @param source - the expression
+     * @return Result<i64, String> - error message if the expression can't be evaluated
+     */
+    fn evaluate(source: &str) -> Result<i64, String> {
+        let cancelled: AtomicBool = AtomicBool::new(false);
+        new_run(&cancelled).evaluate(&parse(source))
+    }
+
+    #[test]
+    fn compile_valid_script() {
+        let source: &str = "# comment\n\
+            set $count = 2\n\
+            repeat $count + 1 {\n\
+                press A\n\
+                wait random(1, 3)\n\
+                if window title \"term.*\" {\n\
+                    type \"hi\\n\"\n\
+                } else {\n\
+                    move 10, -20\n\
+                }\n\
+                if not $count >= 10 {\n\
+                    set $count = $count * 2\n\
+                }\n\
+            }\n\
+            \n\
+            down LShft\n\
+            up LShft\n\
+            scroll 0, -3\n\
+            waitkey F8\n\
+            click MouseLeft\n";
+
+        assert!(Script::compile(source).is_ok());
+        assert!(check_script(source).is_empty());
+    }
+
+    #[test]
+    fn compile_unbalanced_blocks() {
+        assert_eq!(error_lines("press A\nrepeat 2 {\npress A"), vec![2]);
+        assert_eq!(error_lines("press A\n}"), vec![2]);
+        assert_eq!(error_lines("if 1 == 1 {\n}\n} else {\n}"), vec![3, 4]);
+        assert_eq!(error_lines("repeat 1 {\n} else {\n}"), vec![2]);
+        assert_eq!(error_lines("repeat 1 {\nif 1 < 2 {\n}"), vec![1]);
+    }
+
+    #[test]
+    fn compile_unknown_keys() {
+        assert_eq!(error_lines("press A\npress Foo\nwaitkey Bar\ndown F8\nclick MouseFoo"), vec![2, 3, 5]);
+
+        let errors: Vec<ScriptError> = check_script("press A\npress Foo");
+        assert_eq!(errors[0].message, "Unknown key: Foo");
+    }
+
+    #[test]
+    fn compile_undefined_variables() {
+        assert_eq!(error_lines("wait $delay\nset $delay = 1\nwait $delay"), vec![1]);
+        assert_eq!(error_lines("set $a = 1\nmove $a, $b\nset $c = $c + 1\nif $d > $a {\n}"), vec![2, 3, 4]);
+
+        let errors: Vec<ScriptError> = check_script("set $a = 1\nscroll $a, $missing");
+        assert_eq!(errors[0].line, 2);
+        assert_eq!(errors[0].message, "Undefined variable: $missing");
+    }
+
+    #[test]
+    fn compile_reports_all_invalid_lines() {
+        let errors: Vec<ScriptError> = check_script("repeat 3 {\nfoo\ntype \"abc\nwait 1 2\nmove 1");
+        let lines: Vec<usize> = errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![1, 2, 3, 4, 5]);
+        assert_eq!(errors[1].message, "Unknown command: foo");
+    }
+
+    #[test]
+    fn evaluate_arithmetic() {
+        assert_eq!(evaluate("1 + 2 * 3"), Ok(7));
+        assert_eq!(evaluate("(1 + 2) * 3"), Ok(9));
+        assert_eq!(evaluate("7 % 4 - -2 * (3 + 1)"), Ok(11));
+        assert_eq!(evaluate("-7 / 2"), Ok(-3));
+    }
+
+    #[test]
+    fn evaluate_overflow() {
+        let overflow: Result<i64, String> = Err(String::from("Arithmetic overflow"));
+        assert_eq!(evaluate("9223372036854775807 + 1"), overflow);
+        assert_eq!(evaluate("-9223372036854775807 - 2"), overflow);
+        assert_eq!(evaluate("4611686018427387904 * 2"), overflow);
+        assert_eq!(evaluate("(-9223372036854775807 - 1) / -1"), overflow);
+        assert_eq!(evaluate("-(-9223372036854775807 - 1)"), overflow);
+        assert_eq!(evaluate("-9223372036854775807 - 1"), Ok(i64::MIN));
+    }
+
+    #[test]
+    fn evaluate_division_by_zero() {
+        let division_by_zero: Result<i64, String> = Err(String::from("Division by zero"));
+        assert_eq!(evaluate("1 / 0"), division_by_zero);
+        assert_eq!(evaluate("1 % (2 - 2)"), division_by_zero);
+    }
+
+    #[test]
+    fn evaluate_random_range() {
+        let cancelled: AtomicBool = AtomicBool::new(false);
+        let mut run: ScriptRun = new_run(&cancelled);
+
+        let expression: Expression = parse("random(1, 3)");
+        let mut seen: HashSet<i64> = HashSet::new();
+        for _ in 0..1000 {
+            let number: i64 = run.evaluate(&expression).unwrap();
+            assert!((1..=3).contains(&number));
+            seen.insert(number);
+        }
+        assert_eq!(seen.len(), 3);
+
+        assert_eq!(run.evaluate(&parse("random(5, 5)")), Ok(5));
+        assert!(run.evaluate(&parse("random(-9223372036854775807 - 1, 9223372036854775807)")).is_ok());
+        assert_eq!(
+            run.evaluate(&parse("random(3, 1)")),
+            Err(String::from("Empty random range: 3 to 1"))
+        );
+    }
+
+    #[test]
+    fn evaluate_unset_variable() {
+        let cancelled: AtomicBool = AtomicBool::new(false);
+        let mut run: ScriptRun = new_run(&cancelled);
+        assert_eq!(run.evaluate(&parse("$a + 1")), Err(String::from("Variable $a is not set")));
+
+        run.variables.insert(String::from("a"), 41);
+        assert_eq!(run.evaluate(&parse("$a + 1")), Ok(42));
+    }
+
+    #[test]
+    fn run_reports_error_line() {
+        let script: Script = Script::compile("set $zero = 0\nset $a = 1\nwait 10 / $zero").unwrap();
+        let cancelled: AtomicBool = AtomicBool::new(false);
+
+        let error: ScriptError = script.run("test", 0, &cancelled).unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.message, "Division by zero");
+    }
+}
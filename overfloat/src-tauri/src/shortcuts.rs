@@ -0,0 +1,670 @@
+/*****************************************************************************
+ * @FilePath              : src-tauri/src/shortcuts.rs                       *
+ * @Author                : Jakub Šediba <xsedib00@vutbr.cz>                 *
+ * @Year                  : 2024                                             *
+ ****************************************************************************/
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tauri::Manager;
+
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+
+use crate::activewindow::{get_active_window, ActiveWindow};
+use crate::appactions::perform_app_action;
+use crate::keybinds::{is_modifier_key, DOUBLE_TAP_PREFIX, DOUBLE_TAP_SUFFIX};
+use crate::keygrab::request_grab_update;
+use crate::keys::string_to_key;
+use crate::macros::run_bound_macro;
+
+/*
+* Lazily initialized global instance of ShortcutRegistry holding the shortcuts of all module windows
+* This has to be global because it needs to be accessible from tauri commands and the keyboard listener
+*/
+lazy_static! {
+    static ref SHORTCUT_REGISTRY: Mutex<ShortcutRegistry> = Mutex::new(ShortcutRegistry::new());
+}
+
+/*
+* Get the global instance of ShortcutRegistry
+*/
+fn get_shortcut_registry() -> &'static Mutex<ShortcutRegistry> {
+    &SHORTCUT_REGISTRY
+}
+
+// Generic modifiers matching either the left or the right modifier key
+const MODIFIER_ALIASES: [(&str, [&str; 2]); 3] = [
+    ("Ctrl", ["LCtrl", "RCtrl"]),
+    ("Shft", ["LShft", "RShft"]),
+    ("Meta", ["LMeta", "RMeta"]),
+];
+
+// Label of the owner of the shortcuts running saved macros, they are handled by the backend instead of a window
+const MACRO_SHORTCUT_OWNER: &str = "Overfloat://Macros";
+
+// Prefix of the IDs of the shortcuts running saved macros, followed by the name of the macro
+const MACRO_SHORTCUT_PREFIX: &str = "Overfloat://Macro/";
+
+// Label of the owner of the shortcuts performing app-level actions, they are handled by the backend instead of a window
+const APP_ACTION_SHORTCUT_OWNER: &str = "Overfloat://AppActions";
+
+// Prefix of the IDs of the shortcuts performing app-level actions, followed by the index and the name of the action
+const APP_ACTION_SHORTCUT_PREFIX: &str = "Overfloat://AppAction/";
+
+// Struct for a condition on the focused window that has to be met for a shortcut to be triggered
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+pub struct WindowCondition {
+    wm_class: Option<String>,
+    title: Option<String>,
+    process_name: Option<String>,
+}
+
+// Struct for returning results from shortcut operations
+#[derive(Clone, serde::Serialize)]
+pub struct ShortcutResult {
+    successful: bool,
+    message: String,
+}
+
+// Struct for a shortcut bound to a key combination that conflicts with another shortcut
+#[derive(Clone, serde::Serialize)]
+pub struct ConflictingShortcut {
+    id: String,
+    window_label: String,
+    keybind: String,
+}
+
+// Struct for a conflict between two shortcuts
+// A prefix conflict means the key combination of the first shortcut is the first tap of the double-tap of the second one,
+// a resolved conflict means only the exclusive shortcut with the higher priority is triggered
+#[derive(Clone, serde::Serialize)]
+pub struct ShortcutConflict {
+    first: ConflictingShortcut,
+    second: ConflictingShortcut,
+    prefix: bool,
+    resolved: bool,
+}
+
+// Struct for a key combination independent of the order of its keys
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Combination {
+    double_tap: bool,
+    keys: BTreeSet<String>,
+}
+
+/**
+ * @brief Function to strip the double-tap from a keybind
+ * @param keybind - key combination string
+ * @return (bool, &str) - whether the keybind is a double-tap, and the tapped key combination
+ */
+fn strip_double_tap(keybind: &str) -> (bool, &str) {
+    match keybind
+        .strip_prefix(DOUBLE_TAP_PREFIX)
+        .and_then(|keybind| keybind.strip_suffix(DOUBLE_TAP_SUFFIX))
+    {
+        Some(keybind) => (true, keybind),
+        None => (false, keybind),
+    }
+}
+
+/**
+ * @brief Function to get the key combinations matched by a keybind
+ * @param keybind - key combination string, generic modifiers (Ctrl, Shft, Meta) match either side
+ * @return Vec<Combination> - one combination for every side of every generic modifier
+ * @note Characters without a key name are uppercased like the dispatched key combinations
+ */
+fn keybind_combinations(keybind: &str) -> Vec<Combination> {
+    // Strip the double-tap
+    let (double_tap, keybind) = strip_double_tap(keybind);
+
+    // Expand the generic modifiers to the modifier keys of both sides
+    let mut combinations: Vec<BTreeSet<String>> = vec![BTreeSet::new()];
+    for key in keybind.split('+') {
+        // Uppercase characters unless the uppercase form has more characters (e.g. ß is SS)
+        let uppercase: String = key.to_uppercase();
+        let key: &str = if string_to_key(key).is_err()
+            && key.chars().count() == 1
+            && uppercase.chars().count() == 1
+        {
+            &uppercase
+        } else {
+            key
+        };
+
+        let alternatives: Vec<&str> = match MODIFIER_ALIASES.iter().find(|(alias, _)| *alias == key) {
+            Some((_, sides)) => sides.to_vec(),
+            None => vec![key],
+        };
+
+        combinations = combinations
+            .into_iter()
+            .flat_map(|keys| {
+                alternatives.iter().map(move |alternative| {
+                    let mut keys: BTreeSet<String> = keys.clone();
+                    keys.insert(alternative.to_string());
+                    keys
+                })
+            })
+            .collect();
+    }
+
+    combinations
+        .into_iter()
+        .map(|keys| Combination {
+            double_tap: double_tap,
+            keys: keys,
+        })
+        .collect()
+}
+
+// Struct for a shortcut registered by a module window
+struct RegisteredShortcut {
+    window_label: String,
+    keybinds: Vec<String>,
+    combinations: HashSet<Combination>,
+    condition: Option<WindowCondition>,
+    title_regex: Option<regex::Regex>,
+    consume: bool,
+    priority: i32,
+    exclusive: bool,
+}
+
+// Implementation of RegisteredShortcut
+impl RegisteredShortcut {
+    /**
+     * @brief Function to check whether the condition of the shortcut is met by the focused window
+     * @param active_window - the focused window, None if it couldn't be determined
+     * @return bool
+     * @note If the focused window can't be determined (e.g. on Wayland or after an X error),
+     * the condition isn't met, so a shortcut limited to a window isn't triggered in other windows
+     */
+    fn condition_met(&self, active_window: &Option<ActiveWindow>) -> bool {
+        let (condition, active_window) = match (&self.condition, active_window) {
+            (None, _) => return true,
+            (Some(_), None) => return false,
+            (Some(condition), Some(active_window)) => (condition, active_window),
+        };
+
+        // Window class has to match the instance or the class name of the window
+        if let Some(wm_class) = &condition.wm_class {
+            if !active_window
+                .wm_class
+                .iter()
+                .any(|class| class.eq_ignore_ascii_case(wm_class))
+            {
+                return false;
+            }
+        }
+
+        // Title has to match the regular expression
+        if let Some(title_regex) = &self.title_regex {
+            if !title_regex.is_match(&active_window.title) {
+                return false;
+            }
+        }
+
+        // Process name has to match one of the names of the process owning the window
+        if let Some(process_name) = &condition.process_name {
+            if !active_window
+                .process_name
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(process_name))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /**
+     * @brief Function to check whether the shortcut is bound to a key combination
+     * @param combination - the pressed key combination
+     * @return bool
+     */
+    fn bound_to(&self, combination: &Combination) -> bool {
+        self.combinations.contains(combination)
+    }
+}
+
+// Struct for the registry of shortcuts of all module windows
+struct ShortcutRegistry {
+    shortcuts: HashMap<String, RegisteredShortcut>,
+}
+
+// Implementation of ShortcutRegistry
+impl ShortcutRegistry {
+    fn new() -> ShortcutRegistry {
+        ShortcutRegistry {
+            shortcuts: HashMap::new(),
+        }
+    }
+
+    /**
+     * @brief Function to register a shortcut or update an already registered one
+     * @param window_label - label of the window the shortcut belongs to
+     * @param id - ID of the shortcut
+     * @param keybinds - key combinations bound to the shortcut
+     * @param condition - condition on the focused window, None if the shortcut is triggered in any window
+     * @param consume - whether the key combinations are consumed instead of being passed to the focused application
+     * @param priority - priority of the shortcut, shortcuts with higher priority are triggered first
+     * @param exclusive - whether shortcuts with lower priority bound to the same key combination are not triggered
+     * @return Result<(), String> - error message if the condition is invalid
+     */
+    fn register(
+        &mut self,
+        window_label: String,
+        id: String,
+        keybinds: Vec<String>,
+        condition: Option<WindowCondition>,
+        consume: bool,
+        priority: i32,
+        exclusive: bool,
+    ) -> Result<(), String> {
+        // Compile the title regular expression once instead of on every key press
+        let title_regex: Option<regex::Regex> = match condition.as_ref().and_then(|c| c.title.as_ref()) {
+            Some(title) => match regex::Regex::new(title) {
+                Ok(title_regex) => Some(title_regex),
+                Err(error) => return Err(format!("Invalid title regex: {}", error)),
+            },
+            None => None,
+        };
+
+        // Expand the generic modifiers once instead of on every key press
+        let combinations: HashSet<Combination> = keybinds
+            .iter()
+            .flat_map(|keybind| keybind_combinations(keybind))
+            .collect();
+
+        self.shortcuts.insert(
+            id,
+            RegisteredShortcut {
+                window_label: window_label,
+                keybinds: keybinds,
+                combinations: combinations,
+                condition: condition,
+                title_regex: title_regex,
+                consume: consume,
+                priority: priority,
+                exclusive: exclusive,
+            },
+        );
+
+        Ok(())
+    }
+
+    /**
+     * @brief Function to unregister a shortcut
+     * @param id - ID of the shortcut
+     */
+    fn unregister(&mut self, id: &str) {
+        self.shortcuts.remove(id);
+    }
+
+    /**
+     * @brief Function to get the key combinations of consuming shortcuts whose condition is met
+     * @return HashSet<String>
+     */
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn consumed_keybinds(&self) -> HashSet<String> {
+        let consuming: Vec<&RegisteredShortcut> = self
+            .shortcuts
+            .values()
+            .filter(|shortcut| shortcut.consume && !shortcut.keybinds.is_empty())
+            .collect();
+
+        // Only query the focused window when a consuming shortcut has a condition
+        let active_window: Option<ActiveWindow> =
+            if consuming.iter().any(|shortcut| shortcut.condition.is_some()) {
+                get_active_window()
+            } else {
+                None
+            };
+
+        consuming
+            .into_iter()
+            .filter(|shortcut| shortcut.condition_met(&active_window))
+            .flat_map(|shortcut| shortcut.keybinds.iter().cloned())
+            .collect()
+    }
+
+    /**
+     * @brief Function to trigger the shortcuts bound to a key combination
+     * @param handle - tauri::AppHandle
+     * @param key - key combination string
+     * @param payload - payload of the shortcut event
+     */
+    fn trigger<P: serde::Serialize + Clone>(&self, handle: &tauri::AppHandle, key: &str, payload: P) {
+        // Get the shortcuts bound to the key combination, the pressed combination has no generic modifiers
+        let combination: Combination = match keybind_combinations(key).pop() {
+            Some(combination) => combination,
+            None => return,
+        };
+        let mut bound: Vec<(&String, &RegisteredShortcut)> = self
+            .shortcuts
+            .iter()
+            .filter(|(_, shortcut)| shortcut.bound_to(&combination))
+            .collect();
+
+        if bound.is_empty() {
+            return;
+        }
+
+        // Only query the focused window when a shortcut has a condition
+        let active_window: Option<ActiveWindow> =
+            if bound.iter().any(|(_, shortcut)| shortcut.condition.is_some()) {
+                get_active_window()
+            } else {
+                None
+            };
+
+        // Trigger the shortcuts whose condition is met in order of priority,
+        // an exclusive shortcut stops the shortcuts with lower priority from being triggered
+        bound.retain(|(_, shortcut)| shortcut.condition_met(&active_window));
+        bound.sort_by(|(_, a), (_, b)| b.priority.cmp(&a.priority));
+
+        let mut exclusive_priority: Option<i32> = None;
+        for (id, shortcut) in bound {
+            if exclusive_priority.is_some_and(|priority| shortcut.priority < priority) {
+                break;
+            }
+            if shortcut.exclusive {
+                exclusive_priority = Some(shortcut.priority);
+            }
+
+            // Run the saved macro of a macro shortcut, perform the action of an app-level action shortcut,
+            // emit the shortcut event to the window of other shortcuts
+            if shortcut.window_label == MACRO_SHORTCUT_OWNER {
+                if let Some(name) = id.strip_prefix(MACRO_SHORTCUT_PREFIX) {
+                    run_bound_macro(handle, name.to_string());
+                }
+            } else if shortcut.window_label == APP_ACTION_SHORTCUT_OWNER {
+                if let Some(Ok(index)) = id
+                    .strip_prefix(APP_ACTION_SHORTCUT_PREFIX)
+                    .and_then(|rest| rest.split('/').next())
+                    .map(|index| index.parse::<usize>())
+                {
+                    perform_app_action(handle, index);
+                }
+            } else if let Some(window) = handle.get_window(&shortcut.window_label) {
+                let _ = window.emit(&format!("Overfloat://Shortcut/{}", id), payload.clone());
+            }
+        }
+    }
+
+    /**
+     * @brief Function to find the conflicts between the key combinations of the registered shortcuts
+     * @return Vec<ShortcutConflict>
+     * @note Key combinations conflict if they match the same key presses, including generic modifiers
+     * (e.g. Ctrl+A and LCtrl+A) and key combinations that are the first tap of a double-tap
+     */
+    fn conflicts(&self) -> Vec<ShortcutConflict> {
+        // Sort the shortcuts to report the conflicts in a stable order
+        let mut shortcuts: Vec<(&String, &RegisteredShortcut)> = self.shortcuts.iter().collect();
+        shortcuts.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut conflicts: Vec<ShortcutConflict> = Vec::new();
+        for (index, (first_id, first)) in shortcuts.iter().enumerate() {
+            for (second_id, second) in &shortcuts[index + 1..] {
+                for first_keybind in &first.keybinds {
+                    let first_combinations: Vec<Combination> = keybind_combinations(first_keybind);
+                    for second_keybind in &second.keybinds {
+                        let second_combinations: Vec<Combination> = keybind_combinations(second_keybind);
+
+                        // Check if the key combinations overlap
+                        let overlap: bool = first_combinations
+                            .iter()
+                            .any(|combination| second_combinations.contains(combination));
+
+                        // Check if one key combination is the first tap of the double-tap of the other
+                        let is_prefix = |a: &[Combination], b: &[Combination]| {
+                            a.iter().any(|combination| {
+                                !combination.double_tap
+                                    && b.iter().any(|other| other.double_tap && other.keys == combination.keys)
+                            })
+                        };
+                        let (prefix, swapped) = if is_prefix(&first_combinations, &second_combinations) {
+                            (true, false)
+                        } else if is_prefix(&second_combinations, &first_combinations) {
+                            (true, true)
+                        } else {
+                            (false, false)
+                        };
+
+                        if !overlap && !prefix {
+                            continue;
+                        }
+
+                        // An exclusive shortcut with a higher priority resolves the overlap
+                        let resolved: bool = !prefix
+                            && first.priority != second.priority
+                            && if first.priority > second.priority {
+                                first.exclusive
+                            } else {
+                                second.exclusive
+                            };
+
+                        let mut shortcuts = [
+                            ConflictingShortcut {
+                                id: first_id.to_string(),
+                                window_label: first.window_label.clone(),
+                                keybind: first_keybind.clone(),
+                            },
+                            ConflictingShortcut {
+                                id: second_id.to_string(),
+                                window_label: second.window_label.clone(),
+                                keybind: second_keybind.clone(),
+                            },
+                        ];
+                        if swapped {
+                            shortcuts.reverse();
+                        }
+                        let [first, second] = shortcuts;
+
+                        conflicts.push(ShortcutConflict {
+                            first: first,
+                            second: second,
+                            prefix: prefix,
+                            resolved: resolved,
+                        });
+                    }
+                }
+            }
+        }
+
+        conflicts
+    }
+}
+
+/**
+ * @brief Function to register a shortcut or update an already registered one
+ * @param window_label - label of the window the shortcut belongs to
+ * @param id - ID of the shortcut
+ * @param keybinds - key combinations bound to the shortcut
+ * @param condition - condition on the focused window, None if the shortcut is triggered in any window
+ * @param consume - whether the key combinations are consumed instead of being passed to the focused application
+ * @param priority - priority of the shortcut, shortcuts with higher priority are triggered first
+ * @param exclusive - whether shortcuts with lower priority bound to the same key combination are not triggered
+ * @return ShortcutResult
+ * @note Exposes the operation to tauri commands
+ */
+pub fn register_shortcut(
+    window_label: String,
+    id: String,
+    keybinds: Vec<String>,
+    condition: Option<WindowCondition>,
+    consume: bool,
+    priority: i32,
+    exclusive: bool,
+) -> ShortcutResult {
+    let mut shortcut_registry = get_shortcut_registry().lock().unwrap();
+    match shortcut_registry.register(window_label, id, keybinds, condition, consume, priority, exclusive) {
+        Ok(()) => {
+            // Grab the key combinations of the shortcut if it consumes them
+            request_grab_update();
+            ShortcutResult {
+                successful: true,
+                message: String::new(),
+            }
+        }
+        Err(message) => ShortcutResult {
+            successful: false,
+            message: message,
+        },
+    }
+}
+
+/**
+ * @brief Function to unregister a shortcut
+ * @param id - ID of the shortcut
+ * @note Exposes the operation to tauri commands
+ */
+pub fn unregister_shortcut(id: String) {
+    let mut shortcut_registry = get_shortcut_registry().lock().unwrap();
+    shortcut_registry.unregister(&id);
+    request_grab_update();
+}
+
+/**
+ * @brief Function to replace the shortcuts running saved macros
+ * @param bindings - names of the macros and the key combinations bound to them
+ * @note The key combinations are consumed and matched like the key combinations of other shortcuts,
+ * so they take part in conflict detection. Exposes the operation to the macro library
+ */
+pub fn set_macro_shortcuts(bindings: Vec<(String, String)>) {
+    let mut shortcut_registry = get_shortcut_registry().lock().unwrap();
+    shortcut_registry
+        .shortcuts
+        .retain(|_, shortcut| shortcut.window_label != MACRO_SHORTCUT_OWNER);
+
+    for (name, keybind) in bindings {
+        let _ = shortcut_registry.register(
+            MACRO_SHORTCUT_OWNER.to_string(),
+            format!("{}{}", MACRO_SHORTCUT_PREFIX, name),
+            vec![keybind],
+            None,
+            true,
+            0,
+            false,
+        );
+    }
+
+    request_grab_update();
+}
+
+/**
+ * @brief Function to replace the shortcuts performing app-level actions
+ * @param bindings - indices and names of the actions and the key combinations bound to them
+ * @note The key combinations are consumed and matched like the key combinations of other shortcuts,
+ * so they take part in conflict detection. Exposes the operation to the app-level actions
+ */
+pub fn set_app_action_shortcuts(bindings: Vec<(usize, String, String)>) {
+    let mut shortcut_registry = get_shortcut_registry().lock().unwrap();
+    shortcut_registry
+        .shortcuts
+        .retain(|_, shortcut| shortcut.window_label != APP_ACTION_SHORTCUT_OWNER);
+
+    for (index, name, keybind) in bindings {
+        let _ = shortcut_registry.register(
+            APP_ACTION_SHORTCUT_OWNER.to_string(),
+            format!("{}{}/{}", APP_ACTION_SHORTCUT_PREFIX, index, name),
+            vec![keybind],
+            None,
+            true,
+            0,
+            false,
+        );
+    }
+
+    request_grab_update();
+}
+
+/**
+ * @brief Function to check whether a pressed key combination matches a keybind
+ * @param keybind - key combination string, generic modifiers (Ctrl, Shft, Meta) match either side
+ * @param key - the pressed key combination string
+ * @param extra_modifiers - whether additional held modifiers are allowed
+ * @return bool
+ * @note Exposes the operation to the keyboard state, key combinations are matched like the ones of shortcuts
+ */
+pub fn keybind_matches(keybind: &str, key: &str, extra_modifiers: bool) -> bool {
+    let pressed: Combination = match keybind_combinations(key).pop() {
+        Some(pressed) => pressed,
+        None => return false,
+    };
+
+    keybind_combinations(keybind).iter().any(|combination| {
+        if combination.double_tap != pressed.double_tap {
+            return false;
+        }
+        if !extra_modifiers {
+            return *combination == pressed;
+        }
+
+        // The keys missing from the keybind have to be modifiers
+        combination.keys.is_subset(&pressed.keys)
+            && pressed
+                .keys
+                .difference(&combination.keys)
+                .all(|key| string_to_key(key).map_or(false, is_modifier_key))
+    })
+}
+
+/**
+ * @brief Function to validate a key combination string
+ * @param keybind - key combination string, optionally a double-tap
+ * @return Result<(), String> - error message if a modifier or the key is unknown
+ * @note The key can be a key name or a single character when binding by character
+ */
+pub fn validate_keybind(keybind: &str) -> Result<(), String> {
+    let (_, combo) = strip_double_tap(keybind);
+    let mut parts: Vec<&str> = combo.split('+').collect();
+    let key: &str = parts.pop().unwrap_or_default();
+
+    for modifier in parts {
+        let known: bool = MODIFIER_ALIASES.iter().any(|(alias, _)| *alias == modifier)
+            || string_to_key(modifier).map_or(false, is_modifier_key);
+        if !known {
+            return Err(format!("Unknown modifier: {}", modifier));
+        }
+    }
+
+    if string_to_key(key).is_err() && key.chars().count() != 1 {
+        return Err(format!("Unknown key: {}", key));
+    }
+
+    Ok(())
+}
+
+/**
+ * @brief Function to find the conflicts between the key combinations of the registered shortcuts
+ * @return Vec<ShortcutConflict>
+ * @note Exposes the operation to tauri commands
+ */
+pub fn get_shortcut_conflicts() -> Vec<ShortcutConflict> {
+    let shortcut_registry = get_shortcut_registry().lock().unwrap();
+    shortcut_registry.conflicts()
+}
+
+/**
+ * @brief Function to get the key combinations that should currently be consumed
+ * @return HashSet<String>
+ * @note Exposes the operation to the key grabber, grabbing is only supported on Linux
+ */
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn consumed_keybinds() -> HashSet<String> {
+    let shortcut_registry = get_shortcut_registry().lock().unwrap();
+    shortcut_registry.consumed_keybinds()
+}
+
+/**
+ * @brief Function to trigger the shortcuts bound to a key combination
+ * @param handle - tauri::AppHandle
+ * @param key - key combination string
+ * @param payload - payload of the shortcut event
+ * @note Exposes the operation to the keyboard listener
+ */
+pub fn trigger_shortcuts<P: serde::Serialize + Clone>(handle: &tauri::AppHandle, key: &str, payload: P) {
+    let shortcut_registry = get_shortcut_registry().lock().unwrap();
+    shortcut_registry.trigger(handle, key, payload);
+}
@@ -0,0 +1,49 @@
+/*****************************************************************************
+ * @FilePath    : src/api/InputScript.ts                                     *
+ * @Author      : Jakub Šediba <xsedib00@vutbr.cz>                           *
+ * @Year        : 2024                                                       *
+ ****************************************************************************/
+
+import { invoke } from "@tauri-apps/api";
+
+// Type for an error of a script, lines are numbered from 1
+export type ScriptError = {
+    line: number;
+    message: string;
+};
+
+// Type for the result of queueing a script
+export type ScriptResult = {
+    successful: boolean;
+    message: string;
+    errors: ScriptError[];
+    job_id: number | null;
+};
+
+/**
+ * @brief Function for checking an input simulation script for errors without running it
+ * @param source Source of the script
+ * @returns Errors of all invalid lines, empty if the script is valid
+ */
+export async function checkInputScript(source: string): Promise<ScriptError[]> {
+    return invoke<ScriptError[]>("check_input_script", { source: source });
+}
+
+/**
+ * @brief Function for queueing an input simulation script, nothing is run if any line is invalid
+ * @param source Source of the script
+ * @param delayMs Delay in milliseconds after each simulated event
+ * @param priority Priority of the simulation, queued simulations with higher priority are simulated first
+ * @returns Result containing the ID of the simulation, or the errors of the script
+ */
+export async function runInputScript(
+    source: string,
+    delayMs: number = 1,
+    priority: number = 0
+): Promise<ScriptResult> {
+    return invoke<ScriptResult>("run_input_script", {
+        source: source,
+        delayMs: delayMs,
+        priority: priority,
+    });
+}
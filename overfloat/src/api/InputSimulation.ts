@@ -5,41 +5,70 @@
  ****************************************************************************/
 
 import { invoke } from "@tauri-apps/api";
+import { UnlistenFn, listen, Event } from "@tauri-apps/api/event";
 import { Key, ModifierKey } from "./ShortcutManager";
+import { ScriptError } from "./InputScript";
 
-// Type for a single simulation step
-export type SimulationStep = {
-    device_type: number;
-    simulation_type: number;
-    data_str: string;
-    data_num1: number;
-    data_num2: number;
+// Type for a single simulation step, tagged by its type
+export type SimulationStep =
+    | { type: "keyDown"; key: string }
+    | { type: "keyUp"; key: string }
+    | { type: "keyPress"; key: string }
+    | { type: "mouseDown"; button: string }
+    | { type: "mouseUp"; button: string }
+    | { type: "mouseClick"; button: string }
+    | { type: "mouseScroll"; delta_x: number; delta_y: number }
+    | { type: "mouseMove"; x: number; y: number }
+    | { type: "delay"; ms: number }
+    | { type: "typeText"; text: string };
+
+// Type for an error of a single simulation step
+export type SimulationStepError = {
+    step: number;
+    message: string;
 };
 
-// Function for constructing a single simulation step
-function constructSimulationStep(
-    device_type: number,
-    simulation_type: number,
-    data_str: string = "",
-    data_num1: number = 0,
-    data_num2: number = 0
-): SimulationStep {
-    return {
-        device_type: device_type,
-        simulation_type: simulation_type,
-        data_str: data_str,
-        data_num1: data_num1,
-        data_num2: data_num2,
-    };
+// Enum for the policies of starting an input simulation while another simulation of the same module is queued or running
+export enum SimulationPolicy {
+    Queue = "queue",
+    Reject = "reject",
+    Replace = "replace",
 }
 
+// Type for the result of starting an input simulation
+export type SimulationResult = {
+    successful: boolean;
+    message: string;
+    errors: SimulationStepError[];
+    job_id: number | null;
+};
+
+// Type for the notification about the start of a queued input simulation
+export type SimulationStarted = {
+    job_id: number;
+};
+
+// Type for the progress of a running input simulation
+export type SimulationProgress = {
+    job_id: number;
+    completed_steps: number;
+    total_steps: number;
+};
+
+// Type for the notification about the end of an input simulation
+export type SimulationFinished = {
+    job_id: number;
+    cancelled: boolean;
+    error: ScriptError | null;
+};
+
 /**
  * @brief Function for simulating a KeyDown event
  * @param key Key to be pushed down
  * @returns SimulationStep object representing the KeyDown event
  */
 export function simKeyDown(key: Key|ModifierKey): SimulationStep {
-    return constructSimulationStep(1, 0, key);
+    return { type: "keyDown", key: key };
 }
 
 /**
@@ -48,7 +77,7 @@ export function simKeyDown(key: Key|ModifierKey): SimulationStep {
  * @returns SimulationStep object representing the KeyUp event
  */
 export function simKeyUp(key: Key|ModifierKey): SimulationStep {
-    return constructSimulationStep(1, 1, key);
+    return { type: "keyUp", key: key };
 }
 
 /**
@@ -57,7 +86,7 @@ export function simKeyUp(key: Key|ModifierKey): SimulationStep {
  * @returns SimulationStep object representing the KeyPress event
  */
 export function simKeyPress(key: Key|ModifierKey): SimulationStep {
-    return constructSimulationStep(1, 2, key);
+    return { type: "keyPress", key: key };
 }
 
 // Enum for different mouse buttons
@@ -65,6 +94,8 @@ export enum MouseButton {
     MouseLeft = "MouseLeft",
     MouseMiddle = "MouseMiddle",
     MouseRight = "MouseRight",
+    Mouse4 = "Mouse4",
+    Mouse5 = "Mouse5",
 }
 
 // Enum for different scroll directions
@@ -75,32 +106,13 @@ export enum Direction {
     Right = "Right",
 }
 
-/**
- * @brief Function for converting MouseButton enum to a number representation
- * @param button MouseButton enum
- * @returns Number representation of the MouseButton for the SimulationStep
- */
-function mouseButtonToNumber(button: MouseButton): number {
-    switch (button) {
-        case MouseButton.MouseLeft:
-            return 0;
-            break;
-        case MouseButton.MouseMiddle:
-            return 1;
-            break;
-        case MouseButton.MouseRight:
-            return 2;
-            break;
-    }
-}
-
 /**
  * @brief Function for simulating a MouseDown event
  * @param button MouseButton enum
  * @returns SimulationStep object representing the MouseDown event
  */
 export function simMouseDown(button: MouseButton): SimulationStep {
-    return constructSimulationStep(2,0, undefined, mouseButtonToNumber(button));
+    return { type: "mouseDown", button: button };
 }
 
 /**
@@ -109,7 +121,7 @@ export function simMouseDown(button: MouseButton): SimulationStep {
  * @returns SimulationStep object representing the MouseUp event
  */
 export function simMouseUp(button: MouseButton): SimulationStep {
-    return constructSimulationStep(2,1, undefined, mouseButtonToNumber(button));
+    return { type: "mouseUp", button: button };
 }
 
 /**
@@ -118,8 +130,7 @@ export function simMouseUp(button: MouseButton): SimulationStep {
  * @returns SimulationStep object representing the MouseClick event
  */
 export function simMouseClick(button: MouseButton): SimulationStep {
-    return constructSimulationStep(2,2, undefined, mouseButtonToNumber(button));
-
+    return { type: "mouseClick", button: button };
 }
 
 /**
@@ -151,7 +162,7 @@ function directionToDelta(direction: Direction): [number, number] {
  */
 export function simMouseScroll(direction: Direction): SimulationStep {
     const mouseDelta: [number, number] = directionToDelta(direction);
-    return constructSimulationStep(2,3, undefined, mouseDelta[0], mouseDelta[1]);
+    return { type: "mouseScroll", delta_x: mouseDelta[0], delta_y: mouseDelta[1] };
 }
 
 /**
@@ -161,15 +172,109 @@ export function simMouseScroll(direction: Direction): SimulationStep {
  * @returns SimulationStep object representing the MouseMove event
  */
 export function simMouseMove(x: number, y: number): SimulationStep {
-    return constructSimulationStep(2,4, undefined, x, y);
+    return { type: "mouseMove", x: x, y: y };
+}
+
+/**
+ * @brief Function for typing text
+ * @param text Text to be typed, may contain any Unicode characters
+ * @returns SimulationStep object representing the typing of the text
+ */
+export function simTypeText(text: string): SimulationStep {
+    return { type: "typeText", text: text };
 }
 
 /**
- * @brief Function for executing a sequence of input simulation steps
+ * @brief Function for waiting between simulation steps
+ * @param ms Time to wait in milliseconds, at most 60000
+ * @returns SimulationStep object representing the delay
+ */
+export function simDelay(ms: number): SimulationStep {
+    return { type: "delay", ms: ms };
+}
+
+/**
+ * @brief Function for queueing the execution of a sequence of input simulation steps in the background
  * @param steps Array of SimulationStep objects, should be constructed using the provided functions
+ * @param delayMs Delay in milliseconds after each simulated event, defaults to 1
+ * @param priority Priority of the simulation, queued simulations with higher priority are executed first, defaults to 0
+ * @returns ID of the queued simulation, or the reason why it wasn't queued. Nothing is simulated if any step is invalid
  * @example inputSimulation([simKeyDown("A"), simMouseMove(250, 20), simKeyUp("A")]);
  */
-export function inputSimulation(steps: SimulationStep[]) {
-    invoke("input_simulation", { simulationSteps: steps });
+export async function inputSimulation(
+    steps: SimulationStep[],
+    delayMs?: number,
+    priority?: number
+): Promise<SimulationResult> {
+    return invoke<SimulationResult>("input_simulation", {
+        simulationSteps: steps,
+        delayMs: delayMs,
+        priority: priority,
+    });
+}
+
+/**
+ * @brief Function for setting what happens when the module starts an input simulation while another one of it is queued or running
+ * @param policy Queue the simulation (default), reject it, or cancel the other simulations of the module
+ */
+export async function setSimulationPolicy(policy: SimulationPolicy) {
+    return invoke("set_simulation_policy", { policy: policy });
+}
 
+/**
+ * @brief Function for cancelling a queued or running input simulation started by the current window
+ * @param jobId ID of the simulation
+ * @returns True if the simulation was cancelled, false if no such simulation is queued or running
+ * @note Keys and mouse buttons held by the simulation are released
+ */
+export async function cancelInputSimulation(jobId: number): Promise<boolean> {
+    return invoke<boolean>("cancel_input_simulation", { jobId: jobId });
+}
+
+/**
+ * @brief Function for listening to the start of the input simulations queued by the current window
+ * @param callback Callback function to be called when a simulation starts
+ * @returns Function to stop listening
+ */
+export async function listenSimulationStarted(
+    callback: (started: SimulationStarted) => void
+): Promise<UnlistenFn> {
+    return listen(
+        "Overfloat://SimulationStarted",
+        (event: Event<SimulationStarted>) => {
+            callback(event.payload);
+        }
+    );
+}
+
+/**
+ * @brief Function for listening to the progress of the input simulations started by the current window
+ * @param callback Callback function to be called after each simulated step
+ * @returns Function to stop listening
+ */
+export async function listenSimulationProgress(
+    callback: (progress: SimulationProgress) => void
+): Promise<UnlistenFn> {
+    return listen(
+        "Overfloat://SimulationProgress",
+        (event: Event<SimulationProgress>) => {
+            callback(event.payload);
+        }
+    );
+}
+
+/**
+ * @brief Function for listening to the end of the input simulations started by the current window
+ * @param callback Callback function to be called when a simulation is completed or cancelled
+ * @returns Function to stop listening
+ */
+export async function listenSimulationFinished(
+    callback: (finished: SimulationFinished) => void
+): Promise<UnlistenFn> {
+    return listen(
+        "Overfloat://SimulationFinished",
+        (event: Event<SimulationFinished>) => {
+            callback(event.payload);
+        }
+    );
 }
@@ -0,0 +1,48 @@
+/*****************************************************************************
+ * @FilePath    : src/api/KeyboardState.ts                                   *
+ * @Author      : Jakub Šediba <xsedib00@vutbr.cz>                           *
+ * @Year        : 2024                                                       *
+ ****************************************************************************/
+
+import { invoke } from "@tauri-apps/api";
+import { UnlistenFn, listen, Event } from "@tauri-apps/api/event";
+
+// Type for the state of the keyboard
+// Lock key states are null if the platform doesn't support querying them
+export type KeyboardStateSnapshot = {
+    pressed_keys: string[];
+    caps_lock: boolean | null;
+    num_lock: boolean | null;
+};
+
+/**
+ * @brief Function for getting the current state of the keyboard
+ * @returns Names of the pressed keys and states of the lock keys
+ */
+export async function getKeyboardState(): Promise<KeyboardStateSnapshot> {
+    return invoke<KeyboardStateSnapshot>("get_keyboard_state");
+}
+
+/**
+ * @brief Function for listening to changes of the keyboard state
+ * @param callback Callback function to be called with the new state whenever a key is pressed or released
+ * @returns Function to stop listening
+ */
+export async function listenKeyboardState(
+    callback: (state: KeyboardStateSnapshot) => void
+): Promise<UnlistenFn> {
+    const unlisten: UnlistenFn = await listen(
+        "Overfloat://KeyboardState",
+        (event: Event<KeyboardStateSnapshot>) => {
+            callback(event.payload);
+        }
+    );
+
+    // The events are only emitted to subscribed windows
+    await invoke("set_keyboard_state_events", { enabled: true });
+
+    return () => {
+        unlisten();
+        invoke("set_keyboard_state_events", { enabled: false });
+    };
+}
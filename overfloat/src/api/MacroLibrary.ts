@@ -0,0 +1,86 @@
+/*****************************************************************************
+ * @FilePath    : src/api/MacroLibrary.ts                                    *
+ * @Author      : Jakub Šediba <xsedib00@vutbr.cz>                           *
+ * @Year        : 2024                                                       *
+ ****************************************************************************/
+
+import { invoke } from "@tauri-apps/api";
+import { SimulationResult, SimulationStep } from "./InputSimulation";
+
+// Type for the content of a saved macro
+export type SavedMacro = {
+    keybind?: string | null;
+    delay_ms?: number;
+    steps: SimulationStep[];
+};
+
+// Type for a macro in the list of saved macros
+export type MacroInfo = {
+    name: string;
+    keybind: string | null;
+};
+
+// Type for the result of a macro library operation
+export type MacroResult = {
+    successful: boolean;
+    message: string;
+    saved_macro: SavedMacro | null;
+};
+
+/**
+ * @brief Function for getting the list of saved macros
+ * @returns Names and key combinations of the macros, sorted by name
+ */
+export async function listMacros(): Promise<MacroInfo[]> {
+    return invoke<MacroInfo[]>("list_macros");
+}
+
+/**
+ * @brief Function for saving a macro, an existing macro with the same name is overwritten
+ * @param name Name of the macro, can only contain alphanumerical characters, '-' and '_'
+ * @param savedMacro Content of the macro
+ * @returns Result of the operation, unsuccessful if the name or the steps are invalid
+ */
+export async function saveMacro(
+    name: string,
+    savedMacro: SavedMacro
+): Promise<MacroResult> {
+    return invoke<MacroResult>("save_macro", {
+        name: name,
+        savedMacro: savedMacro,
+    });
+}
+
+/**
+ * @brief Function for loading a saved macro
+ * @param name Name of the macro
+ * @returns Result containing the content of the macro
+ */
+export async function loadMacro(name: string): Promise<MacroResult> {
+    return invoke<MacroResult>("load_macro", { name: name });
+}
+
+/**
+ * @brief Function for deleting a saved macro
+ * @param name Name of the macro
+ * @returns Result of the operation
+ */
+export async function deleteMacro(name: string): Promise<MacroResult> {
+    return invoke<MacroResult>("delete_macro", { name: name });
+}
+
+/**
+ * @brief Function for queueing the simulation of a saved macro
+ * @param name Name of the macro
+ * @param priority Priority of the simulation, queued simulations with higher priority are simulated first
+ * @returns Result containing the ID of the simulation, or the reason why it wasn't queued
+ */
+export async function runMacro(
+    name: string,
+    priority: number = 0
+): Promise<SimulationResult> {
+    return invoke<SimulationResult>("run_macro", {
+        name: name,
+        priority: priority,
+    });
+}
@@ -0,0 +1,61 @@
+/*****************************************************************************
+ * @FilePath    : src/api/MacroRecorder.ts                                   *
+ * @Author      : Jakub Šediba <xsedib00@vutbr.cz>                           *
+ * @Year        : 2024                                                       *
+ ****************************************************************************/
+
+import { invoke } from "@tauri-apps/api";
+import { UnlistenFn, listen, Event } from "@tauri-apps/api/event";
+import { SimulationStep } from "./InputSimulation";
+
+// Type for the options of a macro recording
+export type RecordingOptions = {
+    record_mouse_moves?: boolean;
+    quantize_ms?: number;
+    stop_key?: string | null;
+};
+
+// Type for the result of a macro recording operation
+export type RecordingResult = {
+    successful: boolean;
+    message: string;
+    steps: SimulationStep[];
+};
+
+/**
+ * @brief Function for starting to record keyboard and mouse events into a simulation sequence
+ * @param options Options of the recording, mouse moves are stripped and delays kept exact by default
+ * @returns Result of the operation, unsuccessful if the stop key is unknown
+ * @note An ongoing recording is discarded
+ */
+export async function startMacroRecording(
+    options: RecordingOptions = {}
+): Promise<RecordingResult> {
+    return invoke<RecordingResult>("start_macro_recording", {
+        options: options,
+    });
+}
+
+/**
+ * @brief Function for stopping the recording
+ * @returns Result containing the recorded simulation sequence, unsuccessful if not recording
+ */
+export async function stopMacroRecording(): Promise<RecordingResult> {
+    return invoke<RecordingResult>("stop_macro_recording");
+}
+
+/**
+ * @brief Function for listening to recordings stopped by their stop key
+ * @param callback Callback function to be called with the recorded simulation sequence
+ * @returns Function to stop listening
+ */
+export async function listenMacroRecorded(
+    callback: (result: RecordingResult) => void
+): Promise<UnlistenFn> {
+    return listen(
+        "Overfloat://MacroRecorded",
+        (event: Event<RecordingResult>) => {
+            callback(event.payload);
+        }
+    );
+}
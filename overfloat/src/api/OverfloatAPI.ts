@@ -7,7 +7,11 @@
 // Re-export all API functions, for easier importing
 export * from "./ClipboardOperations"
 export * from "./FSOperations"
+export * from "./InputScript"
 export * from "./InputSimulation"
+export * from "./KeyboardState"
+export * from "./MacroLibrary"
+export * from "./MacroRecorder"
 export * from "./ModuleComponents"
 export * from "./ShortcutManager"
 export * from "./WindowOperations"
@@ -5,16 +5,28 @@
  ****************************************************************************/
 
 import { WebviewWindow, appWindow } from "@tauri-apps/api/window";
-import { UnlistenFn, once, listen } from "@tauri-apps/api/event";
+import { UnlistenFn, once, listen, Event } from "@tauri-apps/api/event";
+import { KeypressEventPayload } from "../utils/KeybindEventHandler";
+import { WindowCondition } from "../utils/Shortcut";
+
+// Re-export the type of the information about the key press that triggered a shortcut
+export type { KeypressEventPayload };
+
+// Re-export the type of the condition on the focused window for triggering a shortcut
+export type { WindowCondition };
 
 // Enum for modifier keys
+// Generic modifiers (Meta, Control, Shift) match the modifier key on either side
 export enum ModifierKey {
+    Meta = "Meta",
     LeftMeta = "LMeta",
     RightMeta = "RMeta",
+    Control = "Ctrl",
     LeftControl = "LCtrl",
     RightControl = "RCtrl",
     Alt = "Alt",
     AltGr = "AltGr",
+    Shift = "Shft",
     LeftShift = "LShft",
     RightShift = "RShft",
 }
@@ -47,6 +59,18 @@ export enum Key {
     F10 = "F10",
     F11 = "F11",
     F12 = "F12",
+    F13 = "F13",
+    F14 = "F14",
+    F15 = "F15",
+    F16 = "F16",
+    F17 = "F17",
+    F18 = "F18",
+    F19 = "F19",
+    F20 = "F20",
+    F21 = "F21",
+    F22 = "F22",
+    F23 = "F23",
+    F24 = "F24",
 
     Q = "Q",
     W = "W",
@@ -99,7 +123,7 @@ export enum Key {
 
     Insert = "Insert",
     Home = "Home",
-    Delete = "Delete",
+    Delete = "Del",
     End = "End",
     PageUp = "PgUp",
     PageDown = "PgDown",
@@ -119,15 +143,32 @@ export enum Key {
     Semicolon = ";",
     Quote = '"',
     Backslash = "\\",
+    IntlBackslash = "IntlBackslash",
     Comma = ",",
     Period = ".",
     Slash = "/",
+
+    MediaPlayPause = "MediaPlayPause",
+    MediaStop = "MediaStop",
+    MediaNextTrack = "MediaNextTrack",
+    MediaPrevTrack = "MediaPrevTrack",
+    VolumeMute = "VolumeMute",
+    VolumeDown = "VolumeDown",
+    VolumeUp = "VolumeUp",
+}
+
+// Enum for the ways a key combination can be triggered
+export enum TriggerType {
+    Press = "Press",
+    DoubleTap = "DoubleTap",
 }
 
 // Type for a key combination
+// If the key is a modifier key, the combination is triggered by tapping the modifiers alone
 export type KeyCombination = {
-    key: Key;
+    key: Key | ModifierKey;
     modifiers?: ModifierKey[];
+    trigger?: TriggerType;
 };
 
 /**
@@ -136,6 +177,32 @@ export type KeyCombination = {
  * @returns String representation of the KeyCombination
  */
 function KeyCombinationToString(keyCombination: KeyCombination): string {
+    const combinationString: string = PressCombinationToString(keyCombination);
+
+    if (keyCombination.trigger === TriggerType.DoubleTap) {
+        return "DoubleTap(" + combinationString + ")";
+    }
+
+    return combinationString;
+}
+
+/**
+ * Transforms a KeyCombination object to a string, ignoring its trigger type
+ * @param keyCombination The KeyCombination object to be transformed
+ * @returns String representation of a single press of the KeyCombination
+ */
+function PressCombinationToString(keyCombination: KeyCombination): string {
+    // Modifier-only combinations consist of the tapped modifiers joined in order of priority
+    if (Object.values<string>(ModifierKey).includes(keyCombination.key)) {
+        const modifiers: string[] = [
+            ...(keyCombination.modifiers ?? []),
+            keyCombination.key,
+        ];
+        return Object.values<string>(ModifierKey)
+            .filter((modifier) => modifiers.includes(modifier))
+            .join("+");
+    }
+
     if (keyCombination.modifiers === undefined) {
         return keyCombination.key;
     }
@@ -143,12 +210,18 @@ function KeyCombinationToString(keyCombination: KeyCombination): string {
     let resultString: string = "";
 
     // Add the modifiers in order of priority
+    if (keyCombination.modifiers.includes(ModifierKey.Meta)) {
+        resultString += "Meta+";
+    }
     if (keyCombination.modifiers.includes(ModifierKey.LeftMeta)) {
         resultString += "LMeta+";
     }
     if (keyCombination.modifiers.includes(ModifierKey.RightMeta)) {
         resultString += "RMeta+";
     }
+    if (keyCombination.modifiers.includes(ModifierKey.Control)) {
+        resultString += "Ctrl+";
+    }
     if (keyCombination.modifiers.includes(ModifierKey.LeftControl)) {
         resultString += "LCtrl+";
     }
@@ -161,6 +234,9 @@ function KeyCombinationToString(keyCombination: KeyCombination): string {
     if (keyCombination.modifiers.includes(ModifierKey.AltGr)) {
         resultString += "AltGr+";
     }
+    if (keyCombination.modifiers.includes(ModifierKey.Shift)) {
+        resultString += "Shft+";
+    }
     if (keyCombination.modifiers.includes(ModifierKey.LeftShift)) {
         resultString += "LShft+";
     }
@@ -214,16 +290,24 @@ class _ShortcutManager {
      * @param id ID of the shortcut
      * @param name Name of the shortcut
      * @param description Description of the shortcut
-     * @param callback Callback function to be called when the shortcut is triggered
+     * @param callback Callback function to be called when the shortcut is triggered, receives the key press that triggered it
      * @param defaultKeybinds Default key combinations bound to the shortcut
+     * @param condition Condition on the focused window, the shortcut is only triggered while it is met
+     * @param consume If true, the bound key combinations are not passed to the focused application, if supported by the platform
+     * @param priority Priority of the shortcut, shortcuts bound to the same key combination are triggered in order of priority
+     * @param exclusive If true, shortcuts with lower priority bound to the same key combination are not triggered
      * @returns True if the shortcut was added successfully, false otherwise
      */
     public addShortcut(
         id: string,
         name: string,
         description: string,
-        callback: () => void,
-        defaultKeybinds?: KeyCombination[]
+        callback: (keypress: KeypressEventPayload) => void,
+        defaultKeybinds?: KeyCombination[],
+        condition?: WindowCondition,
+        consume?: boolean,
+        priority?: number,
+        exclusive?: boolean
     ): boolean {
         const shortcut_id: string = _SHORTCUT_PREFIX + id;
 
@@ -244,13 +328,17 @@ class _ShortcutManager {
             name: name,
             description: description,
             defaultKeybinds: keybinds,
+            condition: condition,
+            consume: consume,
+            priority: priority,
+            exclusive: exclusive,
         });
 
         // Listen for the shortcut event
         const unlisten = listen(
             "Overfloat://Shortcut/" + shortcut_id,
-            () => {
-                callback();
+            (event: Event<KeypressEventPayload>) => {
+                callback(event.payload);
             }
         );
         this.listeners.set(shortcut_id, unlisten);
@@ -5,7 +5,7 @@
  ****************************************************************************/
 
 import { useEffect, useState } from "react";
-import { ModuleManager } from "../../utils/ModuleManager";
+import { ModuleManager, ProfileRemaps } from "../../utils/ModuleManager";
 import {
     IconDeviceFloppy,
     IconPlus,
@@ -27,11 +27,37 @@ const ProfileSettings: React.FC = () => {
     const [selectedProfile, setSelectedProfile] = useState<string>(
         ModuleManager.getInstance().getActiveProfile()
     );
+
+    // State for the key remapping of the selected profile
+    const [remaps, setRemaps] = useState<ProfileRemaps>(
+        ModuleManager.getInstance().getRemaps(selectedProfile)
+    );
+    const [remapErrorMessage, setRemapErrorMessage] = useState<string>(
+        ModuleManager.getInstance().getRemapErrorMessage()
+    );
+
+    useEffect(() => {
+        setRemaps(ModuleManager.getInstance().getRemaps(selectedProfile));
+        setRemapErrorMessage(
+            selectedProfile == ModuleManager.getInstance().getActiveProfile()
+                ? ModuleManager.getInstance().getRemapErrorMessage()
+                : ""
+        );
+    }, [selectedProfile]);
+
     useEffect(() => {
         // Function to update the profiles
         const updateProfiles = () => {
             setAllProfiles([...ModuleManager.getInstance().getProfiles()]);
             setSelectedProfile(ModuleManager.getInstance().getActiveProfile());
+            setRemaps(
+                ModuleManager.getInstance().getRemaps(
+                    ModuleManager.getInstance().getActiveProfile()
+                )
+            );
+            setRemapErrorMessage(
+                ModuleManager.getInstance().getRemapErrorMessage()
+            );
         };
 
         // Subscribe to the module manager notifications
@@ -209,6 +235,49 @@ const ProfileSettings: React.FC = () => {
                     </button>
                 </div>
             </div>
+
+            {/* Key remapping of the selected profile, rules are declared in config/remaps.json */}
+            <div className="row">
+                <div className="col form-check form-switch">
+                    <input
+                        className="form-check-input"
+                        type="checkbox"
+                        id="remapsEnabled"
+                        checked={remaps.enabled}
+                        disabled={selectedProfile == ""}
+                        onChange={async (event) => {
+                            setRemapErrorMessage(
+                                await ModuleManager.getInstance().setRemapsEnabled(
+                                    selectedProfile,
+                                    event.target.checked
+                                )
+                            );
+                            setRemaps(
+                                ModuleManager.getInstance().getRemaps(
+                                    selectedProfile
+                                )
+                            );
+                        }}
+                    />
+                    <label className="form-check-label" htmlFor="remapsEnabled">
+                        Key remapping ({remaps.rules.length} rules)
+                    </label>
+                </div>
+            </div>
+            {/* List of the remapping rules */}
+            {remaps.rules.map((rule, index) => (
+                <div className="row small" key={index}>
+                    <div className="col">
+                        {(rule.while_held ? rule.while_held + " held: " : "") +
+                            rule.source +
+                            " → " +
+                            rule.target}
+                    </div>
+                </div>
+            ))}
+            <div className={"row" + (remapErrorMessage != "" ? "" : " d-none")}>
+                <p className="col text-danger small">{remapErrorMessage}</p>
+            </div>
         </div>
     );
 };
@@ -7,12 +7,7 @@
 import { useState } from "react";
 import useStateRef from "react-usestateref";
 import { Modal } from "react-bootstrap";
-import { UnlistenFn, listen } from "@tauri-apps/api/event";
-import { OverfloatEvent } from "../../utils/WindowEventHandler";
-import {
-    KeybindEventHandler,
-    KeypressEventPayload,
-} from "../../utils/KeybindEventHandler";
+import { KeybindEventHandler } from "../../utils/KeybindEventHandler";
 import { Shortcut } from "../../utils/Shortcut";
 import { KeybindManager } from "../../utils/KeybindManager";
 import { IconTrash } from "@tabler/icons-react";
@@ -38,19 +33,21 @@ const KeybindDisplay: React.FC<KeybindDisplayProps> = (
     const id = shortcut.getId();
     const keybind = shortcut.getBoundKeys()[position];
 
-    // Reference to the unlisten promise for the KeyPress event, used when changing a keybind
-    let unlisten: Promise<UnlistenFn> | undefined = undefined;
+    // Reference to whether key combinations are being captured, used when changing a keybind
+    const [, setCapturing, refCapturing] = useStateRef<boolean>(false);
 
     // State for the keybind change modal
     const [modalVisible, setModalVisible] = useState<boolean>(false);
     const [newKeybind, setNewKeybind] = useState<string>(keybind);
+    const [newCharacter, setNewCharacter] = useState<string>("");
 
     const [errorMessage, setErrorMessage, refErrorMessage] =
         useStateRef<string>("");
 
     // Handler for the KeyPress event
-    const handleKeypress = (key: string) => {
+    const handleKeypress = (key: string, character: string) => {
         setNewKeybind(key);
+        setNewCharacter(character);
         if (shortcut.getBoundKeys().includes(key)) {
             setErrorMessage(
                 "This action already has selected key combination bound!"
@@ -64,26 +61,29 @@ const KeybindDisplay: React.FC<KeybindDisplayProps> = (
     const handleShow = async () => {
         // Reset the keybind and error message
         setNewKeybind(keybind);
+        setNewCharacter("");
         setErrorMessage(
             "This action already has selected key combination bound!"
         );
 
-        // Temporarily stop propagating KeyPress events to modules
-        await KeybindEventHandler.getInstance().stopListening();
-        unlisten = listen(
-            "Overfloat://GlobalKeypress",
-            (event: OverfloatEvent<KeypressEventPayload>) =>
-                handleKeypress(event.payload.key)
-        );
+        setCapturing(true);
         setModalVisible(true);
+
+        // Capture key combinations until the modal is closed, shortcuts are not triggered while capturing
+        while (refCapturing.current) {
+            const result =
+                await KeybindEventHandler.getInstance().captureKeybind();
+            if (result.successful && refCapturing.current) {
+                handleKeypress(result.key, result.character);
+            }
+        }
     };
 
     // Handler for closing the keybind change modal
     const handleClose = async () => {
-        // Restart propagating KeyPress events to modules
-        KeybindEventHandler.getInstance().startListening();
-        // Stop listening for KeyPress events
-        await unlisten?.then((f) => f());
+        // Stop capturing key combinations
+        setCapturing(false);
+        await KeybindEventHandler.getInstance().cancelKeybindCapture();
         setModalVisible(false);
     };
 
@@ -123,6 +123,15 @@ const KeybindDisplay: React.FC<KeybindDisplayProps> = (
                                 {newKeybind.replace(/\+/g, " + ")}
                             </div>
                         </div>
+                        {/* Display the character produced by the key in the current keyboard layout */}
+                        <div
+                            className={
+                                "row" + (newCharacter != "" ? "" : " d-none")
+                            }>
+                            <div className="col text-center small">
+                                Character: {newCharacter}
+                            </div>
+                        </div>
                         <hr />
 
                         {/* Display the error message if the key combination is invalid */}
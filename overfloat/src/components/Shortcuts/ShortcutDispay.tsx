@@ -11,9 +11,6 @@ import { KeybindManager } from "../../utils/KeybindManager";
 import { Modal } from "react-bootstrap";
 import useStateRef from "react-usestateref";
 import { KeybindEventHandler } from "../../utils/KeybindEventHandler";
-import { listen, UnlistenFn } from "@tauri-apps/api/event";
-import { OverfloatEvent } from "../../utils/WindowEventHandler";
-import { KeypressEventPayload } from "../../utils/KeybindEventHandler";
 import { IconPlus } from "@tabler/icons-react";
 
 interface ShortcutDisplayProps {
@@ -52,8 +49,8 @@ const ShortcutDisplay: React.FC<ShortcutDisplayProps> = (
         };
     }, []);
 
-    // Reference to the unlisten promise for the KeyPress event, used when adding a new keybind
-    let unlisten: Promise<UnlistenFn> | undefined = undefined;
+    // Reference to whether key combinations are being captured, used when adding a new keybind
+    const [, setCapturing, refCapturing] = useStateRef<boolean>(false);
 
     // State for the new keybind modal
     const [modalVisible, setModalVisible] = useState<boolean>(false);
@@ -100,26 +97,24 @@ const ShortcutDisplay: React.FC<ShortcutDisplayProps> = (
         setNewKeybind("");
         setErrorMessage("No key combination selected!");
 
-        // Temporarily stop propagation of KeyPress events to modules
-        await KeybindEventHandler.getInstance().stopListening();
-
-        // Listen for KeyPress events
-        unlisten = listen(
-            "Overfloat://GlobalKeypress",
-            (event: OverfloatEvent<KeypressEventPayload>) =>
-                handleKeypress(event.payload.key)
-        );
-
+        setCapturing(true);
         setModalVisible(true);
+
+        // Capture key combinations until the modal is closed, shortcuts are not triggered while capturing
+        while (refCapturing.current) {
+            const result =
+                await KeybindEventHandler.getInstance().captureKeybind();
+            if (result.successful && refCapturing.current) {
+                handleKeypress(result.key);
+            }
+        }
     };
 
     // Handler for closing the new keybind modal
     const handleCloseModal = async () => {
-        // Restart propagation of KeyPress events to modules
-        KeybindEventHandler.getInstance().startListening();
-
-        // Stop listening for KeyPress events
-        await unlisten?.then((f) => f());
+        // Stop capturing key combinations
+        setCapturing(false);
+        await KeybindEventHandler.getInstance().cancelKeybindCapture();
         setModalVisible(false);
     };
 
@@ -4,7 +4,20 @@
  * @Year        : 2024                                                       *
  ****************************************************************************/
 
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
 import { OverfloatModule } from "../../utils/OverfloatModule";
+import { Hotstrings, ModuleManager } from "../../utils/ModuleManager";
+import {
+    KeybindEventHandler,
+    ShortcutsPausedEventPayload,
+} from "../../utils/KeybindEventHandler";
+import { OverfloatEvent } from "../../utils/WindowEventHandler";
+import {
+    KeybindManager,
+    ShortcutConflict,
+} from "../../utils/KeybindManager";
+import "./css/Shortcuts.css";
 import ModuleShortcutsDisplay from "./ModuleShortcutsDisplay";
 
 type ShortcutSettingsProps = {
@@ -19,10 +32,289 @@ const ShortcutSettings: React.FC<ShortcutSettingsProps> = (
 ) => {
     const activeModules = props["activeModules"];
 
+    // State for binding shortcuts by the character produced by the keyboard layout
+    const [bindByCharacter, setBindByCharacter] = useState<boolean>(
+        ModuleManager.getInstance().getBindByCharacter()
+    );
+
+    // Handler for toggling binding by character
+    const handleBindByCharacterChange = async (enabled: boolean) => {
+        await ModuleManager.getInstance().setBindByCharacter(enabled);
+        setBindByCharacter(enabled);
+    };
+
+    // State for the time window for detecting double-tap shortcuts
+    const [doubleTapWindow, setDoubleTapWindow] = useState<number>(
+        ModuleManager.getInstance().getDoubleTapWindow()
+    );
+
+    // Handler for changing the double-tap window, invalid values are ignored
+    const handleDoubleTapWindowChange = async (value: string) => {
+        const windowMs: number = parseInt(value);
+        if (isNaN(windowMs) || windowMs < 0) return;
+
+        await ModuleManager.getInstance().setDoubleTapWindow(windowMs);
+        setDoubleTapWindow(windowMs);
+    };
+
+    // State for pausing shortcuts, kept in sync with the backend which can also be paused from the tray menu
+    const [paused, setPaused] = useState<boolean>(false);
+
+    useEffect(() => {
+        KeybindEventHandler.getInstance().getShortcutsPaused().then(setPaused);
+
+        const unlisten = listen(
+            "Overfloat://ShortcutsPaused",
+            (event: OverfloatEvent<ShortcutsPausedEventPayload>) =>
+                setPaused(event.payload.paused)
+        );
+
+        return () => {
+            unlisten.then((f) => f());
+        };
+    }, []);
+
+    // State for the key combination resuming paused shortcuts
+    const [resumeCombo, setResumeCombo] = useState<string>(
+        ModuleManager.getInstance().getResumeCombo()
+    );
+    const [capturingResumeCombo, setCapturingResumeCombo] =
+        useState<boolean>(false);
+    const [resumeComboErrorMessage, setResumeComboErrorMessage] =
+        useState<string>("");
+
+    // Handler for changing the resume key combination, captures the next key combination
+    const handleResumeComboChange = async () => {
+        setCapturingResumeCombo(true);
+        const result = await KeybindEventHandler.getInstance().captureKeybind();
+        if (result.successful) {
+            const message: string =
+                await ModuleManager.getInstance().setResumeCombo(result.key);
+            setResumeComboErrorMessage(message);
+            if (message == "") setResumeCombo(result.key);
+        }
+        setCapturingResumeCombo(false);
+    };
+
+    // State for the key combination stopping all input simulations
+    const [panicCombo, setPanicCombo] = useState<string>(
+        ModuleManager.getInstance().getPanicCombo()
+    );
+    const [capturingPanicCombo, setCapturingPanicCombo] =
+        useState<boolean>(false);
+    const [panicComboErrorMessage, setPanicComboErrorMessage] =
+        useState<string>("");
+
+    // Handler for changing the panic key combination, captures the next key combination
+    const handlePanicComboChange = async () => {
+        setCapturingPanicCombo(true);
+        const result = await KeybindEventHandler.getInstance().captureKeybind();
+        if (result.successful) {
+            const message: string =
+                await ModuleManager.getInstance().setPanicCombo(result.key);
+            setPanicComboErrorMessage(message);
+            if (message == "") setPanicCombo(result.key);
+        }
+        setCapturingPanicCombo(false);
+    };
+
+    // State for the hotstrings, they are declared in config/hotstrings.json
+    const [hotstrings, setHotstrings] = useState<Hotstrings>(
+        ModuleManager.getInstance().getHotstrings()
+    );
+    const [hotstringErrorMessage, setHotstringErrorMessage] =
+        useState<string>("");
+
+    // Handler for enabling or disabling the hotstrings
+    const handleHotstringsEnabledChange = async (enabled: boolean) => {
+        setHotstringErrorMessage(
+            await ModuleManager.getInstance().setHotstringsEnabled(enabled)
+        );
+        setHotstrings(ModuleManager.getInstance().getHotstrings());
+    };
+
+    // State for the conflicts between the key combinations of the shortcuts, updated when keybinds change
+    const [conflicts, setConflicts] = useState<ShortcutConflict[]>([]);
+
+    useEffect(() => {
+        const updateConflicts = () => {
+            KeybindManager.getInstance()
+                .getShortcutConflicts()
+                .then(setConflicts);
+        };
+        updateConflicts();
+
+        KeybindManager.getInstance().subscribe(updateConflicts);
+        return () => {
+            KeybindManager.getInstance().unsubscribe(updateConflicts);
+        };
+    }, []);
+
     return (
         <div className="container-fluid">
             <h2 className="h2 fw-bold fst-italic">Shortcut Settings</h2>
             <hr />
+            {/* Toggle for pausing all shortcuts, e.g. while typing in a game chat */}
+            <div className="form-check form-switch">
+                <input
+                    className="form-check-input"
+                    type="checkbox"
+                    id="shortcutsPaused"
+                    checked={paused}
+                    onChange={(event) =>
+                        KeybindEventHandler.getInstance().setShortcutsPaused(
+                            event.target.checked
+                        )
+                    }
+                />
+                <label className="form-check-label" htmlFor="shortcutsPaused">
+                    Pause shortcuts
+                    {paused && (
+                        <span className="text-danger fw-bold"> (paused)</span>
+                    )}
+                </label>
+            </div>
+            {/* Key combination resuming paused shortcuts */}
+            <div className="row align-items-center">
+                <label className="col-auto">Resume shortcuts with</label>
+                <div className="col-auto">
+                    <button
+                        className="keybindButton text-truncate"
+                        onClick={handleResumeComboChange}
+                        disabled={capturingResumeCombo}
+                        title={resumeCombo}>
+                        {capturingResumeCombo
+                            ? "Press a key combination..."
+                            : resumeCombo}
+                    </button>
+                </div>
+            </div>
+            <div
+                className={
+                    "row" + (resumeComboErrorMessage != "" ? "" : " d-none")
+                }>
+                <p className="col text-danger small">{resumeComboErrorMessage}</p>
+            </div>
+            {/* Key combination stopping all input simulations */}
+            <div className="row align-items-center">
+                <label className="col-auto">Stop all input simulations with</label>
+                <div className="col-auto">
+                    <button
+                        className="keybindButton text-truncate"
+                        onClick={handlePanicComboChange}
+                        disabled={capturingPanicCombo}
+                        title={panicCombo}>
+                        {capturingPanicCombo
+                            ? "Press a key combination..."
+                            : panicCombo}
+                    </button>
+                </div>
+            </div>
+            <div
+                className={
+                    "row" + (panicComboErrorMessage != "" ? "" : " d-none")
+                }>
+                <p className="col text-danger small">{panicComboErrorMessage}</p>
+            </div>
+            {/* Toggle between binding by physical key position and by produced character */}
+            <div className="form-check form-switch">
+                <input
+                    className="form-check-input"
+                    type="checkbox"
+                    id="bindByCharacter"
+                    checked={bindByCharacter}
+                    onChange={(event) =>
+                        handleBindByCharacterChange(event.target.checked)
+                    }
+                />
+                <label className="form-check-label" htmlFor="bindByCharacter">
+                    Bind shortcuts by the character of the keyboard layout
+                    instead of the physical key position
+                </label>
+            </div>
+            {/* Time window for detecting double-tap shortcuts */}
+            <div className="row align-items-center">
+                <label className="col-auto" htmlFor="doubleTapWindow">
+                    Double-tap window (ms)
+                </label>
+                <div className="col-auto">
+                    <input
+                        className="form-control form-control-sm"
+                        type="number"
+                        min={0}
+                        id="doubleTapWindow"
+                        value={doubleTapWindow}
+                        onChange={(event) =>
+                            handleDoubleTapWindowChange(event.target.value)
+                        }
+                    />
+                </div>
+            </div>
+            {/* Text expansion, typed abbreviations are replaced in any application */}
+            <div className="form-check form-switch">
+                <input
+                    className="form-check-input"
+                    type="checkbox"
+                    id="hotstringsEnabled"
+                    checked={hotstrings.enabled}
+                    onChange={(event) =>
+                        handleHotstringsEnabledChange(event.target.checked)
+                    }
+                />
+                <label className="form-check-label" htmlFor="hotstringsEnabled">
+                    Text expansion ({hotstrings.hotstrings.length} hotstrings)
+                </label>
+            </div>
+            {/* List of the hotstrings */}
+            {hotstrings.hotstrings.map((hotstring, index) => (
+                <div className="row small" key={index}>
+                    <div className="col text-truncate">
+                        {hotstring.abbreviation +
+                            " → " +
+                            hotstring.replacement +
+                            (hotstring.paste ? " (paste)" : "")}
+                    </div>
+                </div>
+            ))}
+            <div
+                className={
+                    "row" + (hotstringErrorMessage != "" ? "" : " d-none")
+                }>
+                <p className="col text-danger small">{hotstringErrorMessage}</p>
+            </div>
+            {/* Conflicts between the key combinations of the shortcuts */}
+            {conflicts.length > 0 && (
+                <div>
+                    <hr />
+                    <h5 className="fw-bold">Conflicting shortcuts</h5>
+                    {conflicts.map((conflict, index) => (
+                        <div
+                            className={
+                                "row small" +
+                                (conflict.resolved ? " text-muted" : " text-danger")
+                            }
+                            key={index}>
+                            <div className="col text-truncate">
+                                {conflict.first.id +
+                                    " (" +
+                                    conflict.first.keybind +
+                                    ")" +
+                                    (conflict.prefix
+                                        ? " is the first tap of "
+                                        : " overlaps ") +
+                                    conflict.second.id +
+                                    " (" +
+                                    conflict.second.keybind +
+                                    ")" +
+                                    (conflict.resolved
+                                        ? ", resolved by priority"
+                                        : "")}
+                            </div>
+                        </div>
+                    ))}
+                </div>
+            )}
+            <hr />
             {/* Display the shortcuts for each active module */}
             {Array.from(activeModules).map(([moduleName, module]) => (
                 <ModuleShortcutsDisplay key={moduleName} module={module} />
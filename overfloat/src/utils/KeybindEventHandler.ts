@@ -4,10 +4,11 @@
  * @Year        : 2024                                                       *
  ****************************************************************************/
 
-import { UnlistenFn, listen } from "@tauri-apps/api/event";
+import { listen } from "@tauri-apps/api/event";
+import { invoke } from "@tauri-apps/api";
 import { KeybindManager } from "./KeybindManager";
-import { WebviewWindow } from "@tauri-apps/api/window";
 import { OverfloatEvent } from "./WindowEventHandler";
+import { WindowCondition } from "./Shortcut";
 
 // Types for different payload used in custom OverfloatEvents(custom Tauri events)
 export type AddShortcutEventPayload = {
@@ -15,6 +16,10 @@ export type AddShortcutEventPayload = {
     name: string;
     description: string;
     defaultKeybinds?: string[];
+    condition?: WindowCondition;
+    consume?: boolean;
+    priority?: number;
+    exclusive?: boolean;
 };
 
 export type RemoveShortcutEventPayload = {
@@ -23,6 +28,23 @@ export type RemoveShortcutEventPayload = {
 
 export type KeypressEventPayload = {
     key: string;
+    character: string;
+    physical_key: string;
+    modifiers: string[];
+    repeat: boolean;
+    scancode: number | null;
+    timestamp: number;
+};
+
+export type ShortcutsPausedEventPayload = {
+    paused: boolean;
+};
+
+export type KeybindCaptureResult = {
+    successful: boolean;
+    key: string;
+    character: string;
+    message: string;
 };
 
 /**
@@ -30,7 +52,6 @@ export type KeypressEventPayload = {
  */
 export class KeybindEventHandler {
     private static instance: KeybindEventHandler;
-    private keypressUnlisten: Promise<UnlistenFn>;
 
     public static getInstance(): KeybindEventHandler {
         if (!KeybindEventHandler.instance) {
@@ -41,14 +62,8 @@ export class KeybindEventHandler {
     }
 
     private constructor() {
-        // Listen for Key Press events from backend
-        this.keypressUnlisten = listen(
-            "Overfloat://GlobalKeypress",
-            (event: OverfloatEvent<KeypressEventPayload>) =>
-                this.handleKeypress(event)
-        );
-
         // Listen for shortcut related events from the API
+        // Shortcuts are triggered by the backend, which evaluates their conditions on the focused window
         listen(
             "Overfloat://AddShortcut",
             (event: OverfloatEvent<AddShortcutEventPayload>) =>
@@ -72,21 +87,6 @@ export class KeybindEventHandler {
         return moduleName;
     }
 
-    /**
-     * @brief Handles KeyPress events from the backend
-     * @param event OverfloatEvent containing the keypress event payload
-     */
-    private handleKeypress(event: OverfloatEvent<KeypressEventPayload>) {
-        KeybindManager.getInstance()
-            .getKeybinds()
-            .get(event.payload.key)
-            ?.forEach((shortcut) => {
-                WebviewWindow.getByLabel(shortcut.getWindowLabel())?.emit(
-                    "Overfloat://Shortcut/" + shortcut.getId()
-                );
-            });
-    }
-
     /**
      * @brief Adds a new shortcut to the KeybindManager after a request from the API
      * @param event OverfloatEvent containing the AddShortcutEventPayload
@@ -98,7 +98,11 @@ export class KeybindEventHandler {
             event.payload.id,
             event.payload.name,
             event.payload.description,
-            event.payload.defaultKeybinds
+            event.payload.defaultKeybinds,
+            event.payload.condition,
+            event.payload.consume,
+            event.payload.priority,
+            event.payload.exclusive
         );
     }
 
@@ -115,22 +119,38 @@ export class KeybindEventHandler {
     }
 
     /**
-     * @brief Stops listening for KeyPress events from the backend
-     * This prevents event propagation during keybind editing
+     * @brief Captures the next key combination in the backend
+     * Shortcuts are not triggered while capturing, which prevents event propagation during keybind editing
+     * @param timeoutMs Time to wait for the key combination in milliseconds, waits indefinitely if not set
+     * @returns Result of the capture containing the captured key combination
      */
-    public async stopListening() {
-        await this.keypressUnlisten.then((f) => f());
+    public captureKeybind(timeoutMs?: number): Promise<KeybindCaptureResult> {
+        return invoke<KeybindCaptureResult>("capture_keybind", {
+            timeoutMs: timeoutMs,
+        });
     }
 
     /**
-     * @brief Starts listening for KeyPress events from the backend
-     * This is used to enable the keybinds after editing
+     * @brief Cancels the ongoing key combination capture
      */
-    public startListening() {
-        this.keypressUnlisten = listen(
-            "Overfloat://GlobalKeypress",
-            (event: OverfloatEvent<KeypressEventPayload>) =>
-                this.handleKeypress(event)
-        );
+    public async cancelKeybindCapture() {
+        await invoke("cancel_keybind_capture");
+    }
+
+    /**
+     * @brief Pauses or resumes triggering of shortcuts in the backend
+     * While paused, only the resume key combination is handled
+     * @param paused True to pause, false to resume
+     */
+    public async setShortcutsPaused(paused: boolean) {
+        await invoke("set_shortcuts_paused", { paused: paused });
+    }
+
+    /**
+     * @brief Gets whether triggering of shortcuts is paused
+     * @returns True if shortcuts are paused
+     */
+    public getShortcutsPaused(): Promise<boolean> {
+        return invoke<boolean>("get_shortcuts_paused");
     }
 }
@@ -4,9 +4,32 @@
  * @Year        : 2024                                                       *
  ****************************************************************************/
 
+import { invoke } from "@tauri-apps/api";
 import { ModuleManager } from "./ModuleManager";
 import { OverfloatModule } from "./OverfloatModule";
-import { Shortcut } from "./Shortcut";
+import { Shortcut, WindowCondition } from "./Shortcut";
+
+// Type for the result of shortcut operations in the backend
+export type ShortcutResult = {
+    successful: boolean;
+    message: string;
+};
+
+// Types for conflicts between the key combinations of shortcuts
+// A prefix conflict means the first key combination is the first tap of the double-tap of the second one
+// A resolved conflict means only the exclusive shortcut with the higher priority is triggered
+export type ConflictingShortcut = {
+    id: string;
+    window_label: string;
+    keybind: string;
+};
+
+export type ShortcutConflict = {
+    first: ConflictingShortcut;
+    second: ConflictingShortcut;
+    prefix: boolean;
+    resolved: boolean;
+};
 
 /**
  * @brief Singleton class for managing keybinds
@@ -48,6 +71,42 @@ export class KeybindManager {
         }
     }
 
+    /**
+     * @brief Register a shortcut in the backend, which triggers it when its key combination is pressed
+     * Registering an already registered shortcut updates its keybinds
+     * @param shortcut Shortcut to register
+     * @returns Result of the registration
+     */
+    private registerShortcut(shortcut: Shortcut): Promise<ShortcutResult> {
+        return invoke<ShortcutResult>("register_shortcut", {
+            windowLabel: shortcut.getWindowLabel(),
+            id: shortcut.getId(),
+            keybinds: shortcut.getBoundKeys(),
+            condition: shortcut.getCondition(),
+            consume: shortcut.getConsume(),
+            priority: shortcut.getPriority(),
+            exclusive: shortcut.getExclusive(),
+        });
+    }
+
+    /**
+     * @brief Get the conflicts between the key combinations of the shortcuts registered in the backend
+     * Key combinations conflict if they are triggered by the same key presses,
+     * e.g. Ctrl+A and LCtrl+A, or LCtrl+A and DoubleTap(LCtrl+A)
+     * @returns Conflicts between pairs of shortcuts
+     */
+    public getShortcutConflicts(): Promise<ShortcutConflict[]> {
+        return invoke<ShortcutConflict[]>("get_shortcut_conflicts");
+    }
+
+    /**
+     * @brief Unregister a shortcut from the backend
+     * @param shortcut Shortcut to unregister
+     */
+    private unregisterShortcut(shortcut: Shortcut) {
+        invoke("unregister_shortcut", { id: shortcut.getId() });
+    }
+
     /**
      * @brief Add a new shortcut to the keybind manager
      * @param moduleName Name of the module
@@ -56,6 +115,10 @@ export class KeybindManager {
      * @param name Name of the shortcut
      * @param description Description of the shortcut
      * @param defaultKeybinds Default keybinds for the shortcut
+     * @param condition Condition on the focused window for the shortcut to be triggered
+     * @param consume Whether the bound keys are consumed instead of being passed to the focused application
+     * @param priority Priority of the shortcut, shortcuts bound to the same keys are triggered in order of priority
+     * @param exclusive Whether shortcuts with lower priority bound to the same keys are not triggered
      * @param skipNotify Skip notifying subscribers, used for batch operations
      * @returns
      */
@@ -66,6 +129,10 @@ export class KeybindManager {
         name: string,
         description: string,
         defaultKeybinds: string[] = [],
+        condition?: WindowCondition,
+        consume: boolean = false,
+        priority: number = 0,
+        exclusive: boolean = false,
         skipNotify: boolean = false
     ) {
         // Get the module and add the shortcut
@@ -80,7 +147,11 @@ export class KeybindManager {
             id,
             name,
             description,
-            defaultKeybinds
+            defaultKeybinds,
+            condition,
+            consume,
+            priority,
+            exclusive
         );
 
         if (shortcut == undefined) return;
@@ -98,6 +169,9 @@ export class KeybindManager {
             this.keybinds.get(keybind)?.add(shortcut);
         });
 
+        // Register the shortcut in the backend
+        this.registerShortcut(shortcut);
+
         if (!skipNotify) {
             this.notifySubscribers();
         }
@@ -130,6 +204,9 @@ export class KeybindManager {
             this.keybinds.get(key)?.delete(shortcut);
         });
 
+        // Unregister the shortcut from the backend
+        if (shortcut != undefined) this.unregisterShortcut(shortcut);
+
         // Remove the shortcut from the module
         module?.removeShortcut(windowLabel, id, true);
 
@@ -178,6 +255,9 @@ export class KeybindManager {
 
         this.keybinds.get(keybind)?.add(shortcut);
 
+        // Update the keybinds of the shortcut in the backend
+        this.registerShortcut(shortcut);
+
         if (!skipNotify) {
             this.notifySubscribers();
         }
@@ -214,6 +294,9 @@ export class KeybindManager {
         });
         shortcut.removeBoundKeys();
 
+        // Update the keybinds of the shortcut in the backend
+        this.registerShortcut(shortcut);
+
         if (!skipNotify) {
             this.notifySubscribers();
         }
@@ -254,6 +337,9 @@ export class KeybindManager {
         // Remove the shortcut from the keybinds map
         this.keybinds.get(removedKey)?.delete(shortcut);
 
+        // Update the keybinds of the shortcut in the backend
+        this.registerShortcut(shortcut);
+
         if (!skipNotify) {
             this.notifySubscribers();
         }
@@ -309,6 +395,9 @@ export class KeybindManager {
 
         this.keybinds.get(keybind)?.add(shortcut);
 
+        // Update the keybinds of the shortcut in the backend
+        this.registerShortcut(shortcut);
+
         if (!skipNotify) {
             this.notifySubscribers();
         }
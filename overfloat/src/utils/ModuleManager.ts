@@ -18,6 +18,38 @@ type SerializedConfig = {
     [key: string]: string;
 };
 
+// Type for a key remapping rule, the source key is replaced by the target key
+// If while_held is set, the rule only applies while the key or mouse button is held
+export type RemapRule = {
+    source: string;
+    target: string;
+    while_held?: string;
+};
+
+// Type for the key remapping of a profile
+export type ProfileRemaps = {
+    enabled: boolean;
+    rules: RemapRule[];
+};
+
+type SerializedRemaps = {
+    [profileName: string]: ProfileRemaps;
+};
+
+// Type for a hotstring, the typed abbreviation is replaced by the replacement text
+// If paste is set, the replacement is pasted from the clipboard instead of being typed
+export type Hotstring = {
+    abbreviation: string;
+    replacement: string;
+    paste?: boolean;
+};
+
+// Type for the hotstrings, they are shared by all profiles
+export type Hotstrings = {
+    enabled: boolean;
+    hotstrings: Hotstring[];
+};
+
 /**
  * @brief Singleton class for managing modules
  * Includes subscribing for react components to listen for changes
@@ -31,6 +63,9 @@ export class ModuleManager {
 
     private profiles: SerializedProfiles = {};
     private config: SerializedConfig = {};
+    private remaps: SerializedRemaps = {};
+    private remapErrorMessage: string = "";
+    private hotstrings: Hotstrings = { enabled: false, hotstrings: [] };
 
     private constructor() {
         this.subscribers = new Set<Function>();
@@ -43,8 +78,19 @@ export class ModuleManager {
     async initialLoad() {
         this.setupModules();
         await this.loadProfiles();
+        await this.loadRemaps();
+        await this.loadHotstrings();
         await this.loadConfig();
-        this.loadProfile(this.config["activeProfile"]);
+
+        // Reopen the modules that were open before the Overfloat window was reloaded, load the active profile otherwise
+        const reopenedModules: SerializedModule[] | null = await invoke<
+            SerializedModule[] | null
+        >("take_reopened_modules");
+        if (reopenedModules != null) {
+            this.reopenModules(reopenedModules);
+        } else {
+            this.loadProfile(this.config["activeProfile"]);
+        }
         this.notifySubscribers();
     }
 
@@ -60,6 +106,20 @@ export class ModuleManager {
             this.config = { activeProfile: "" };
             this.saveConfig();
         }
+
+        // Pass the keybind settings to the backend
+        await invoke("set_bind_by_character", {
+            enabled: this.getBindByCharacter(),
+        });
+        await invoke("set_double_tap_window", {
+            windowMs: this.getDoubleTapWindow(),
+        });
+        await invoke("set_resume_combo", {
+            combo: this.getResumeCombo(),
+        });
+        await invoke("set_panic_combo", {
+            combo: this.getPanicCombo(),
+        });
     }
 
     /**
@@ -93,6 +153,145 @@ export class ModuleManager {
         }
     }
 
+    /**
+     * @brief Save the key remapping of the profiles to the backend
+     */
+    async saveRemaps() {
+        await invoke("save_remaps", {
+            remapsJson: JSON.stringify(this.remaps, null, "\t"),
+        });
+    }
+
+    /**
+     * @brief Load the key remapping of the profiles from the backend
+     */
+    async loadRemaps() {
+        const remapsString: string = await invoke<string>("get_remaps");
+        try {
+            this.remaps = JSON.parse(remapsString);
+        } catch (_) {
+            this.remaps = {};
+        }
+    }
+
+    /**
+     * @brief Pass the key remapping rules of a profile to the backend
+     * @param profileName Name of the profile
+     * @returns Error message if the rules are invalid or remapping is not supported on this platform, empty string otherwise
+     */
+    async applyRemaps(profileName: string): Promise<string> {
+        const remaps: ProfileRemaps = this.getRemaps(profileName);
+        const result = await invoke<{ successful: boolean; message: string }>(
+            "set_remap_rules",
+            { rules: remaps.enabled ? remaps.rules : [] }
+        );
+        this.remapErrorMessage = result.message;
+        return result.message;
+    }
+
+    /**
+     * @brief Get the error message of the last application of key remapping rules
+     * @returns Error message, empty string if the rules were applied
+     */
+    public getRemapErrorMessage(): string {
+        return this.remapErrorMessage;
+    }
+
+    /**
+     * @brief Get the key remapping of a profile
+     * @param profileName Name of the profile
+     * @returns Key remapping of the profile, disabled with no rules if none is declared
+     */
+    public getRemaps(profileName: string): ProfileRemaps {
+        return this.remaps[profileName] ?? { enabled: false, rules: [] };
+    }
+
+    /**
+     * @brief Enable or disable the key remapping of a profile
+     * @param profileName Name of the profile
+     * @param enabled True to enable the key remapping
+     * @returns Error message if the rules are invalid, empty string otherwise
+     */
+    public async setRemapsEnabled(
+        profileName: string,
+        enabled: boolean
+    ): Promise<string> {
+        this.remaps[profileName] = {
+            ...this.getRemaps(profileName),
+            enabled: enabled,
+        };
+        await this.saveRemaps();
+
+        // Apply the change if the profile is active
+        let message: string = "";
+        if (profileName == this.getActiveProfile()) {
+            message = await this.applyRemaps(profileName);
+        }
+
+        this.notifySubscribers();
+        return message;
+    }
+
+    /**
+     * @brief Save the hotstrings to the backend
+     */
+    async saveHotstrings() {
+        await invoke("save_hotstrings", {
+            hotstringsJson: JSON.stringify(this.hotstrings, null, "\t"),
+        });
+    }
+
+    /**
+     * @brief Load the hotstrings from the backend and pass them to the hotstring engine
+     */
+    async loadHotstrings() {
+        const hotstringsString: string = await invoke<string>("get_hotstrings");
+        try {
+            this.hotstrings = JSON.parse(hotstringsString);
+        } catch {
+            this.hotstrings = { enabled: false, hotstrings: [] };
+        }
+        await this.applyHotstrings();
+    }
+
+    /**
+     * @brief Pass the hotstrings to the hotstring engine in the backend
+     * @returns Error message if a hotstring is invalid, empty string otherwise
+     */
+    async applyHotstrings(): Promise<string> {
+        const result = await invoke<{ successful: boolean; message: string }>(
+            "set_hotstrings",
+            {
+                hotstrings: this.hotstrings.enabled
+                    ? this.hotstrings.hotstrings
+                    : [],
+            }
+        );
+        return result.message;
+    }
+
+    /**
+     * @brief Get the hotstrings
+     * @returns Hotstrings shared by all profiles
+     */
+    public getHotstrings(): Hotstrings {
+        return this.hotstrings;
+    }
+
+    /**
+     * @brief Enable or disable the hotstrings
+     * @param enabled True to enable the hotstrings
+     * @returns Error message if a hotstring is invalid, empty string otherwise
+     */
+    public async setHotstringsEnabled(enabled: boolean): Promise<string> {
+        this.hotstrings = { ...this.hotstrings, enabled: enabled };
+        await this.saveHotstrings();
+
+        const message: string = await this.applyHotstrings();
+        this.notifySubscribers();
+        return message;
+    }
+
     /**
      * @brief Close all modules and notify the subscribers
      * @param skipNotify Skip notifying subscribers, used for batch operations
@@ -132,14 +331,70 @@ export class ModuleManager {
         // Close all modules before loading the profile
         await this.closeAllModules();
 
-        // Load the profile
-        const profile: SerializedModule[] = this.profiles[profileName];
+        // Set the active profile and save the config
+        this.config["activeProfile"] = profileName;
+        this.saveConfig();
+
+        // Apply the key remapping of the profile
+        await this.applyRemaps(profileName);
+
+        // Load the modules of the profile
+        await this.loadModules(this.profiles[profileName]);
+    }
+
+    /**
+     * @brief Reopen the modules that were open before the Overfloat window was reloaded
+     * @param reopenedModules Modules serialized by the backend, without shortcuts and transparency
+     * The shortcuts and transparency are taken from the active profile
+     */
+    private async reopenModules(reopenedModules: SerializedModule[]) {
+        const profile: SerializedModule[] =
+            this.profiles[this.config["activeProfile"]] ?? [];
+
+        const modules: SerializedModule[] = reopenedModules.map((reopened) => {
+            const saved: SerializedModule | undefined = profile.find(
+                (serializedModule) =>
+                    serializedModule.moduleName == reopened.moduleName
+            );
+            if (saved == undefined) return reopened;
+
+            return {
+                ...reopened,
+                mainWindow: {
+                    ...reopened.mainWindow,
+                    isTransparent: saved.mainWindow.isTransparent,
+                    shortcuts: saved.mainWindow.shortcuts,
+                },
+                subwindows: reopened.subwindows.map((subwindow) => {
+                    const savedSubwindow = saved.subwindows.find(
+                        (serializedSubwindow) =>
+                            serializedSubwindow.subwindowName ==
+                            subwindow.subwindowName
+                    );
+                    if (savedSubwindow == undefined) return subwindow;
+
+                    return {
+                        ...subwindow,
+                        isTransparent: savedSubwindow.isTransparent,
+                        shortcuts: savedSubwindow.shortcuts,
+                    };
+                }),
+            };
+        });
+
+        await this.loadModules(modules);
+    }
 
+    /**
+     * @brief Start serialized modules and show their visible windows
+     * @param serializedModules Modules serialized like in profiles
+     */
+    private async loadModules(serializedModules: SerializedModule[]) {
         // Prepare an array for promises to wait for visible windows to be created
         const promises: Promise<Window>[] = [];
 
-        // Load the modules from the profile
-        profile.forEach((serializedModule) => {
+        // Load the serialized modules
+        serializedModules.forEach((serializedModule) => {
             // If the module is not in the list of all modules, skip it
             if (!this.allModules.includes(serializedModule.moduleName)) return;
 
@@ -169,10 +424,6 @@ export class ModuleManager {
             promises.push(...module.loadSubwindows(serializedModule));
         });
 
-        // Set the active profile and save the config
-        this.config["activeProfile"] = profileName;
-        this.saveConfig();
-
         // Gather window created promises for all windows
         const webviewCreatedPromises: Promise<void>[] = [];
         this.activeModules.forEach((module) => {
@@ -354,6 +605,15 @@ export class ModuleManager {
         if (!(profileName in this.profiles)) return;
         delete this.profiles[profileName];
 
+        // Delete the key remapping of the profile
+        if (profileName in this.remaps) {
+            delete this.remaps[profileName];
+            await this.saveRemaps();
+        }
+        if (profileName == this.getActiveProfile()) {
+            await invoke("set_remap_rules", { rules: [] });
+        }
+
         // Update the config if the profile is active
         if (profileName == this.getActiveProfile()) {
             this.setActiveProfile("");
@@ -402,6 +662,99 @@ export class ModuleManager {
         this.config["activeProfile"] = profileName;
     }
 
+    /**
+     * @brief Get whether shortcuts are bound by the character produced by the keyboard layout
+     * @returns True if shortcuts are bound by character, false if by physical key position
+     */
+    public getBindByCharacter(): boolean {
+        return this.config["bindByCharacter"] == "true";
+    }
+
+    /**
+     * @brief Set whether shortcuts are bound by the character produced by the keyboard layout
+     * @param enabled True to bind by character, false to bind by physical key position
+     */
+    public async setBindByCharacter(enabled: boolean) {
+        this.config["bindByCharacter"] = String(enabled);
+        await invoke("set_bind_by_character", { enabled: enabled });
+        this.saveConfig();
+        this.notifySubscribers();
+    }
+
+    /**
+     * @brief Get the time window for detecting double-tap shortcuts
+     * @returns Maximum time between the two taps in milliseconds
+     */
+    public getDoubleTapWindow(): number {
+        const windowMs: number = parseInt(this.config["doubleTapWindow"]);
+        return isNaN(windowMs) || windowMs < 0 ? 300 : windowMs;
+    }
+
+    /**
+     * @brief Set the time window for detecting double-tap shortcuts
+     * @param windowMs Maximum time between the two taps in milliseconds
+     */
+    public async setDoubleTapWindow(windowMs: number) {
+        this.config["doubleTapWindow"] = String(windowMs);
+        await invoke("set_double_tap_window", { windowMs: windowMs });
+        this.saveConfig();
+        this.notifySubscribers();
+    }
+
+    /**
+     * @brief Get the key combination resuming paused shortcuts
+     * @returns Key combination string
+     */
+    public getResumeCombo(): string {
+        const combo: string | undefined = this.config["resumeCombo"];
+        return combo ? combo : "LCtrl+LShft+Pause";
+    }
+
+    /**
+     * @brief Set the key combination resuming paused shortcuts
+     * @param combo Key combination string
+     * @returns Error message if the key combination is invalid, empty string otherwise
+     */
+    public async setResumeCombo(combo: string): Promise<string> {
+        const result = await invoke<{ successful: boolean; message: string }>(
+            "set_resume_combo",
+            { combo: combo }
+        );
+        if (!result.successful) return result.message;
+
+        this.config["resumeCombo"] = combo;
+        this.saveConfig();
+        this.notifySubscribers();
+        return "";
+    }
+
+    /**
+     * @brief Get the key combination stopping all input simulations
+     * @returns Key combination string
+     */
+    public getPanicCombo(): string {
+        const combo: string | undefined = this.config["panicCombo"];
+        return combo ? combo : "LCtrl+LShft+End";
+    }
+
+    /**
+     * @brief Set the key combination stopping all input simulations
+     * @param combo Key combination string
+     * @returns Error message if the key combination is invalid, empty string otherwise
+     */
+    public async setPanicCombo(combo: string): Promise<string> {
+        const result = await invoke<{ successful: boolean; message: string }>(
+            "set_panic_combo",
+            { combo: combo }
+        );
+        if (!result.successful) return result.message;
+
+        this.config["panicCombo"] = combo;
+        this.saveConfig();
+        this.notifySubscribers();
+        return "";
+    }
+
     /**
      * @brief Dectivate a module
      * @param moduleName Name of the module to deactivate
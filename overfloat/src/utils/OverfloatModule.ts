@@ -10,7 +10,7 @@ import {
     WebviewWindow,
     appWindow,
 } from "@tauri-apps/api/window";
-import { SerializedShortcut, Shortcut } from "./Shortcut";
+import { SerializedShortcut, Shortcut, WindowCondition } from "./Shortcut";
 import { KeybindManager } from "./KeybindManager";
 import { ModuleManager } from "./ModuleManager";
 
@@ -449,6 +449,10 @@ export class OverfloatModule {
      * @param name Name of the shortcut
      * @param description Description of the shortcut
      * @param boundKeys Keys bound to the shortcut
+     * @param condition Condition on the focused window for the shortcut to be triggered
+     * @param consume Whether the bound keys are consumed instead of being passed to the focused application
+     * @param priority Priority of the shortcut, shortcuts bound to the same keys are triggered in order of priority
+     * @param exclusive Whether shortcuts with lower priority bound to the same keys are not triggered
      * @param skipNotify Skip notifying subscribers, used for batch operations
      * @returns The added shortcut
      */
@@ -458,6 +462,10 @@ export class OverfloatModule {
         name: string,
         description: string,
         boundKeys: string[] = [],
+        condition?: WindowCondition,
+        consume: boolean = false,
+        priority: number = 0,
+        exclusive: boolean = false,
         skipNotify: boolean = false
     ): Shortcut | undefined {
         // Get the window
@@ -479,7 +487,11 @@ export class OverfloatModule {
             windowLabel,
             id,
             description,
-            boundKeys
+            boundKeys,
+            condition,
+            consume,
+            priority,
+            exclusive
         );
 
         // Add the shortcut to the window
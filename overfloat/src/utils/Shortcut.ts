@@ -4,6 +4,13 @@
  * @Year        : 2024                                                       *
  ****************************************************************************/
 
+// Type for a condition on the focused window that has to be met for a shortcut to be triggered
+export type WindowCondition = {
+    wm_class?: string;
+    title?: string;
+    process_name?: string;
+};
+
 // Types for serialized shortcuts
 export type SerializedShortcut = {
     id: string;
@@ -19,6 +26,10 @@ export class Shortcut {
     private id: string;
     private description: string;
     private boundKeys: string[];
+    private condition?: WindowCondition;
+    private consume: boolean;
+    private priority: number;
+    private exclusive: boolean;
 
     /**
      * @brief Constructor for the Shortcut class
@@ -27,19 +38,31 @@ export class Shortcut {
      * @param id ID of the shortcut
      * @param description Description of the shortcut
      * @param boundKeys Keys bound to the shortcut
+     * @param condition Condition on the focused window for the shortcut to be triggered
+     * @param consume Whether the bound keys are consumed instead of being passed to the focused application
+     * @param priority Priority of the shortcut, shortcuts bound to the same keys are triggered in order of priority
+     * @param exclusive Whether shortcuts with lower priority bound to the same keys are not triggered
      */
     public constructor(
         name: string,
         windowLabel: string,
         id: string,
         description: string,
-        boundKeys: string[] = []
+        boundKeys: string[] = [],
+        condition?: WindowCondition,
+        consume: boolean = false,
+        priority: number = 0,
+        exclusive: boolean = false
     ) {
         this.name = name;
         this.windowLabel = windowLabel;
         this.id = id;
         this.description = description;
         this.boundKeys = boundKeys;
+        this.condition = condition;
+        this.consume = consume;
+        this.priority = priority;
+        this.exclusive = exclusive;
     }
 
     /**
@@ -126,6 +149,38 @@ export class Shortcut {
         return this.boundKeys;
     }
 
+    /**
+     * @brief Gets the condition on the focused window for the shortcut to be triggered
+     * @returns Condition of the shortcut, undefined if the shortcut is triggered in any window
+     */
+    public getCondition(): WindowCondition | undefined {
+        return this.condition;
+    }
+
+    /**
+     * @brief Gets whether the bound keys are consumed instead of being passed to the focused application
+     * @returns True if the bound keys are consumed
+     */
+    public getConsume(): boolean {
+        return this.consume;
+    }
+
+    /**
+     * @brief Gets the priority of the shortcut
+     * @returns Priority of the shortcut
+     */
+    public getPriority(): number {
+        return this.priority;
+    }
+
+    /**
+     * @brief Gets whether shortcuts with lower priority bound to the same keys are not triggered
+     * @returns True if the shortcut is exclusive
+     */
+    public getExclusive(): boolean {
+        return this.exclusive;
+    }
+
     /**
      * @brief Removes all bound keys from the shortcut
      */
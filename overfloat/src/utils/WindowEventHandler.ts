@@ -83,6 +83,28 @@ export class WindowEventHandler {
             (event: OverfloatEvent<SubwindowModificationEventPayload>) =>
                 this.subwindowModification(event)
         );
+
+        // Listen for app-level actions performed by the backend
+        listen("Overfloat://ModulesHidden", () => this.modulesHidden());
+    }
+
+    /**
+     * @brief Updates the state of module windows hidden by the backend
+     */
+    private async modulesHidden() {
+        const promises: Promise<void>[] = [];
+
+        this.moduleManager.getActiveModules().forEach((module) => {
+            const modulePromises: Promise<void>[] = [module.hideMainWindow(true)];
+            module.getSubwindows().forEach((_, label) => {
+                modulePromises.push(module.hideSubwindow(label, true));
+            });
+            promises.push(
+                Promise.all(modulePromises).then(() => module.notifySubscribers())
+            );
+        });
+
+        await Promise.all(promises);
     }
 
     /**